@@ -1,18 +1,600 @@
-#[derive(Debug)]
-pub enum Expr {
-    Int(i64),
+use crate::tokens::{FloatLiteral, IntLiteral, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    /// `+%`, `-%`, `*%`: wrapping.
+    AddWrap,
+    SubWrap,
+    MulWrap,
+    /// `+|`, `-|`, `*|`: saturating.
+    AddSat,
+    SubSat,
+    MulSat,
+    /// `+?`, `-?`, `*?`: checked, yielding the result and an overflow flag.
+    AddChecked,
+    SubChecked,
+    MulChecked,
+    /// `+%?`, `-%?`, `*%?`
+    AddWrapChecked,
+    SubWrapChecked,
+    MulWrapChecked,
+    /// `+|?`, `-|?`, `*|?`
+    AddSatChecked,
+    SubSatChecked,
+    MulSatChecked,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    And,
+    Or,
+    Xor,
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+}
+
+impl BinaryOp {
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Eq
+                | BinaryOp::NotEq
+                | BinaryOp::Lt
+                | BinaryOp::Gt
+                | BinaryOp::LtEq
+                | BinaryOp::GtEq
+        )
+    }
+
+    /// `&&`, `||` and `^^`, which take and produce `bool`.
+    pub fn is_logical(self) -> bool {
+        matches!(self, BinaryOp::And | BinaryOp::Or | BinaryOp::Xor)
+    }
+
+    /// For a checked operator (`+?`, `+%?`, `+|?`, ...), the operator that
+    /// computes its value; the checked form also yields an overflow flag.
+    pub fn unchecked(self) -> Option<BinaryOp> {
+        Some(match self {
+            BinaryOp::AddChecked => BinaryOp::Add,
+            BinaryOp::SubChecked => BinaryOp::Sub,
+            BinaryOp::MulChecked => BinaryOp::Mul,
+            BinaryOp::AddWrapChecked => BinaryOp::AddWrap,
+            BinaryOp::SubWrapChecked => BinaryOp::SubWrap,
+            BinaryOp::MulWrapChecked => BinaryOp::MulWrap,
+            BinaryOp::AddSatChecked => BinaryOp::AddSat,
+            BinaryOp::SubSatChecked => BinaryOp::SubSat,
+            BinaryOp::MulSatChecked => BinaryOp::MulSat,
+            _ => return None,
+        })
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Pow => "**",
+            BinaryOp::AddWrap => "+%",
+            BinaryOp::SubWrap => "-%",
+            BinaryOp::MulWrap => "*%",
+            BinaryOp::AddSat => "+|",
+            BinaryOp::SubSat => "-|",
+            BinaryOp::MulSat => "*|",
+            BinaryOp::AddChecked => "+?",
+            BinaryOp::SubChecked => "-?",
+            BinaryOp::MulChecked => "*?",
+            BinaryOp::AddWrapChecked => "+%?",
+            BinaryOp::SubWrapChecked => "-%?",
+            BinaryOp::MulWrapChecked => "*%?",
+            BinaryOp::AddSatChecked => "+|?",
+            BinaryOp::SubSatChecked => "-|?",
+            BinaryOp::MulSatChecked => "*|?",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::Xor => "^^",
+            BinaryOp::Eq => "==",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Gt => ">",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::GtEq => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `-x`
+    Neg,
+    /// `!x`, logical NOT.
+    Not,
+    /// `~x`, bitwise NOT.
+    BitNot,
+}
+
+impl UnaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+            UnaryOp::BitNot => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    Int(IntLiteral),
+    Float(FloatLiteral),
+    Str(String),
+    ByteStr(Vec<u8>),
+    Char(char),
     Ident(String),
-    Return(Box<Expr>),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Arg>,
+        /// Index of the called overload among the functions of that name, in
+        /// program order. Set by semantic analysis; `None` until then and
+        /// for enum variants.
+        overload: Option<usize>,
+    },
+    /// A loop used as a value: `while (c) { .. break 5; .. } break 0`.
+    Loop(Box<Loop>),
+    /// `if (cond) a else b`, or the postfix form `a if (cond) else b`.
+    If {
+        cond: Box<Expr>,
+        then_expr: Box<Expr>,
+        else_expr: Box<Expr>,
+    },
+    /// `Point { x = 1, y = 2 }`, giving every field of the struct once, in
+    /// any order.
+    StructLit {
+        name: String,
+        fields: Vec<FieldInit>,
+    },
+    /// `base.name`
+    Field {
+        base: Box<Expr>,
+        name: String,
+        name_span: Span,
+    },
+    /// A `match` used as a value; every arm's body is an expression.
+    Match(Box<Match>),
+}
+
+/// One field of a struct literal, `<name> = <value>`.
+#[derive(Debug, Clone)]
+pub struct FieldInit {
+    pub name: String,
+    pub name_span: Span,
+    pub value: Expr,
+}
+
+/// Declaration modifiers, written before the type: `const u32 x = 1;`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    /// The variable cannot be assigned after its declaration.
+    Const,
+}
+
+impl Modifier {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "const" => Some(Modifier::Const),
+            _ => None,
+        }
+    }
+}
+
+/// `{ <stmt>* }`
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    /// Span of the braces and everything between them.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stmt {
+    pub attrs: Vec<Attribute>,
+    pub kind: StmtKind,
+    /// Span of the statement, without its attributes.
+    pub span: Span,
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Span) -> Self {
+        Self {
+            attrs: Vec::new(),
+            kind,
+            span,
+        }
+    }
+}
+
+/// One name bound by a destructuring declaration: `<type> <name>`.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub ty: String,
+    pub ty_span: Span,
+    pub name: String,
+    pub name_span: Span,
+}
+
+/// `<modifiers> <type> <name>, <type> <name> {, ..} = <init>;`, which binds
+/// each value of a call with several return values, or of a checked operator.
+#[derive(Debug, Clone)]
+pub struct DestructureDecl {
+    pub modifiers: Vec<Modifier>,
+    pub bindings: Vec<Binding>,
+    pub init: Expr,
+}
+
+/// `<modifiers> <type> <name> [= <init>];`
+#[derive(Debug, Clone)]
+pub struct VarDecl {
+    pub modifiers: Vec<Modifier>,
+    pub ty: String,
+    pub ty_span: Span,
+    pub name: String,
+    pub name_span: Span,
+    /// `None` zero-initializes the variable.
+    pub init: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
+    VarDecl(VarDecl),
+    Destructure(DestructureDecl),
+    /// `target = value;`, or a compound assignment such as `target += value;`
+    /// when `op` is set.
+    Assign {
+        target: Expr,
+        op: Option<BinaryOp>,
+        value: Expr,
+    },
+    /// `target++;` or `++target;`
+    Increment(Expr),
+    /// `target--;` or `--target;`
+    Decrement(Expr),
+    /// An expression evaluated for its side effects; the value is discarded.
+    Expr(Expr),
+    /// `return <value> {, <value>};`, one value per return value, or a single
+    /// call or checked operator yielding all of them.
+    Return(Vec<Expr>),
+    Block(Block),
+    Loop(Loop),
+    /// `break [label] [value];`
+    Break {
+        label: Option<Label>,
+        value: Option<Expr>,
+    },
+    /// `continue [label];`
+    Continue {
+        label: Option<Label>,
+    },
+    /// A statement that failed to parse; the parser reported why.
+    Error,
+    /// `if (cond) { .. }`, optionally followed by `else` and either a block
+    /// or another `if` statement.
+    If {
+        cond: Expr,
+        then_block: Block,
+        else_branch: Option<Box<Stmt>>,
+    },
+    /// A `match` run for its effects; arm bodies may be blocks.
+    Match(Match),
+}
+
+/// `match (<scrutinee>) { <pattern> => <body>, .. }`. Arms are tried in
+/// order and the first whose pattern matches runs.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub scrutinee: Expr,
+    pub arms: Vec<MatchArm>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ArmBody,
+}
+
+#[derive(Debug, Clone)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Block),
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub enum PatternKind {
+    /// `_`, which matches anything.
+    Wildcard,
+    /// A name, which matches anything and binds it to the matched value.
+    Binding(String),
+    /// An integer literal, optionally negated: `3`, `-1`.
+    Int {
+        negative: bool,
+        lit: IntLiteral,
+    },
+    Char(char),
+    /// `Enum.Variant` or `Enum.Variant(<pattern> {, <pattern>})`, with one
+    /// pattern per field of the variant.
+    Variant {
+        enum_name: String,
+        variant: String,
+        fields: Vec<Pattern>,
+    },
+}
+
+/// A loop label, declared as `name:` before the loop.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Loop {
+    pub label: Option<Label>,
+    pub kind: LoopKind,
+    pub body: Block,
+    /// `else { .. }`, run instead of the body when the loop's condition is
+    /// false on the first check.
+    pub else_block: Option<Block>,
+    /// The trailing `break <value>` of a loop used as a value: its result
+    /// when it ends without a `break` carrying one.
+    pub default: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LoopKind {
+    /// `while (cond) { .. }`
+    While(Expr),
+    /// `for (init; cond; update) { .. }`; each part may be left out, and a
+    /// missing condition loops until a `break`.
+    For {
+        init: Option<Box<Stmt>>,
+        cond: Option<Expr>,
+        update: Option<Box<Stmt>>,
+    },
+    /// `do { .. } while (cond);`, which runs the body at least once.
+    DoWhile(Expr),
+    /// `loop { .. }`, which only ends through `break` or `return`.
+    Infinite,
+}
+
+/// A call argument, `<value>` or `<name> = <value>`.
+#[derive(Debug, Clone)]
+pub struct Arg {
+    pub name: Option<String>,
+    pub value: Expr,
+    /// Span of the whole argument, name through value.
+    pub span: Span,
+}
+
+/// A function parameter, written `<type> <name> [= <default>]`.
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub ty: String,
+    pub ty_span: Span,
+    pub name: String,
+    /// Span of the whole parameter, type through name.
+    pub span: Span,
+    /// Evaluated at each call site that leaves the argument out.
+    pub default: Option<Expr>,
+}
+
+/// A return value in a signature, written `<type> [<name>]`. Named return
+/// values are locals of the body, returned when it falls off its end.
+#[derive(Debug, Clone)]
+pub struct ReturnValue {
+    pub ty: String,
+    pub ty_span: Span,
+    pub name: Option<String>,
+    /// Span of the whole return value, type through name.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
 pub struct Function {
+    pub attrs: Vec<Attribute>,
+    /// `pub fn`: callable from modules that import this one.
+    pub public: bool,
+    /// `entry fn`; see [`Function::is_entry`].
+    pub entry: bool,
+    pub name: String,
+    pub params: Vec<Param>,
+    /// At least one, except for an entry function, which may return
+    /// nothing. The parser ensures either all or none are named.
+    pub returns: Vec<ReturnValue>,
+    pub body: Block,
+    /// Span of the function name, used to point at the whole item.
+    pub span: Span,
+}
+
+impl Function {
+    /// Whether the program starts here: the function is written `entry fn`
+    /// or has the `#[entry]` attribute.
+    pub fn is_entry(&self) -> bool {
+        self.entry || self.attrs.iter().any(|attr| attr.name == "entry")
+    }
+}
+
+/// `#[<path> <args>]`, written before an item, a struct field, an enum
+/// variant or a statement. See [`crate::attributes`] for the ones the
+/// compiler knows.
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    /// The path naming the attribute, e.g. `packed` or `lint.allow`.
+    pub name: String,
+    pub name_span: Span,
+    pub args: AttrArgs,
+    /// Span of the whole attribute, `#` through `]`.
+    pub span: Span,
+}
+
+/// What follows the path of an attribute, or of a path nested in its
+/// arguments.
+#[derive(Debug, Clone)]
+pub enum AttrArgs {
+    /// Nothing: `#[packed]`.
+    None,
+    /// `= <literal>`: `#[doc = "text"]`.
+    Value(AttrLit),
+    /// `(<item> {, <item>})`: `#[cfg(any(unix, target.os = "windows"))]`.
+    List(Vec<MetaItem>),
+}
+
+/// An entry in the argument list of an attribute.
+#[derive(Debug, Clone)]
+pub enum MetaItem {
+    /// A path with arguments of its own, written like an attribute without
+    /// the `#[` and `]`.
+    Meta {
+        name: String,
+        args: AttrArgs,
+        /// Span of the path and its arguments.
+        span: Span,
+    },
+    Lit(AttrLit),
+}
+
+/// A literal in an attribute: an integer or a string.
+#[derive(Debug, Clone)]
+pub struct AttrLit {
+    pub kind: AttrLitKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum AttrLitKind {
+    Int(IntLiteral),
+    Str(String),
+}
+
+/// A struct field, written `<type> <name>;`.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub attrs: Vec<Attribute>,
+    pub ty: String,
+    pub ty_span: Span,
+    pub name: String,
+    /// Span of the whole field, type through name.
+    pub span: Span,
+}
+
+/// `struct <name> { <field>* }`. Fields are laid out in declaration order
+/// with C alignment and padding, or without padding under `#[packed]`.
+#[derive(Debug, Clone)]
+pub struct StructDecl {
+    pub attrs: Vec<Attribute>,
+    /// `pub struct`: usable from modules that import this one.
+    pub public: bool,
     pub name: String,
-    pub return_type: String,
-    pub body: Vec<Expr>,
+    pub fields: Vec<Field>,
+    /// Span of the struct name, used to point at the whole item.
+    pub span: Span,
+}
+
+/// An enum variant, `<name>` or `<name>(<type> {, <type>})`.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub attrs: Vec<Attribute>,
+    pub name: String,
+    /// The type of each field of the variant's payload, with its span.
+    pub fields: Vec<(String, Span)>,
+    /// Span of the variant name.
+    pub span: Span,
+}
+
+/// `enum <name> { <variant> {, <variant>} }`, a tagged union: a value is
+/// one of the variants, holding that variant's fields.
+#[derive(Debug, Clone)]
+pub struct EnumDecl {
+    pub attrs: Vec<Attribute>,
+    /// `pub enum`: usable from modules that import this one.
+    pub public: bool,
+    pub name: String,
+    pub variants: Vec<Variant>,
+    /// Span of the enum name, used to point at the whole item.
+    pub span: Span,
+}
+
+/// `import <name> {. <name>} [as <alias>];`, which makes the public items
+/// of the module at that path available as `<alias>.<item>`.
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+    /// Span of the path, `a.b` in `import a.b as c;`.
+    pub path_span: Span,
+}
+
+impl Import {
+    /// The name the module is known by: its alias, or else the last name
+    /// of its path.
+    pub fn binding(&self) -> &str {
+        self.alias
+            .as_deref()
+            .unwrap_or_else(|| self.path.last().expect("import paths are not empty"))
+    }
+
+    /// The path as written, `a.b`.
+    pub fn dotted_path(&self) -> String {
+        self.path.join(".")
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Program {
+    pub imports: Vec<Import>,
+    pub structs: Vec<StructDecl>,
+    pub enums: Vec<EnumDecl>,
     pub functions: Vec<Function>,
 }
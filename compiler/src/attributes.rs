@@ -0,0 +1,323 @@
+//! The attributes the compiler knows, and checking a program's attributes
+//! against them.
+//!
+//! Each known attribute is written in one form and applies to some kinds of
+//! targets. An attribute that is unknown, written in another form, put on
+//! something it doesn't apply to or given twice is an error. For an unknown
+//! attribute, the error suggests a known one with a similar name.
+
+use std::collections::HashSet;
+
+use crate::ast::{
+    ArmBody, AttrArgs, Attribute, Block, Expr, ExprKind, Loop, LoopKind, Match, Program, Stmt,
+    StmtKind,
+};
+use crate::error::AttributeError;
+
+/// What an attribute is written before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrTarget {
+    Function,
+    Struct,
+    Enum,
+    Field,
+    Variant,
+    Statement,
+}
+
+impl AttrTarget {
+    /// `a function`, for diagnostics.
+    fn describe(self) -> &'static str {
+        match self {
+            AttrTarget::Function => "a function",
+            AttrTarget::Struct => "a struct",
+            AttrTarget::Enum => "an enum",
+            AttrTarget::Field => "a struct field",
+            AttrTarget::Variant => "an enum variant",
+            AttrTarget::Statement => "a statement",
+        }
+    }
+
+    fn plural(self) -> &'static str {
+        match self {
+            AttrTarget::Function => "functions",
+            AttrTarget::Struct => "structs",
+            AttrTarget::Enum => "enums",
+            AttrTarget::Field => "struct fields",
+            AttrTarget::Variant => "enum variants",
+            AttrTarget::Statement => "statements",
+        }
+    }
+}
+
+/// How an attribute takes arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrForm {
+    /// `#[name]`
+    Word,
+    /// `#[name = <literal>]`
+    Value,
+    /// `#[name(<item>, ..)]`
+    List,
+}
+
+#[derive(Debug)]
+pub struct KnownAttribute {
+    pub name: &'static str,
+    pub form: AttrForm,
+    pub targets: &'static [AttrTarget],
+    /// How the attribute is written, shown when it is written otherwise.
+    pub template: &'static str,
+}
+
+/// Every attribute the compiler knows.
+pub const KNOWN_ATTRIBUTES: &[KnownAttribute] = &[
+    KnownAttribute {
+        name: "entry",
+        form: AttrForm::Word,
+        targets: &[AttrTarget::Function],
+        template: "#[entry]",
+    },
+    KnownAttribute {
+        name: "packed",
+        form: AttrForm::Word,
+        targets: &[AttrTarget::Struct],
+        template: "#[packed]",
+    },
+];
+
+pub fn lookup(name: &str) -> Option<&'static KnownAttribute> {
+    KNOWN_ATTRIBUTES.iter().find(|known| known.name == name)
+}
+
+/// Checks every attribute in `program`, in source order within each item,
+/// and returns an error for each one that is wrong.
+pub fn check_attributes(program: &Program) -> Vec<AttributeError> {
+    let mut checker = Checker::default();
+    for decl in &program.structs {
+        checker.check(&decl.attrs, AttrTarget::Struct);
+        for field in &decl.fields {
+            checker.check(&field.attrs, AttrTarget::Field);
+        }
+    }
+    for decl in &program.enums {
+        checker.check(&decl.attrs, AttrTarget::Enum);
+        for variant in &decl.variants {
+            checker.check(&variant.attrs, AttrTarget::Variant);
+        }
+    }
+    for f in &program.functions {
+        checker.check(&f.attrs, AttrTarget::Function);
+        checker.check_block(&f.body);
+    }
+    checker.errors
+}
+
+#[derive(Default)]
+struct Checker {
+    errors: Vec<AttributeError>,
+}
+
+impl Checker {
+    /// Checks the attributes written before one target.
+    fn check(&mut self, attrs: &[Attribute], target: AttrTarget) {
+        let mut seen = HashSet::new();
+        for attr in attrs {
+            if let Err(e) = check_attribute(attr, target) {
+                self.errors.push(e);
+            } else if !seen.insert(attr.name.as_str()) {
+                self.errors.push(AttributeError::new(
+                    format!("attribute '{}' is given more than once", attr.name),
+                    attr.span,
+                ));
+            }
+        }
+    }
+
+    fn check_block(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            self.check_stmt(stmt);
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        self.check(&stmt.attrs, AttrTarget::Statement);
+        match &stmt.kind {
+            StmtKind::VarDecl(decl) => {
+                if let Some(init) = &decl.init {
+                    self.check_expr(init);
+                }
+            }
+            StmtKind::Destructure(decl) => self.check_expr(&decl.init),
+            StmtKind::Assign { target, value, .. } => {
+                self.check_expr(target);
+                self.check_expr(value);
+            }
+            StmtKind::Increment(expr) | StmtKind::Decrement(expr) | StmtKind::Expr(expr) => {
+                self.check_expr(expr)
+            }
+            StmtKind::Return(values) => values.iter().for_each(|v| self.check_expr(v)),
+            StmtKind::Block(block) => self.check_block(block),
+            StmtKind::Loop(lp) => self.check_loop(lp),
+            StmtKind::Break { value, .. } => {
+                if let Some(value) = value {
+                    self.check_expr(value);
+                }
+            }
+            StmtKind::Continue { .. } | StmtKind::Error => {}
+            StmtKind::If {
+                cond,
+                then_block,
+                else_branch,
+            } => {
+                self.check_expr(cond);
+                self.check_block(then_block);
+                if let Some(else_branch) = else_branch {
+                    self.check_stmt(else_branch);
+                }
+            }
+            StmtKind::Match(m) => self.check_match(m),
+        }
+    }
+
+    fn check_loop(&mut self, lp: &Loop) {
+        match &lp.kind {
+            LoopKind::While(cond) | LoopKind::DoWhile(cond) => self.check_expr(cond),
+            LoopKind::For { init, cond, update } => {
+                if let Some(init) = init {
+                    self.check_stmt(init);
+                }
+                if let Some(cond) = cond {
+                    self.check_expr(cond);
+                }
+                if let Some(update) = update {
+                    self.check_stmt(update);
+                }
+            }
+            LoopKind::Infinite => {}
+        }
+        self.check_block(&lp.body);
+        if let Some(else_block) = &lp.else_block {
+            self.check_block(else_block);
+        }
+        if let Some(default) = &lp.default {
+            self.check_expr(default);
+        }
+    }
+
+    fn check_match(&mut self, m: &Match) {
+        self.check_expr(&m.scrutinee);
+        for arm in &m.arms {
+            match &arm.body {
+                ArmBody::Expr(e) => self.check_expr(e),
+                ArmBody::Block(block) => self.check_block(block),
+            }
+        }
+    }
+
+    /// Expressions have no attributes, but blocks inside them do.
+    fn check_expr(&mut self, e: &Expr) {
+        match &e.kind {
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::Str(_)
+            | ExprKind::ByteStr(_)
+            | ExprKind::Char(_)
+            | ExprKind::Ident(_) => {}
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            ExprKind::Unary { operand, .. } => self.check_expr(operand),
+            ExprKind::Call { callee, args, .. } => {
+                self.check_expr(callee);
+                args.iter().for_each(|arg| self.check_expr(&arg.value));
+            }
+            ExprKind::Loop(lp) => self.check_loop(lp),
+            ExprKind::If {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                self.check_expr(cond);
+                self.check_expr(then_expr);
+                self.check_expr(else_expr);
+            }
+            ExprKind::StructLit { fields, .. } => {
+                fields
+                    .iter()
+                    .for_each(|field| self.check_expr(&field.value));
+            }
+            ExprKind::Field { base, .. } => self.check_expr(base),
+            ExprKind::Match(m) => self.check_match(m),
+        }
+    }
+}
+
+fn check_attribute(attr: &Attribute, target: AttrTarget) -> Result<(), AttributeError> {
+    let Some(known) = lookup(&attr.name) else {
+        let message = match suggest(&attr.name) {
+            Some(similar) => format!(
+                "unknown attribute '{}'; did you mean '{similar}'?",
+                attr.name
+            ),
+            None => format!("unknown attribute '{}'", attr.name),
+        };
+        return Err(AttributeError::new(message, attr.name_span));
+    };
+    if !known.targets.contains(&target) {
+        let targets: Vec<&str> = known.targets.iter().map(|t| t.plural()).collect();
+        return Err(AttributeError::new(
+            format!(
+                "attribute '{}' cannot be used on {}; it applies to {}",
+                attr.name,
+                target.describe(),
+                targets.join(" and ")
+            ),
+            attr.span,
+        ));
+    }
+    let form = match attr.args {
+        AttrArgs::None => AttrForm::Word,
+        AttrArgs::Value(_) => AttrForm::Value,
+        AttrArgs::List(_) => AttrForm::List,
+    };
+    if form != known.form {
+        return Err(AttributeError::new(
+            format!(
+                "attribute '{}' must be written as `{}`",
+                attr.name, known.template
+            ),
+            attr.span,
+        ));
+    }
+    Ok(())
+}
+
+/// The known attribute closest to `name`, if one is close enough to be a
+/// likely typo: about a third of its letters differ at most.
+fn suggest(name: &str) -> Option<&'static str> {
+    KNOWN_ATTRIBUTES
+        .iter()
+        .map(|known| (edit_distance(name, known.name), known.name))
+        .filter(|&(distance, known)| distance <= known.len().div_ceil(3))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, known)| known)
+}
+
+/// Levenshtein distance: the fewest insertions, deletions and substitutions
+/// of characters that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -1,114 +1,2340 @@
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use inkwell::OptimizationLevel;
+use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
+use inkwell::intrinsics::Intrinsic;
+use inkwell::module::Linkage;
 use inkwell::module::Module;
 use inkwell::targets::{
-    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine,
+    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetData, TargetMachine,
 };
-use inkwell::values::FunctionValue;
+use inkwell::types::{
+    BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FloatType, IntType, StructType,
+};
+use inkwell::values::{
+    BasicMetadataValueEnum, BasicValueEnum, FloatValue, FunctionValue, IntValue, PointerValue,
+};
+use inkwell::{AddressSpace, OptimizationLevel};
+use inkwell::{FloatPredicate, IntPredicate};
 
-use crate::ast::{Expr, Function, Program};
+use crate::ast::{
+    Arg, ArmBody, BinaryOp, Block, DestructureDecl, EnumDecl, Expr, ExprKind, FieldInit, Function,
+    Label, Loop, LoopKind, Match, MatchArm, Modifier, Pattern, PatternKind, Program, Stmt,
+    StmtKind, StructDecl, UnaryOp, VarDecl,
+};
+use crate::error::{CodegenError, CodegenResult};
+use crate::tokens::{FloatLiteral, IntLiteral, IntSuffix, Span};
+use crate::types::{FloatKind, Type};
 
 pub struct CodeGen<'ctx> {
     context: &'ctx Context,
     module: Module<'ctx>,
     builder: Builder<'ctx>,
+    /// Layout of the target, which enums need to size their unions.
+    target_data: TargetData,
+    /// Every struct of the program by name.
+    structs: HashMap<String, StructInfo<'ctx>>,
+    /// Every enum of the program by name.
+    enums: HashMap<String, EnumInfo<'ctx>>,
+    /// The overloads of every function of the program by name, declared
+    /// before any body is compiled so calls can refer to functions defined later.
+    functions: HashMap<String, Vec<Signature<'ctx>>>,
+    /// Locals of the function being compiled, innermost scope last.
+    scopes: Vec<HashMap<String, Local<'ctx>>>,
+    /// Enclosing loops of the statement being compiled, innermost last.
+    loops: Vec<LoopTargets<'ctx>>,
+    /// Return types of the function being compiled.
+    return_types: Vec<Type>,
+}
+
+/// Where `break` and `continue` jump to inside a loop.
+#[derive(Debug, Clone)]
+struct LoopTargets<'ctx> {
+    label: Option<String>,
+    break_bb: BasicBlock<'ctx>,
+    continue_bb: BasicBlock<'ctx>,
+    /// Set when the loop is used as a value.
+    value: Option<LoopValue<'ctx>>,
+}
+
+/// The values a loop used as a value can end with, merged by a phi in its end block.
+#[derive(Debug, Clone)]
+struct LoopValue<'ctx> {
+    /// Known from context, or from the first `break` that carries a value.
+    ty: Option<Type>,
+    /// Each value with the block that branches to the end block with it.
+    incoming: Vec<(BasicValueEnum<'ctx>, BasicBlock<'ctx>)>,
+}
+
+/// A declared struct: its named LLVM type and its fields in declaration order.
+#[derive(Debug, Clone)]
+struct StructInfo<'ctx> {
+    llvm: StructType<'ctx>,
+    fields: Vec<(String, Type)>,
+}
+
+/// A declared enum: its named LLVM type, `{ tag, union }`, and its variants
+/// in declaration order. A variant's tag is its index. The union is the
+/// payload with the strictest alignment, padded with bytes to the size of
+/// the largest, so it fits and is aligned for every variant's payload. An
+/// enum without fields in any variant is only its tag.
+#[derive(Debug, Clone)]
+struct EnumInfo<'ctx> {
+    llvm: StructType<'ctx>,
+    tag: IntType<'ctx>,
+    variants: Vec<VariantInfo<'ctx>>,
+}
+
+#[derive(Debug, Clone)]
+struct VariantInfo<'ctx> {
+    name: String,
+    fields: Vec<Type>,
+    /// The fields laid out as a C struct, stored at the start of the union.
+    payload: StructType<'ctx>,
+}
+
+#[derive(Debug, Clone)]
+struct Signature<'ctx> {
+    value: FunctionValue<'ctx>,
+    params: Vec<Type>,
+    /// Several return values are returned together as an LLVM struct.
+    returns: Vec<Type>,
+    /// The program's entry point, which only the generated `main` calls.
+    entry: bool,
+}
+
+/// A stack slot and the type of the value stored in it: a named local, or
+/// a field of one.
+#[derive(Debug, Clone)]
+struct Local<'ctx> {
+    ptr: PointerValue<'ctx>,
+    ty: Type,
+    mutable: bool,
 }
 
 impl<'ctx> CodeGen<'ctx> {
-    pub fn new(context: &'ctx Context, module_name: &str) -> Self {
+    /// A code generator for a module targeting `target`, whose data layout
+    /// decides the size of enums.
+    pub fn new(context: &'ctx Context, module_name: &str, target: &TargetMachine) -> Self {
         let module = context.create_module(module_name);
+        let target_data = target.get_target_data();
+        module.set_triple(&target.get_triple());
+        module.set_data_layout(&target_data.get_data_layout());
         let builder = context.create_builder();
         Self {
             context,
             module,
             builder,
+            target_data,
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            functions: HashMap::new(),
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            return_types: Vec::new(),
         }
     }
 
-    pub fn compile_program(mut self, program: &Program) -> Result<Module<'ctx>, String> {
+    /// Lowers a program whose attributes and entry point were checked;
+    /// `entry` is the index of its entry function in `program.functions`.
+    pub fn compile_program(
+        mut self,
+        program: &Program,
+        entry: Option<usize>,
+    ) -> CodegenResult<Module<'ctx>> {
+        self.declare_types(&program.structs, &program.enums)?;
+        let mut overload_counts: HashMap<&str, usize> = HashMap::new();
+        for f in &program.functions {
+            *overload_counts.entry(&f.name).or_default() += 1;
+        }
+        let mut signatures = Vec::with_capacity(program.functions.len());
         for f in &program.functions {
-            self.compile_function(f)?;
+            // The generated C `main` takes that symbol, so functions named
+            // `main` are mangled like overloads when there is an entry point.
+            let mangled = overload_counts[f.name.as_str()] > 1
+                || f.name.contains('.')
+                || (entry.is_some() && f.name == "main");
+            signatures.push(self.declare_function(f, mangled)?);
+        }
+        let entry_sig = entry.map(|index| signatures[index].clone());
+        for (f, sig) in program.functions.iter().zip(signatures) {
+            self.compile_function(f, sig)?;
+        }
+        if let Some(sig) = entry_sig {
+            self.build_c_main(&sig)?;
         }
         Ok(self.module)
     }
 
-    fn compile_function(&mut self, f: &Function) -> Result<FunctionValue<'ctx>, String> {
-        // MVP type mapping:
-        // "u32" -> i32 (close enough for now; refine later)
-        let ret_ty = match f.return_type.as_str() {
-            "u32" | "i32" => self.context.i32_type(),
-            other => return Err(format!("Unsupported return type for MVP: {other}")),
+    /// Creates a named LLVM struct for every struct and enum before filling
+    /// in any fields, so fields can have the type of one declared later.
+    fn declare_types(&mut self, structs: &[StructDecl], enums: &[EnumDecl]) -> CodegenResult<()> {
+        let names = structs
+            .iter()
+            .map(|d| ("struct", &d.name, d.span))
+            .chain(enums.iter().map(|d| ("enum", &d.name, d.span)));
+        for (kind, name, span) in names {
+            if Type::from_name(name).is_some() {
+                return Err(CodegenError::new(
+                    format!("{kind} '{name}' has the name of a built-in type"),
+                    span,
+                ));
+            }
+            if self.structs.contains_key(name) || self.enums.contains_key(name) {
+                return Err(CodegenError::new(
+                    format!("{kind} '{name}' is defined multiple times"),
+                    span,
+                ));
+            }
+            let llvm = self.context.opaque_struct_type(name);
+            if kind == "struct" {
+                let info = StructInfo {
+                    llvm,
+                    fields: Vec::new(),
+                };
+                self.structs.insert(name.clone(), info);
+            } else {
+                let info = EnumInfo {
+                    llvm,
+                    tag: self.context.i8_type(),
+                    variants: Vec::new(),
+                };
+                self.enums.insert(name.clone(), info);
+            }
+        }
+
+        for decl in structs {
+            let packed = decl.attrs.iter().any(|attr| attr.name == "packed");
+            let mut fields: Vec<(String, Type)> = Vec::with_capacity(decl.fields.len());
+            for field in &decl.fields {
+                if fields.iter().any(|(name, _)| *name == field.name) {
+                    return Err(CodegenError::new(
+                        format!(
+                            "field '{}' is declared more than once in struct '{}'",
+                            field.name, decl.name
+                        ),
+                        field.span,
+                    ));
+                }
+                fields.push((
+                    field.name.clone(),
+                    self.resolve_type(&field.ty, field.ty_span)?,
+                ));
+            }
+            // LLVM lays out a non-packed struct like C does: each field at the
+            // next multiple of its alignment, and the size rounded up to the
+            // largest alignment.
+            let llvm_fields: Vec<BasicTypeEnum> =
+                fields.iter().map(|(_, ty)| self.llvm_type(ty)).collect();
+            let info = self.structs.get_mut(&decl.name).expect("declared above");
+            info.llvm.set_body(&llvm_fields, packed);
+            info.fields = fields;
+        }
+
+        for decl in structs {
+            if self.contains_struct(&decl.name, &decl.name, &mut HashSet::new()) {
+                return Err(CodegenError::new(
+                    format!(
+                        "struct '{}' contains itself, so it would have infinite size",
+                        decl.name
+                    ),
+                    decl.span,
+                ));
+            }
+        }
+
+        let decls: HashMap<&str, &EnumDecl> = enums.iter().map(|d| (d.name.as_str(), d)).collect();
+        let mut defined = HashSet::new();
+        for decl in enums {
+            self.define_enum(decl, &decls, &mut Vec::new(), &mut defined)?;
+        }
+        Ok(())
+    }
+
+    /// Lays out enum `decl` after the enums its fields hold, directly or in
+    /// a struct, since its size depends on theirs.
+    fn define_enum(
+        &mut self,
+        decl: &EnumDecl,
+        decls: &HashMap<&str, &EnumDecl>,
+        in_progress: &mut Vec<String>,
+        defined: &mut HashSet<String>,
+    ) -> CodegenResult<()> {
+        if defined.contains(&decl.name) {
+            return Ok(());
+        }
+        if in_progress.contains(&decl.name) {
+            return Err(CodegenError::new(
+                format!(
+                    "enum '{}' contains itself, so it would have infinite size",
+                    decl.name
+                ),
+                decl.span,
+            ));
+        }
+        in_progress.push(decl.name.clone());
+        let mut variants: Vec<VariantInfo<'ctx>> = Vec::with_capacity(decl.variants.len());
+        for variant in &decl.variants {
+            if variants.iter().any(|v| v.name == variant.name) {
+                return Err(CodegenError::new(
+                    format!(
+                        "variant '{}' is declared more than once in enum '{}'",
+                        variant.name, decl.name
+                    ),
+                    variant.span,
+                ));
+            }
+            let mut fields = Vec::with_capacity(variant.fields.len());
+            for (ty, span) in &variant.fields {
+                let ty = self.resolve_type(ty, *span)?;
+                for inner in self.enums_within(&ty) {
+                    self.define_enum(decls[inner.as_str()], decls, in_progress, defined)?;
+                }
+                fields.push(ty);
+            }
+            let llvm_fields: Vec<BasicTypeEnum> =
+                fields.iter().map(|ty| self.llvm_type(ty)).collect();
+            variants.push(VariantInfo {
+                name: variant.name.clone(),
+                fields,
+                payload: self.context.struct_type(&llvm_fields, false),
+            });
+        }
+        in_progress.pop();
+
+        let tag = match variants.len() {
+            0..=0x100 => self.context.i8_type(),
+            0x101..=0x1_0000 => self.context.i16_type(),
+            _ => self.context.i32_type(),
         };
+        let mut body: Vec<BasicTypeEnum> = vec![tag.into()];
+        let size = |v: &VariantInfo| self.target_data.get_abi_size(&v.payload);
+        if let Some(largest) = variants
+            .iter()
+            .filter(|v| !v.fields.is_empty())
+            .map(size)
+            .max()
+        {
+            let aligned = variants
+                .iter()
+                .max_by_key(|v| (self.target_data.get_abi_alignment(&v.payload), size(v)))
+                .expect("some variant has fields");
+            let mut members: Vec<BasicTypeEnum> = vec![aligned.payload.into()];
+            let padding = largest - size(aligned);
+            if padding > 0 {
+                members.push(self.context.i8_type().array_type(padding as u32).into());
+            }
+            body.push(self.context.struct_type(&members, false).into());
+        }
+        let info = self.enums.get_mut(&decl.name).expect("declared above");
+        info.llvm.set_body(&body, false);
+        info.tag = tag;
+        info.variants = variants;
+        defined.insert(decl.name.clone());
+        Ok(())
+    }
 
-        // MVP: no params
-        let fn_ty = ret_ty.fn_type(&[], false);
-        let fn_val = self.module.add_function(&f.name, fn_ty, None);
+    /// The enums a value of type `ty` holds without indirection: itself, or
+    /// those in the fields of a struct.
+    fn enums_within(&self, ty: &Type) -> Vec<String> {
+        match ty {
+            Type::Enum(name) => vec![name.to_string()],
+            Type::Struct(name) => self.structs[&**name]
+                .fields
+                .iter()
+                .flat_map(|(_, ty)| self.enums_within(ty))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether struct `outer` holds an `inner` in one of its fields, directly
+    /// or nested in another struct.
+    fn contains_struct(&self, outer: &str, inner: &str, visited: &mut HashSet<String>) -> bool {
+        self.structs[outer].fields.iter().any(|(_, ty)| match ty {
+            Type::Struct(name) => {
+                **name == *inner
+                    || (visited.insert(name.to_string())
+                        && self.contains_struct(name, inner, visited))
+            }
+            _ => false,
+        })
+    }
+
+    /// Adds `f` to the overload set of its name, declared under a mangled
+    /// symbol when the caller says it is `mangled` and under its own name
+    /// otherwise.
+    fn declare_function(&mut self, f: &Function, mangled: bool) -> CodegenResult<Signature<'ctx>> {
+        let returns = f
+            .returns
+            .iter()
+            .map(|r| self.resolve_type(&r.ty, r.ty_span))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        let params = f
+            .params
+            .iter()
+            .map(|p| self.resolve_type(&p.ty, p.ty_span))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        let overloads = self.functions.entry(f.name.clone()).or_default();
+        if overloads
+            .iter()
+            .any(|sig| sig.params == params && sig.returns == returns)
+        {
+            return Err(CodegenError::new(
+                format!(
+                    "function '{}' is defined multiple times with the same parameter and return types",
+                    f.name
+                ),
+                f.span,
+            ));
+        }
+
+        let llvm_params: Vec<BasicMetadataTypeEnum> =
+            params.iter().map(|ty| self.llvm_type(ty).into()).collect();
+        let fn_ty = if returns.is_empty() {
+            self.context.void_type().fn_type(&llvm_params, false)
+        } else {
+            self.return_llvm_type(&returns).fn_type(&llvm_params, false)
+        };
+        let symbol = if mangled {
+            mangle(&f.name, &params, &returns)
+        } else {
+            f.name.clone()
+        };
+        let value = self.module.add_function(&symbol, fn_ty, None);
+        let sig = Signature {
+            value,
+            params,
+            returns,
+            entry: f.is_entry(),
+        };
+        self.functions
+            .get_mut(&f.name)
+            .expect("entry created above")
+            .push(sig.clone());
+        Ok(sig)
+    }
+
+    fn compile_function(
+        &mut self,
+        f: &Function,
+        sig: Signature<'ctx>,
+    ) -> CodegenResult<FunctionValue<'ctx>> {
+        let Signature {
+            value: fn_val,
+            params: param_types,
+            returns,
+            ..
+        } = sig;
 
         let entry = self.context.append_basic_block(fn_val, "entry");
         self.builder.position_at_end(entry);
 
-        // MVP body: must contain exactly one return
-        for expr in &f.body {
-            match expr {
-                Expr::Return(inner) => {
-                    let value = self.codegen_expr(inner)?;
-                    self.builder
-                        .build_return(Some(&value))
-                        .map_err(|e| format!("build_return failed: {e:?}"))?;
+        // Parameters share the body's outermost scope, and are spilled to stack
+        // slots so they behave like any other local.
+        self.scopes = vec![HashMap::new()];
+        for ((param, ty), value) in f
+            .params
+            .iter()
+            .zip(param_types)
+            .zip(fn_val.get_param_iter())
+        {
+            if self.scopes[0].contains_key(&param.name) {
+                return Err(CodegenError::new(
+                    format!(
+                        "identifier '{}' is bound more than once in this parameter list",
+                        param.name
+                    ),
+                    param.span,
+                ));
+            }
+            value.set_name(&param.name);
+            let ptr = self.build_entry_alloca(&ty, &param.name)?;
+            self.builder.build_store(ptr, value)?;
+            self.scopes[0].insert(
+                param.name.clone(),
+                Local {
+                    ptr,
+                    ty,
+                    mutable: true,
+                },
+            );
+        }
+
+        // Named return values are zero-initialized locals, returned at the end.
+        let mut named = Vec::new();
+        for (ret, ty) in f.returns.iter().zip(&returns) {
+            let Some(name) = &ret.name else { continue };
+            if self.scopes[0].contains_key(name) {
+                return Err(CodegenError::new(
+                    format!("identifier '{name}' is bound more than once in this signature"),
+                    ret.span,
+                ));
+            }
+            let ptr = self.build_entry_alloca(ty, name)?;
+            self.builder
+                .build_store(ptr, self.llvm_type(ty).const_zero())?;
+            let local = Local {
+                ptr,
+                ty: ty.clone(),
+                mutable: true,
+            };
+            self.scopes[0].insert(name.clone(), local.clone());
+            named.push(local);
+        }
+
+        self.return_types = returns.clone();
+        self.codegen_stmts(&f.body.stmts)?;
+
+        let block = self.current_block();
+        if block.get_terminator().is_none() {
+            if !named.is_empty() {
+                let mut values = Vec::with_capacity(named.len());
+                for (local, ret) in named.into_iter().zip(&f.returns) {
+                    values.push(self.load_local(&local, ret.name.as_deref().unwrap_or("ret"))?);
                 }
-                other => return Err(format!("Unsupported statement in MVP: {other:?}")),
+                self.build_return_values(&values)?;
+            } else if returns.is_empty() {
+                self.builder.build_return(None)?;
+            } else if block != entry && block.get_first_use().is_none() {
+                // Only reachable code returns; the fall-through block after a `return` is dead.
+                self.builder.build_unreachable()?;
+            } else {
+                let close = Span {
+                    start: f.body.span.end - 1,
+                    end: f.body.span.end,
+                };
+                let types: Vec<String> = returns.iter().map(Type::to_string).collect();
+                return Err(CodegenError::new(
+                    format!(
+                        "function '{}' may reach its end without returning {} of type {}",
+                        f.name,
+                        if types.len() == 1 {
+                            "a value"
+                        } else {
+                            "values"
+                        },
+                        types.join(", ")
+                    ),
+                    close,
+                ));
             }
         }
+        self.scopes.clear();
+        self.return_types.clear();
 
         Ok(fn_val)
     }
 
-    fn codegen_expr(&self, e: &Expr) -> Result<inkwell::values::IntValue<'ctx>, String> {
-        let i32t = self.context.i32_type();
+    /// A single return value is returned as is; several as a struct of them.
+    fn return_llvm_type(&self, returns: &[Type]) -> BasicTypeEnum<'ctx> {
+        match returns {
+            [ty] => self.llvm_type(ty),
+            _ => {
+                let fields: Vec<BasicTypeEnum> =
+                    returns.iter().map(|ty| self.llvm_type(ty)).collect();
+                self.context.struct_type(&fields, false).into()
+            }
+        }
+    }
+
+    fn build_return_values(&self, values: &[BasicValueEnum<'ctx>]) -> CodegenResult<()> {
+        match values {
+            [value] => self.builder.build_return(Some(value))?,
+            _ => self.builder.build_aggregate_return(values)?,
+        };
+        Ok(())
+    }
+
+    /// Defines the C `main(argc, argv)`, which calls the entry function and
+    /// makes what it returns the exit status: an integer is sign- or
+    /// zero-extended or truncated to a C `int`, and nothing is 0.
+    fn build_c_main(&self, entry: &Signature<'ctx>) -> CodegenResult<()> {
+        let int_type = self.context.i32_type();
+        let argv_type = self.context.ptr_type(AddressSpace::default());
+        let fn_ty = int_type.fn_type(&[int_type.into(), argv_type.into()], false);
+        let main = self.module.add_function("main", fn_ty, None);
+        for (param, name) in main.get_param_iter().zip(["argc", "argv"]) {
+            param.set_name(name);
+        }
+        let block = self.context.append_basic_block(main, "entry");
+        self.builder.position_at_end(block);
+
+        let call = self.builder.build_call(entry.value, &[], "")?;
+        let status = match entry.returns.as_slice() {
+            [Type::Int { signed, bits }] => {
+                let value = call
+                    .try_as_basic_value()
+                    .basic()
+                    .expect("the entry function returns an integer")
+                    .into_int_value();
+                match bits.cmp(&32) {
+                    std::cmp::Ordering::Less if *signed => {
+                        self.builder.build_int_s_extend(value, int_type, "status")?
+                    }
+                    std::cmp::Ordering::Less => {
+                        self.builder.build_int_z_extend(value, int_type, "status")?
+                    }
+                    std::cmp::Ordering::Equal => value,
+                    std::cmp::Ordering::Greater => {
+                        self.builder.build_int_truncate(value, int_type, "status")?
+                    }
+                }
+            }
+            _ => int_type.const_zero(),
+        };
+        self.builder.build_return(Some(&status))?;
+        Ok(())
+    }
+
+    fn current_block(&self) -> BasicBlock<'ctx> {
+        self.builder
+            .get_insert_block()
+            .expect("builder is positioned")
+    }
+
+    /// Allocates a stack slot at the top of the entry block, where LLVM's
+    /// `mem2reg` expects every alloca to be.
+    fn build_entry_alloca(&self, ty: &Type, name: &str) -> CodegenResult<PointerValue<'ctx>> {
+        let entry = self
+            .current_function()
+            .get_first_basic_block()
+            .expect("function has an entry block");
+        let builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => builder.position_before(&first),
+            None => builder.position_at_end(entry),
+        }
+        Ok(builder.build_alloca(self.llvm_type(ty), name)?)
+    }
+
+    fn codegen_block(&mut self, block: &Block) -> CodegenResult<()> {
+        self.scopes.push(HashMap::new());
+        let result = self.codegen_stmts(&block.stmts);
+        self.scopes.pop();
+        result
+    }
+
+    fn codegen_stmts(&mut self, stmts: &[Stmt]) -> CodegenResult<()> {
+        for stmt in stmts {
+            if self.current_block().get_terminator().is_some() {
+                // Code after a `return` still has to be valid, so give it a fresh
+                // block; nothing branches to it.
+                let function = self.current_function();
+                let dead = self.context.append_basic_block(function, "unreachable");
+                self.builder.position_at_end(dead);
+            }
+            self.codegen_stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn codegen_stmt(&mut self, stmt: &Stmt) -> CodegenResult<()> {
+        match &stmt.kind {
+            StmtKind::VarDecl(decl) => self.codegen_var_decl(decl),
+            StmtKind::Destructure(decl) => self.codegen_destructure(decl),
+            StmtKind::Assign { target, op, value } => {
+                let local = self.assignable_place(target)?;
+                let value = match op {
+                    None => self.codegen_expr(value, Some(local.ty.clone()))?,
+                    Some(op) => {
+                        let current = self.load_local(&local, "cur")?;
+                        let rhs = self.codegen_expr(value, Some(local.ty.clone()))?;
+                        self.codegen_binary(*op, current, rhs, &local.ty, stmt.span)?
+                            .0
+                    }
+                };
+                self.builder.build_store(local.ptr, value)?;
+                Ok(())
+            }
+            StmtKind::Increment(target) | StmtKind::Decrement(target) => {
+                let increment = matches!(stmt.kind, StmtKind::Increment(_));
+                let local = self.assignable_place(target)?;
+                let one: BasicValueEnum = match &local.ty {
+                    Type::Int { .. } => self
+                        .llvm_type(&local.ty)
+                        .into_int_type()
+                        .const_int(1, false)
+                        .into(),
+                    Type::Float(kind) => self.float_type(*kind).const_float(1.0).into(),
+                    ty => {
+                        let op = if increment { "++" } else { "--" };
+                        return Err(cannot_apply(op, ty, stmt.span));
+                    }
+                };
+                let op = if increment {
+                    BinaryOp::Add
+                } else {
+                    BinaryOp::Sub
+                };
+                let current = self.load_local(&local, "cur")?;
+                let (value, _) = self.codegen_binary(op, current, one, &local.ty, stmt.span)?;
+                self.builder.build_store(local.ptr, value)?;
+                Ok(())
+            }
+            StmtKind::Expr(expr) => {
+                self.codegen_typed_expr(expr, None)?;
+                Ok(())
+            }
+            StmtKind::Return(exprs) => {
+                let returns = self.return_types.clone();
+                let values = match exprs.as_slice() {
+                    [expr] if returns.len() > 1 => self.codegen_multi_expr(expr, &returns)?,
+                    _ if exprs.len() != returns.len() => {
+                        return Err(CodegenError::new(
+                            format!(
+                                "expected {} return value{}, found {}",
+                                returns.len(),
+                                if returns.len() == 1 { "" } else { "s" },
+                                exprs.len()
+                            ),
+                            stmt.span,
+                        ));
+                    }
+                    _ => {
+                        let mut values = Vec::with_capacity(exprs.len());
+                        for (expr, ty) in exprs.iter().zip(&returns) {
+                            values.push(self.codegen_expr(expr, Some(ty.clone()))?);
+                        }
+                        values
+                    }
+                };
+                self.build_return_values(&values)
+            }
+            StmtKind::Block(block) => self.codegen_block(block),
+            StmtKind::Loop(lp) => {
+                self.codegen_loop(lp, None)?;
+                Ok(())
+            }
+            StmtKind::Break { label, value } => {
+                let idx = self.resolve_loop(label.as_ref());
+                // Semantic analysis checked that a value is given exactly
+                // when the loop is used as one, and that the types agree.
+                if let Some(value) = value {
+                    let ty = self.loops[idx]
+                        .value
+                        .as_ref()
+                        .expect("a break value targets a value loop")
+                        .ty
+                        .clone();
+                    let (v, found) = self.codegen_typed_expr(value, ty)?;
+                    let block = self.current_block();
+                    let slot = self.loops[idx].value.as_mut().expect("checked above");
+                    slot.ty = Some(found);
+                    slot.incoming.push((v, block));
+                }
+                self.builder
+                    .build_unconditional_branch(self.loops[idx].break_bb)?;
+                Ok(())
+            }
+            StmtKind::Continue { label } => {
+                let idx = self.resolve_loop(label.as_ref());
+                self.builder
+                    .build_unconditional_branch(self.loops[idx].continue_bb)?;
+                Ok(())
+            }
+            StmtKind::Error => Err(CodegenError::new(
+                "cannot compile a statement that failed to parse",
+                stmt.span,
+            )),
+            StmtKind::If {
+                cond,
+                then_block,
+                else_branch,
+            } => {
+                let cond = self.codegen_expr(cond, Some(Type::Bool))?.into_int_value();
+                let function = self.current_function();
+                let then_bb = self.context.append_basic_block(function, "if.then");
+                let else_bb = else_branch
+                    .as_ref()
+                    .map(|_| self.context.append_basic_block(function, "if.else"));
+                let end_bb = self.context.append_basic_block(function, "if.end");
+                self.builder
+                    .build_conditional_branch(cond, then_bb, else_bb.unwrap_or(end_bb))?;
+
+                self.builder.position_at_end(then_bb);
+                self.codegen_block(then_block)?;
+                self.branch_if_open(end_bb)?;
+
+                if let (Some(else_bb), Some(else_branch)) = (else_bb, else_branch) {
+                    self.builder.position_at_end(else_bb);
+                    self.codegen_stmt(else_branch)?;
+                    self.branch_if_open(end_bb)?;
+                }
+
+                // When every arm returned, nothing branches here and the block stays dead.
+                self.builder.position_at_end(end_bb);
+                Ok(())
+            }
+            StmtKind::Match(m) => {
+                self.codegen_match(m, false, None, stmt.span)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Index in `self.loops` of the loop a `break`/`continue` targets.
+    fn resolve_loop(&self, label: Option<&Label>) -> usize {
+        let found = match label {
+            None => self.loops.len().checked_sub(1),
+            Some(label) => self
+                .loops
+                .iter()
+                .rposition(|l| l.label.as_deref() == Some(label.name.as_str())),
+        };
+        found.expect("semantic analysis checked the loop exists")
+    }
+
+    /// Lowers a loop used as a value, merging its `break` values and its
+    /// default with a phi.
+    fn codegen_loop_expr(
+        &mut self,
+        lp: &Loop,
+        expected: Option<Type>,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let ty = expected.or_else(|| lp.default.as_deref().and_then(|d| self.natural_type(d)));
+        let value = self
+            .codegen_loop(
+                lp,
+                Some(LoopValue {
+                    ty,
+                    incoming: Vec::new(),
+                }),
+            )?
+            .expect("a value loop keeps its value");
+        let ty = value
+            .ty
+            .expect("semantic analysis inferred the loop's type");
+        if value.incoming.is_empty() {
+            // Nothing reaches the end block; any value will do.
+            return Ok((self.llvm_type(&ty).const_zero(), ty));
+        }
+        let phi = self.builder.build_phi(self.llvm_type(&ty), "loop.value")?;
+        for (v, block) in &value.incoming {
+            phi.add_incoming(&[(v, *block)]);
+        }
+        Ok((phi.as_basic_value(), ty))
+    }
+
+    /// Lowers a loop to the usual shapes, with the condition in its own block
+    /// so it is the loop header (or latch, for `do .. while`):
+    ///
+    /// ```text
+    /// while:    -> cond -> body -> cond ... -> exit -> end
+    /// for:      init -> cond -> body -> update -> cond ... -> exit -> end
+    /// do-while: -> body -> cond -> body ... -> exit -> end
+    /// loop:     -> body -> body ...          (end only via break)
+    /// ```
+    ///
+    /// `exit` is where the loop ends on its own. It runs the `else` block if
+    /// the body never ran, then the default value of a value loop. `break`
+    /// skips it and goes straight to `end`. Returns the collected values of
+    /// a value loop, with the builder left in `end`.
+    fn codegen_loop(
+        &mut self,
+        lp: &Loop,
+        value: Option<LoopValue<'ctx>>,
+    ) -> CodegenResult<Option<LoopValue<'ctx>>> {
+        // A `for` header's declarations are scoped to the loop.
+        self.scopes.push(HashMap::new());
+        let result = self.codegen_loop_blocks(lp, value);
+        self.scopes.pop();
+        result
+    }
+
+    fn codegen_loop_blocks(
+        &mut self,
+        lp: &Loop,
+        value: Option<LoopValue<'ctx>>,
+    ) -> CodegenResult<Option<LoopValue<'ctx>>> {
+        let function = self.current_function();
+        let append = |name: &str| self.context.append_basic_block(function, name);
+
+        let prefix = match &lp.kind {
+            LoopKind::While(_) => "while",
+            LoopKind::For { .. } => "for",
+            LoopKind::DoWhile(_) => "do",
+            LoopKind::Infinite => "loop",
+        };
+        if let LoopKind::For {
+            init: Some(init), ..
+        } = &lp.kind
+        {
+            self.codegen_stmt(init)?;
+        }
+        let (header, body_bb, continue_bb) = match &lp.kind {
+            LoopKind::While(_) | LoopKind::For { .. } => {
+                let cond = append(&format!("{prefix}.cond"));
+                let body = append(&format!("{prefix}.body"));
+                let continue_bb = match lp.kind {
+                    LoopKind::For { .. } => append("for.update"),
+                    _ => cond,
+                };
+                (cond, body, continue_bb)
+            }
+            LoopKind::DoWhile(_) => {
+                let body = append("do.body");
+                (body, body, append("do.cond"))
+            }
+            LoopKind::Infinite => {
+                let body = append("loop.body");
+                (body, body, body)
+            }
+        };
+        let exit_bb = (lp.else_block.is_some() || lp.default.is_some())
+            .then(|| append(&format!("{prefix}.exit")));
+        let end_bb = append(&format!("{prefix}.end"));
+        let exit_bb = exit_bb.unwrap_or(end_bb);
+
+        // The `else` block needs to know whether the body ever ran.
+        let entered = match lp.else_block {
+            Some(_) => {
+                let flag = self.build_entry_alloca(&Type::Bool, &format!("{prefix}.entered"))?;
+                self.builder
+                    .build_store(flag, self.context.bool_type().const_zero())?;
+                Some(flag)
+            }
+            None => None,
+        };
+        self.builder.build_unconditional_branch(header)?;
+
+        // Condition at the top.
+        let top_cond = match &lp.kind {
+            LoopKind::While(cond) => Some(cond),
+            LoopKind::For { cond, .. } => cond.as_ref(),
+            LoopKind::DoWhile(_) | LoopKind::Infinite => None,
+        };
+        if let Some(cond) = top_cond {
+            self.builder.position_at_end(header);
+            let cond = self.codegen_expr(cond, Some(Type::Bool))?.into_int_value();
+            self.builder
+                .build_conditional_branch(cond, body_bb, exit_bb)?;
+        } else if header != body_bb {
+            // `for (;;)`
+            self.builder.position_at_end(header);
+            self.builder.build_unconditional_branch(body_bb)?;
+        }
+
+        self.builder.position_at_end(body_bb);
+        if let Some(flag) = entered {
+            self.builder
+                .build_store(flag, self.context.bool_type().const_all_ones())?;
+        }
+        self.loops.push(LoopTargets {
+            label: lp.label.as_ref().map(|l| l.name.clone()),
+            break_bb: end_bb,
+            continue_bb,
+            value,
+        });
+        let body = self.codegen_block(&lp.body);
+        let mut value = self.loops.pop().expect("pushed above").value;
+        body?;
+        self.branch_if_open(continue_bb)?;
+
+        // Whatever runs after the body: the `for` update or the `do` condition.
+        match &lp.kind {
+            LoopKind::For { update, .. } => {
+                self.builder.position_at_end(continue_bb);
+                if let Some(update) = update {
+                    self.codegen_stmt(update)?;
+                }
+                self.builder.build_unconditional_branch(header)?;
+            }
+            LoopKind::DoWhile(cond) => {
+                self.builder.position_at_end(continue_bb);
+                let cond = self.codegen_expr(cond, Some(Type::Bool))?.into_int_value();
+                self.builder
+                    .build_conditional_branch(cond, body_bb, exit_bb)?;
+            }
+            LoopKind::While(_) | LoopKind::Infinite => {}
+        }
+
+        if exit_bb != end_bb {
+            self.builder.position_at_end(exit_bb);
+            if let (Some(flag), Some(else_block)) = (entered, &lp.else_block) {
+                let else_bb = append(&format!("{prefix}.else"));
+                let done_bb = append(&format!("{prefix}.done"));
+                let ran = self
+                    .load_local(
+                        &Local {
+                            ptr: flag,
+                            ty: Type::Bool,
+                            mutable: true,
+                        },
+                        "entered",
+                    )?
+                    .into_int_value();
+                self.builder
+                    .build_conditional_branch(ran, done_bb, else_bb)?;
+                self.builder.position_at_end(else_bb);
+                self.codegen_block(else_block)?;
+                self.branch_if_open(done_bb)?;
+                self.builder.position_at_end(done_bb);
+            }
+            if let (Some(default), Some(slot)) = (&lp.default, value.as_mut()) {
+                let (v, found) = self.codegen_typed_expr(default, slot.ty.clone())?;
+                slot.ty = Some(found);
+                slot.incoming.push((v, self.current_block()));
+            }
+            self.branch_if_open(end_bb)?;
+        }
+
+        self.builder.position_at_end(end_bb);
+        Ok(value)
+    }
+
+    fn current_function(&self) -> FunctionValue<'ctx> {
+        self.current_block()
+            .get_parent()
+            .expect("block belongs to a function")
+    }
+
+    /// Branches to `target` unless the current block already ended, e.g. in a `return`.
+    fn branch_if_open(&self, target: BasicBlock<'ctx>) -> CodegenResult<()> {
+        if self.current_block().get_terminator().is_none() {
+            self.builder.build_unconditional_branch(target)?;
+        }
+        Ok(())
+    }
+
+    fn codegen_var_decl(&mut self, decl: &VarDecl) -> CodegenResult<()> {
+        let ty = self.resolve_type(&decl.ty, decl.ty_span)?;
+        let scope = self.scopes.last().expect("inside a function body");
+        if scope.contains_key(&decl.name) {
+            return Err(CodegenError::new(
+                format!("'{}' is already declared in this scope", decl.name),
+                decl.name_span,
+            ));
+        }
+
+        // The initializer can't see the variable it initializes.
+        let value = match &decl.init {
+            Some(init) => self.codegen_expr(init, Some(ty.clone()))?,
+            None => self.llvm_type(&ty).const_zero(),
+        };
+        let ptr = self.build_entry_alloca(&ty, &decl.name)?;
+        self.builder.build_store(ptr, value)?;
+        let mutable = !decl.modifiers.contains(&Modifier::Const);
+        self.scopes
+            .last_mut()
+            .expect("inside a function body")
+            .insert(decl.name.clone(), Local { ptr, ty, mutable });
+        Ok(())
+    }
+
+    fn codegen_destructure(&mut self, decl: &DestructureDecl) -> CodegenResult<()> {
+        let mut types = Vec::with_capacity(decl.bindings.len());
+        for (i, binding) in decl.bindings.iter().enumerate() {
+            let scope = self.scopes.last().expect("inside a function body");
+            if scope.contains_key(&binding.name)
+                || decl.bindings[..i].iter().any(|b| b.name == binding.name)
+            {
+                return Err(CodegenError::new(
+                    format!("'{}' is already declared in this scope", binding.name),
+                    binding.name_span,
+                ));
+            }
+            types.push(self.resolve_type(&binding.ty, binding.ty_span)?);
+        }
+
+        let values = self.codegen_multi_expr(&decl.init, &types)?;
+        let mutable = !decl.modifiers.contains(&Modifier::Const);
+        for ((binding, ty), value) in decl.bindings.iter().zip(types).zip(values) {
+            let ptr = self.build_entry_alloca(&ty, &binding.name)?;
+            self.builder.build_store(ptr, value)?;
+            self.scopes
+                .last_mut()
+                .expect("inside a function body")
+                .insert(binding.name.clone(), Local { ptr, ty, mutable });
+        }
+        Ok(())
+    }
+
+    /// The slot an assignment writes to: a non-`const` local, or a field of one.
+    fn assignable_place(&self, target: &Expr) -> CodegenResult<Local<'ctx>> {
+        let Some(place) = self.place(target)? else {
+            return Err(CodegenError::new(
+                "invalid left-hand side of assignment",
+                target.span,
+            ));
+        };
+        if !place.mutable {
+            let mut root = target;
+            while let ExprKind::Field { base, .. } = &root.kind {
+                root = base;
+            }
+            let ExprKind::Ident(name) = &root.kind else {
+                unreachable!("places are rooted at a local");
+            };
+            let message = if std::ptr::eq(root, target) {
+                format!("cannot assign to const variable '{name}'")
+            } else {
+                format!("cannot assign to a field of const variable '{name}'")
+            };
+            return Err(CodegenError::new(message, target.span));
+        }
+        Ok(place)
+    }
+
+    /// The slot `e` names when it is a local or a field of one, addressing
+    /// fields with a GEP. Other expressions only have a value.
+    fn place(&self, e: &Expr) -> CodegenResult<Option<Local<'ctx>>> {
+        match &e.kind {
+            ExprKind::Ident(name) => self.lookup_local(name, e.span).map(Some),
+            ExprKind::Field {
+                base,
+                name,
+                name_span,
+            } => {
+                let Some(base) = self.place(base)? else {
+                    return Ok(None);
+                };
+                let (index, ty) = self.field(&base.ty, name, *name_span)?;
+                let ptr = self.builder.build_struct_gep(
+                    self.llvm_type(&base.ty),
+                    base.ptr,
+                    index,
+                    name,
+                )?;
+                Ok(Some(Local {
+                    ptr,
+                    ty,
+                    mutable: base.mutable,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Index and type of field `name` of a value of type `ty`.
+    fn field(&self, ty: &Type, name: &str, span: Span) -> CodegenResult<(u32, Type)> {
+        let Type::Struct(struct_name) = ty else {
+            return Err(CodegenError::new(
+                format!("type {ty} has no field named '{name}'"),
+                span,
+            ));
+        };
+        let fields = &self.structs[&**struct_name].fields;
+        let index = fields.iter().position(|(f, _)| f == name).ok_or_else(|| {
+            CodegenError::new(
+                format!("struct '{struct_name}' has no field named '{name}'"),
+                span,
+            )
+        })?;
+        Ok((index as u32, fields[index].1.clone()))
+    }
+
+    fn load_local(&self, local: &Local<'ctx>, name: &str) -> CodegenResult<BasicValueEnum<'ctx>> {
+        Ok(self
+            .builder
+            .build_load(self.llvm_type(&local.ty), local.ptr, name)?)
+    }
+
+    /// Type an expression has on its own, without outside context.
+    fn natural_type(&self, e: &Expr) -> Option<Type> {
+        match &e.kind {
+            ExprKind::Int(lit) => lit.suffix.map(Type::from),
+            ExprKind::Float(lit) => lit.suffix.map(Type::from),
+            ExprKind::Binary { op, .. } if op.is_comparison() || op.is_logical() => {
+                Some(Type::Bool)
+            }
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.natural_type(lhs).or_else(|| self.natural_type(rhs))
+            }
+            ExprKind::Unary {
+                op: UnaryOp::Not, ..
+            } => Some(Type::Bool),
+            ExprKind::Unary { operand, .. } => self.natural_type(operand),
+            ExprKind::If {
+                then_expr,
+                else_expr,
+                ..
+            } => self
+                .natural_type(then_expr)
+                .or_else(|| self.natural_type(else_expr)),
+            ExprKind::Loop(lp) => lp.default.as_deref().and_then(|d| self.natural_type(d)),
+            ExprKind::Match(m) => m.arms.iter().find_map(|arm| match &arm.body {
+                ArmBody::Expr(e) => self.natural_type(e),
+                ArmBody::Block(_) => None,
+            }),
+            ExprKind::Call { callee, .. } if self.variant_path(callee).is_some() => {
+                self.natural_type(callee)
+            }
+            ExprKind::Call {
+                callee, overload, ..
+            } => {
+                // Calls after a mistake codegen reports first may not be resolved.
+                let sig = self.overload(callee, (*overload)?);
+                match &sig.returns[..] {
+                    [ty] => Some(ty.clone()),
+                    _ => None,
+                }
+            }
+            ExprKind::Str(_) | ExprKind::ByteStr(_) => Some(Type::Str),
+            ExprKind::Char(_) => Some(Type::Char),
+            ExprKind::Ident(name) => self.find_local(name).map(|local| local.ty),
+            ExprKind::StructLit { name, .. } => self
+                .structs
+                .contains_key(name)
+                .then(|| Type::Struct(Rc::from(name.as_str()))),
+            ExprKind::Field { .. } if self.variant_path(e).is_some() => {
+                let (enum_name, ..) = self.variant_path(e)?;
+                Some(Type::Enum(Rc::from(enum_name)))
+            }
+            ExprKind::Field { base, name, .. } => {
+                let base = self.natural_type(base)?;
+                self.field(&base, name, e.span).ok().map(|(_, ty)| ty)
+            }
+        }
+    }
+
+    fn find_local(&self, name: &str) -> Option<Local<'ctx>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+    }
+
+    fn lookup_local(&self, name: &str, span: Span) -> CodegenResult<Local<'ctx>> {
+        self.find_local(name).ok_or_else(|| {
+            CodegenError::new(format!("cannot find value '{name}' in this scope"), span)
+        })
+    }
+
+    fn resolve_type(&self, name: &str, span: Span) -> CodegenResult<Type> {
+        if let Some(ty) = Type::from_name(name) {
+            return Ok(ty);
+        }
+        if self.structs.contains_key(name) {
+            return Ok(Type::Struct(Rc::from(name)));
+        }
+        if self.enums.contains_key(name) {
+            return Ok(Type::Enum(Rc::from(name)));
+        }
+        Err(CodegenError::new(format!("Unknown type '{name}'"), span))
+    }
+
+    fn llvm_type(&self, ty: &Type) -> BasicTypeEnum<'ctx> {
+        match ty {
+            Type::Int { bits, .. } => self.context.custom_width_int_type(*bits).into(),
+            Type::Bool => self.context.bool_type().into(),
+            Type::Char => self.context.i32_type().into(),
+            Type::Float(kind) => self.float_type(*kind).into(),
+            Type::Str => self.context.ptr_type(AddressSpace::default()).into(),
+            Type::Struct(name) => self.structs[&**name].llvm.into(),
+            Type::Enum(name) => self.enums[&**name].llvm.into(),
+        }
+    }
+
+    fn float_type(&self, kind: FloatKind) -> FloatType<'ctx> {
+        match kind {
+            FloatKind::F16 => self.context.f16_type(),
+            FloatKind::BF16 => self.context.bf16_type(),
+            FloatKind::F32 => self.context.f32_type(),
+            FloatKind::F64 => self.context.f64_type(),
+            FloatKind::F128 => self.context.f128_type(),
+        }
+    }
+
+    /// Lowers `e`, using `expected` to type unsuffixed literals.
+    fn codegen_expr(
+        &mut self,
+        e: &Expr,
+        expected: Option<Type>,
+    ) -> CodegenResult<BasicValueEnum<'ctx>> {
+        let (value, ty) = self.codegen_typed_expr(e, expected.clone())?;
+        match expected {
+            Some(expected) if expected != ty => Err(CodegenError::new(
+                format!("mismatched types: expected {expected}, found {ty}"),
+                e.span,
+            )),
+            _ => Ok(value),
+        }
+    }
+
+    fn codegen_typed_expr(
+        &mut self,
+        e: &Expr,
+        expected: Option<Type>,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        match &e.kind {
+            ExprKind::Int(lit) => self.codegen_int(lit, expected, e.span),
+            ExprKind::Float(lit) => self.codegen_float(lit, expected, e.span),
+            ExprKind::Str(text) => Ok((self.codegen_str_constant(text.as_bytes()), Type::Str)),
+            ExprKind::ByteStr(bytes) => Ok((self.codegen_str_constant(bytes), Type::Str)),
+            ExprKind::Char(c) => {
+                let value = self.context.i32_type().const_int(*c as u64, false);
+                Ok((value.into(), Type::Char))
+            }
+            ExprKind::Ident(name) => {
+                let local = self.lookup_local(name, e.span)?;
+                Ok((self.load_local(&local, name)?, local.ty))
+            }
+            ExprKind::Binary { op, lhs, rhs } if op.is_logical() => {
+                let value = self.codegen_logical(*op, lhs, rhs)?;
+                Ok((value, Type::Bool))
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                // Operands share one type; take it from context, then from either side.
+                let operand_ty = if op.is_comparison() {
+                    self.natural_type(lhs).or_else(|| self.natural_type(rhs))
+                } else {
+                    expected
+                        .or_else(|| self.natural_type(lhs))
+                        .or_else(|| self.natural_type(rhs))
+                };
+                let (lhs, ty) = self.codegen_typed_expr(lhs, operand_ty)?;
+                let rhs = self.codegen_expr(rhs, Some(ty.clone()))?;
+                self.codegen_binary(*op, lhs, rhs, &ty, e.span)
+            }
+            ExprKind::Unary { op, operand } => {
+                let operand_ty = match op {
+                    UnaryOp::Not => Some(Type::Bool),
+                    UnaryOp::Neg | UnaryOp::BitNot => {
+                        expected.or_else(|| self.natural_type(operand))
+                    }
+                };
+                let (value, ty) = self.codegen_typed_expr(operand, operand_ty)?;
+                self.codegen_unary(*op, value, ty, e.span)
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                overload,
+            } => {
+                if let Some((enum_name, variant, name_span)) = self.variant_path(callee) {
+                    return self.codegen_variant(enum_name, variant, name_span, args, e.span);
+                }
+                self.codegen_call(callee, args, *overload)
+            }
+            ExprKind::If {
+                cond,
+                then_expr,
+                else_expr,
+            } => self.codegen_if_expr(cond, then_expr, else_expr, expected),
+            ExprKind::Loop(lp) => self.codegen_loop_expr(lp, expected),
+            ExprKind::StructLit { name, fields } => self.codegen_struct_lit(name, fields, e.span),
+            ExprKind::Match(m) => Ok(self
+                .codegen_match(m, true, expected, e.span)?
+                .expect("a match used as a value has one")),
+            ExprKind::Field {
+                base,
+                name,
+                name_span,
+            } => {
+                if let Some((enum_name, variant, name_span)) = self.variant_path(e) {
+                    return self.codegen_variant(enum_name, variant, name_span, &[], e.span);
+                }
+                if let Some(place) = self.place(e)? {
+                    return Ok((self.load_local(&place, name)?, place.ty));
+                }
+                // A field of a temporary, such as a call's result.
+                let (value, ty) = self.codegen_typed_expr(base, None)?;
+                let (index, ty) = self.field(&ty, name, *name_span)?;
+                let value =
+                    self.builder
+                        .build_extract_value(value.into_struct_value(), index, name)?;
+                Ok((value, ty))
+            }
+        }
+    }
+
+    /// Builds the struct field by field, evaluating the field values in the
+    /// order they are written.
+    fn codegen_struct_lit(
+        &mut self,
+        name: &str,
+        inits: &[FieldInit],
+        span: Span,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let info = self.structs.get(name).cloned().ok_or_else(|| {
+            CodegenError::new(format!("cannot find struct '{name}' in this scope"), span)
+        })?;
+        let mut values = vec![None; info.fields.len()];
+        for init in inits {
+            let index = info
+                .fields
+                .iter()
+                .position(|(f, _)| *f == init.name)
+                .ok_or_else(|| {
+                    CodegenError::new(
+                        format!("struct '{name}' has no field named '{}'", init.name),
+                        init.name_span,
+                    )
+                })?;
+            if values[index].is_some() {
+                return Err(CodegenError::new(
+                    format!("field '{}' is specified more than once", init.name),
+                    init.name_span,
+                ));
+            }
+            let ty = info.fields[index].1.clone();
+            values[index] = Some(self.codegen_expr(&init.value, Some(ty))?);
+        }
+
+        let mut value = info.llvm.get_undef();
+        for (index, (field_value, (field, _))) in values.into_iter().zip(&info.fields).enumerate() {
+            let field_value = field_value.ok_or_else(|| {
+                CodegenError::new(
+                    format!("missing field '{field}' in literal of struct '{name}'"),
+                    span,
+                )
+            })?;
+            value = self
+                .builder
+                .build_insert_value(value, field_value, index as u32, field)?
+                .into_struct_value();
+        }
+        Ok((value.into(), Type::Struct(Rc::from(name))))
+    }
+
+    /// `Enum.Variant` as `(enum, variant, span of the variant)`, unless the
+    /// name before the `.` is a local rather than an enum.
+    fn variant_path<'e>(&self, e: &'e Expr) -> Option<(&'e str, &'e str, Span)> {
+        let ExprKind::Field {
+            base,
+            name,
+            name_span,
+        } = &e.kind
+        else {
+            return None;
+        };
+        let ExprKind::Ident(enum_name) = &base.kind else {
+            return None;
+        };
+        (self.enums.contains_key(enum_name) && self.find_local(enum_name).is_none()).then_some((
+            enum_name.as_str(),
+            name.as_str(),
+            *name_span,
+        ))
+    }
+
+    fn variant_index(&self, enum_name: &str, variant: &str, span: Span) -> CodegenResult<usize> {
+        self.enums[enum_name]
+            .variants
+            .iter()
+            .position(|v| v.name == variant)
+            .ok_or_else(|| {
+                CodegenError::new(
+                    format!("enum '{enum_name}' has no variant named '{variant}'"),
+                    span,
+                )
+            })
+    }
+
+    /// The enum value at `ptr`'s union, where every variant's payload starts.
+    fn payload_ptr(&self, ty: &Type, ptr: PointerValue<'ctx>) -> CodegenResult<PointerValue<'ctx>> {
+        Ok(self
+            .builder
+            .build_struct_gep(self.llvm_type(ty), ptr, 1, "payload")?)
+    }
+
+    /// Builds `Enum.Variant(args)` in a stack slot: the tag, then each
+    /// field in the payload.
+    fn codegen_variant(
+        &mut self,
+        enum_name: &str,
+        variant_name: &str,
+        name_span: Span,
+        args: &[Arg],
+        span: Span,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let index = self.variant_index(enum_name, variant_name, name_span)?;
+        let info = self.enums[enum_name].clone();
+        let variant = &info.variants[index];
+        if let Some(arg) = args.iter().find(|a| a.name.is_some()) {
+            return Err(CodegenError::new(
+                format!(
+                    "the fields of variant '{enum_name}.{variant_name}' have no names; pass them by position"
+                ),
+                arg.span,
+            ));
+        }
+        if args.len() != variant.fields.len() {
+            return Err(CodegenError::new(
+                format!(
+                    "variant '{enum_name}.{variant_name}' has {} field{} but {} value{} {} supplied",
+                    variant.fields.len(),
+                    if variant.fields.len() == 1 { "" } else { "s" },
+                    args.len(),
+                    if args.len() == 1 { "" } else { "s" },
+                    if args.len() == 1 { "was" } else { "were" },
+                ),
+                span,
+            ));
+        }
+        let mut values = Vec::with_capacity(args.len());
+        for (arg, ty) in args.iter().zip(&variant.fields) {
+            values.push(self.codegen_expr(&arg.value, Some(ty.clone()))?);
+        }
+
+        let ty = Type::Enum(Rc::from(enum_name));
+        let slot = self.build_entry_alloca(&ty, variant_name)?;
+        let tag_ptr = self
+            .builder
+            .build_struct_gep(self.llvm_type(&ty), slot, 0, "tag")?;
+        self.builder
+            .build_store(tag_ptr, info.tag.const_int(index as u64, false))?;
+        if !values.is_empty() {
+            let payload = self.payload_ptr(&ty, slot)?;
+            for (i, value) in values.into_iter().enumerate() {
+                let field =
+                    self.builder
+                        .build_struct_gep(variant.payload, payload, i as u32, "field")?;
+                self.builder.build_store(field, value)?;
+            }
+        }
+        let value = self
+            .builder
+            .build_load(self.llvm_type(&ty), slot, variant_name)?;
+        Ok((value, ty))
+    }
+
+    /// Lowers a `match` whose patterns semantic analysis checked cover every
+    /// value. Each arm tests its pattern and falls through to the next arm's
+    /// test on a mismatch, so the last test's failure is unreachable. With `value`, each arm's expression is a
+    /// value, typed by `expected` or else by the first arm, and the values
+    /// are merged with a phi.
+    fn codegen_match(
+        &mut self,
+        m: &Match,
+        value: bool,
+        expected: Option<Type>,
+        span: Span,
+    ) -> CodegenResult<Option<(BasicValueEnum<'ctx>, Type)>> {
+        let (scrutinee, ty) = self.codegen_typed_expr(&m.scrutinee, None)?;
+        let slot = self.build_entry_alloca(&ty, "match.scrutinee")?;
+        self.builder.build_store(slot, scrutinee)?;
+        let function = self.current_function();
+        let end_bb = self.context.append_basic_block(function, "match.end");
+        let mut value_ty = expected;
+        let mut incoming = Vec::new();
+        for arm in &m.arms {
+            let next_bb = self.context.append_basic_block(function, "match.next");
+            let mut bindings = Vec::new();
+            self.codegen_pattern_test(&arm.pattern, slot, &ty, next_bb, &mut bindings)?;
+            self.scopes.push(HashMap::new());
+            let result = self.codegen_arm(arm, bindings, value, &mut value_ty, &mut incoming);
+            self.scopes.pop();
+            result?;
+            self.branch_if_open(end_bb)?;
+            self.builder.position_at_end(next_bb);
+        }
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(end_bb);
+        if !value {
+            return Ok(None);
+        }
+        let ty = value_ty.ok_or_else(|| {
+            CodegenError::new("cannot infer the type of a `match` with no arms", span)
+        })?;
+        if incoming.is_empty() {
+            // Nothing reaches the end block; any value will do.
+            return Ok(Some((self.llvm_type(&ty).const_zero(), ty)));
+        }
+        let phi = self.builder.build_phi(self.llvm_type(&ty), "match.value")?;
+        for (v, block) in &incoming {
+            phi.add_incoming(&[(v, *block)]);
+        }
+        Ok(Some((phi.as_basic_value(), ty)))
+    }
+
+    /// Runs the body of an arm whose pattern matched, in a scope holding
+    /// copies of the values the pattern binds.
+    fn codegen_arm(
+        &mut self,
+        arm: &MatchArm,
+        bindings: Vec<(String, Local<'ctx>)>,
+        value: bool,
+        value_ty: &mut Option<Type>,
+        incoming: &mut Vec<(BasicValueEnum<'ctx>, BasicBlock<'ctx>)>,
+    ) -> CodegenResult<()> {
+        for (name, matched) in bindings {
+            let bound = self.load_local(&matched, &name)?;
+            let ptr = self.build_entry_alloca(&matched.ty, &name)?;
+            self.builder.build_store(ptr, bound)?;
+            let local = Local {
+                ptr,
+                ty: matched.ty,
+                mutable: true,
+            };
+            self.scopes
+                .last_mut()
+                .expect("pushed for the arm")
+                .insert(name, local);
+        }
+        match &arm.body {
+            ArmBody::Block(block) => self.codegen_block(block),
+            ArmBody::Expr(e) if value => {
+                let (v, found) = self.codegen_typed_expr(e, value_ty.clone())?;
+                match value_ty {
+                    Some(expected) if *expected != found => {
+                        return Err(CodegenError::new(
+                            format!("mismatched types: expected {expected}, found {found}"),
+                            e.span,
+                        ));
+                    }
+                    _ => *value_ty = Some(found),
+                }
+                incoming.push((v, self.current_block()));
+                Ok(())
+            }
+            ArmBody::Expr(e) => {
+                self.codegen_typed_expr(e, None)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// The constant an integer or char pattern, checked against the type
+    /// `ty` it matches, compares with.
+    fn pattern_literal(&self, pattern: &Pattern, ty: &Type) -> IntValue<'ctx> {
+        let bits = match (&pattern.kind, ty) {
+            (PatternKind::Int { negative, lit }, Type::Int { bits, .. }) => {
+                let value = if *negative {
+                    lit.value.wrapping_neg()
+                } else {
+                    lit.value
+                };
+                value & (u128::MAX >> (128 - bits))
+            }
+            (PatternKind::Char(c), _) => *c as u128,
+            _ => unreachable!("only literal patterns compare with a constant"),
+        };
+        let words = [bits as u64, (bits >> 64) as u64];
+        self.llvm_type(ty)
+            .into_int_type()
+            .const_int_arbitrary_precision(&words)
+    }
+
+    /// Branches to `fail` unless the value of type `ty` at `ptr` matches
+    /// `pattern`, which was checked against `ty`, and leaves the builder
+    /// where it does. A variant's tag is tested before its payload is read.
+    /// The slots of the values the pattern binds are added to `bindings`.
+    fn codegen_pattern_test(
+        &mut self,
+        pattern: &Pattern,
+        ptr: PointerValue<'ctx>,
+        ty: &Type,
+        fail: BasicBlock<'ctx>,
+        bindings: &mut Vec<(String, Local<'ctx>)>,
+    ) -> CodegenResult<()> {
+        let matched = match &pattern.kind {
+            PatternKind::Wildcard => return Ok(()),
+            PatternKind::Binding(name) => {
+                let local = Local {
+                    ptr,
+                    ty: ty.clone(),
+                    mutable: true,
+                };
+                bindings.push((name.clone(), local));
+                return Ok(());
+            }
+            PatternKind::Int { .. } | PatternKind::Char(_) => {
+                let expected = self.pattern_literal(pattern, ty);
+                let value = self
+                    .builder
+                    .build_load(self.llvm_type(ty), ptr, "value")?
+                    .into_int_value();
+                self.builder
+                    .build_int_compare(IntPredicate::EQ, value, expected, "matches")?
+            }
+            PatternKind::Variant {
+                enum_name, variant, ..
+            } => {
+                let index = self.variant_index(enum_name, variant, pattern.span)?;
+                let info = &self.enums[enum_name.as_str()];
+                let tag_ptr = self
+                    .builder
+                    .build_struct_gep(self.llvm_type(ty), ptr, 0, "tag")?;
+                let tag = self
+                    .builder
+                    .build_load(info.tag, tag_ptr, "tag")?
+                    .into_int_value();
+                let expected = info.tag.const_int(index as u64, false);
+                self.builder
+                    .build_int_compare(IntPredicate::EQ, tag, expected, "is.variant")?
+            }
+        };
+        let next_bb = self
+            .context
+            .append_basic_block(self.current_function(), "match.test");
+        self.builder
+            .build_conditional_branch(matched, next_bb, fail)?;
+        self.builder.position_at_end(next_bb);
+
+        if let PatternKind::Variant {
+            enum_name,
+            variant,
+            fields,
+        } = &pattern.kind
+            && !fields.is_empty()
+        {
+            let index = self.variant_index(enum_name, variant, pattern.span)?;
+            let variant = self.enums[enum_name.as_str()].variants[index].clone();
+            let payload = self.payload_ptr(ty, ptr)?;
+            for (i, (field, field_ty)) in fields.iter().zip(&variant.fields).enumerate() {
+                let field_ptr =
+                    self.builder
+                        .build_struct_gep(variant.payload, payload, i as u32, "field")?;
+                self.codegen_pattern_test(field, field_ptr, field_ty, fail, bindings)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn codegen_int(
+        &self,
+        lit: &IntLiteral,
+        expected: Option<Type>,
+        span: Span,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let (signed, bits) = match (lit.suffix, expected) {
+            (Some(suffix), _) => (suffix.signed, suffix.bits),
+            (None, Some(Type::Int { signed, bits })) => (signed, bits),
+            (None, Some(other)) => {
+                return Err(CodegenError::new(
+                    format!("mismatched types: expected {other}, found integer literal"),
+                    span,
+                ));
+            }
+            (None, None) => (true, 32),
+        };
+
+        let suffix = IntSuffix { signed, bits };
+        if lit.value > suffix.max_literal() {
+            return Err(CodegenError::new(
+                format!(
+                    "integer literal {} out of range for {}",
+                    lit.value,
+                    suffix.type_name()
+                ),
+                span,
+            ));
+        }
+
+        let int_ty = self.context.custom_width_int_type(bits);
+        let words = [lit.value as u64, (lit.value >> 64) as u64];
+        let value = int_ty.const_int_arbitrary_precision(&words);
+        Ok((value.into(), Type::Int { signed, bits }))
+    }
+
+    fn codegen_float(
+        &self,
+        lit: &FloatLiteral,
+        expected: Option<Type>,
+        span: Span,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let kind = match (lit.suffix, expected) {
+            (Some(kind), _) => kind,
+            (None, Some(Type::Float(kind))) => kind,
+            (None, Some(other)) => {
+                return Err(CodegenError::new(
+                    format!("mismatched types: expected {other}, found float literal"),
+                    span,
+                ));
+            }
+            (None, None) => FloatKind::F64,
+        };
+
+        // SAFETY: the lexer only produces decimal float text that LLVM can parse.
+        let value = unsafe { self.float_type(kind).const_float_from_string(&lit.text) };
+        Ok((value.into(), Type::Float(kind)))
+    }
+
+    /// Emits `bytes` as a private NUL-terminated constant and returns a pointer to it.
+    fn codegen_str_constant(&self, bytes: &[u8]) -> BasicValueEnum<'ctx> {
+        let init = self.context.const_string(bytes, true);
+        let global = self.module.add_global(init.get_type(), None, ".str");
+        global.set_initializer(&init);
+        global.set_linkage(Linkage::Private);
+        global.set_constant(true);
+        global.set_unnamed_addr(true);
+        global.as_pointer_value().into()
+    }
+
+    fn codegen_call(
+        &mut self,
+        callee: &Expr,
+        args: &[Arg],
+        overload: Option<usize>,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let (value, returns) = self.build_call(callee, args, overload)?;
+        if let [ty] = &returns[..] {
+            return Ok((value, ty.clone()));
+        }
+        let ExprKind::Ident(name) = &callee.kind else {
+            unreachable!("only named functions are called");
+        };
+        let bindings: Vec<String> = returns
+            .iter()
+            .zip('a'..)
+            .map(|(ty, binding)| format!("{ty} {binding}"))
+            .collect();
+        Err(CodegenError::new(
+            format!(
+                "function '{name}' returns {} values; bind them with a declaration such as `{} = {name}(..);`",
+                returns.len(),
+                bindings.join(", "),
+            ),
+            callee.span,
+        ))
+    }
+
+    /// The overload of a named function that semantic analysis picked for a call.
+    fn overload(&self, callee: &Expr, index: usize) -> &Signature<'ctx> {
+        let ExprKind::Ident(name) = &callee.kind else {
+            unreachable!("only named functions are called");
+        };
+        &self.functions[name][index]
+    }
+
+    /// Emits a call to the overload semantic analysis picked, whose arguments
+    /// are in parameter order, and returns its raw result, a struct when the
+    /// function has several return values, along with the return types.
+    fn build_call(
+        &mut self,
+        callee: &Expr,
+        args: &[Arg],
+        overload: Option<usize>,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Vec<Type>)> {
+        let index = overload.expect("calls are resolved before codegen");
+        let sig = self.overload(callee, index).clone();
+        if sig.entry
+            && let ExprKind::Ident(name) = &callee.kind
+        {
+            return Err(CodegenError::new(
+                format!(
+                    "the entry function '{name}' cannot be called; it runs when the program starts"
+                ),
+                callee.span,
+            ));
+        }
+        let mut values: Vec<BasicMetadataValueEnum> = Vec::with_capacity(args.len());
+        for (arg, ty) in args.iter().zip(&sig.params) {
+            values.push(self.codegen_expr(&arg.value, Some(ty.clone()))?.into());
+        }
+        let call = self.builder.build_call(sig.value, &values, "call")?;
+        let value = call
+            .try_as_basic_value()
+            .basic()
+            .expect("Xenon functions always return a value");
+        Ok((value, sig.returns))
+    }
 
-        match e {
-            Expr::Int(v) => Ok(i32t.const_int(*v as u64, true)),
-            Expr::Ident(name) => Err(format!("MVP: unknown identifier '{name}' (no vars yet)")),
-            Expr::Return(_) => Err("MVP: nested return not allowed".into()),
+    /// Evaluates an expression yielding several values: a call to a function
+    /// with several return values, or a checked operator.
+    fn codegen_multi_expr(
+        &mut self,
+        e: &Expr,
+        expected: &[Type],
+    ) -> CodegenResult<Vec<BasicValueEnum<'ctx>>> {
+        let (values, found) = match &e.kind {
+            ExprKind::Call {
+                callee,
+                args,
+                overload,
+            } => {
+                let (value, returns) = self.build_call(callee, args, *overload)?;
+                let values = match returns.len() {
+                    1 => vec![value],
+                    n => {
+                        let value = value.into_struct_value();
+                        let mut values = Vec::with_capacity(n);
+                        for i in 0..n as u32 {
+                            values.push(self.builder.build_extract_value(value, i, "ret")?);
+                        }
+                        values
+                    }
+                };
+                (values, returns)
+            }
+            ExprKind::Binary { op, lhs, rhs } if op.unchecked().is_some() => {
+                let (value, overflow, ty) =
+                    self.codegen_checked(*op, lhs, rhs, expected[0].clone(), e.span)?;
+                (vec![value, overflow], vec![ty, Type::Bool])
+            }
+            _ => (Vec::new(), Vec::new()),
+        };
+        if found.is_empty() || found.len() != expected.len() {
+            return Err(CodegenError::new(
+                format!(
+                    "expected {} values, found {}",
+                    expected.len(),
+                    found.len().max(1)
+                ),
+                e.span,
+            ));
+        }
+        if let Some((want, got)) = expected.iter().zip(&found).find(|(a, b)| a != b) {
+            return Err(CodegenError::new(
+                format!("mismatched types: expected {want}, found {got}"),
+                e.span,
+            ));
         }
+        Ok(values)
+    }
+
+    /// A checked operator: the result of its unchecked form, and whether the
+    /// exact result overflowed the operand type.
+    fn codegen_checked(
+        &mut self,
+        op: BinaryOp,
+        lhs: &Expr,
+        rhs: &Expr,
+        expected: Type,
+        span: Span,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, BasicValueEnum<'ctx>, Type)> {
+        let base = op.unchecked().expect("caller checked the operator");
+        let (lhs, ty) = self.codegen_typed_expr(lhs, Some(expected))?;
+        let rhs = self.codegen_expr(rhs, Some(ty.clone()))?;
+        let Type::Int { signed, .. } = ty else {
+            return Err(cannot_apply(op.symbol(), &ty, span));
+        };
+        let (lhs, rhs) = (lhs.into_int_value(), rhs.into_int_value());
+        let sign = if signed { 's' } else { 'u' };
+        let name = match base {
+            BinaryOp::Add | BinaryOp::AddWrap | BinaryOp::AddSat => "add",
+            BinaryOp::Sub | BinaryOp::SubWrap | BinaryOp::SubSat => "sub",
+            _ => "mul",
+        };
+        let pair = self
+            .call_intrinsic(
+                &format!("llvm.{sign}{name}.with.overflow"),
+                &[lhs.into(), rhs.into()],
+            )?
+            .into_struct_value();
+        let overflow = self.builder.build_extract_value(pair, 1, "ov")?;
+        let value = match base {
+            BinaryOp::AddSat | BinaryOp::SubSat | BinaryOp::MulSat => self
+                .codegen_int_binary(base, lhs, rhs, signed)?
+                .expect("integers support saturating arithmetic"),
+            _ => self.builder.build_extract_value(pair, 0, name)?,
+        };
+        Ok((value, overflow, ty))
     }
+
+    /// Evaluates only the chosen arm and merges the two results with a phi.
+    fn codegen_if_expr(
+        &mut self,
+        cond: &Expr,
+        then_expr: &Expr,
+        else_expr: &Expr,
+        expected: Option<Type>,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let cond = self.codegen_expr(cond, Some(Type::Bool))?.into_int_value();
+        let function = self.current_function();
+        let then_bb = self.context.append_basic_block(function, "if.then");
+        let else_bb = self.context.append_basic_block(function, "if.else");
+        let end_bb = self.context.append_basic_block(function, "if.end");
+        self.builder
+            .build_conditional_branch(cond, then_bb, else_bb)?;
+
+        // Both arms share a type: the context's, else whichever arm knows its own.
+        let arm_ty = expected
+            .or_else(|| self.natural_type(then_expr))
+            .or_else(|| self.natural_type(else_expr));
+
+        self.builder.position_at_end(then_bb);
+        let (then_val, then_ty) = self.codegen_typed_expr(then_expr, arm_ty)?;
+        let then_end = self.current_block();
+        self.builder.build_unconditional_branch(end_bb)?;
+
+        self.builder.position_at_end(else_bb);
+        let (else_val, else_ty) = self.codegen_typed_expr(else_expr, Some(then_ty.clone()))?;
+        if else_ty != then_ty {
+            return Err(CodegenError::new(
+                format!("`if` and `else` have incompatible types: {then_ty} and {else_ty}"),
+                else_expr.span,
+            ));
+        }
+        let else_end = self.current_block();
+        self.builder.build_unconditional_branch(end_bb)?;
+
+        self.builder.position_at_end(end_bb);
+        let phi = self
+            .builder
+            .build_phi(self.llvm_type(&then_ty), "if.value")?;
+        phi.add_incoming(&[(&then_val, then_end), (&else_val, else_end)]);
+        Ok((phi.as_basic_value(), then_ty))
+    }
+
+    /// `&&` and `||` only evaluate their right side when it decides the result.
+    fn codegen_logical(
+        &mut self,
+        op: BinaryOp,
+        lhs: &Expr,
+        rhs: &Expr,
+    ) -> CodegenResult<BasicValueEnum<'ctx>> {
+        let lhs = self.codegen_expr(lhs, Some(Type::Bool))?.into_int_value();
+        if op == BinaryOp::Xor {
+            let rhs = self.codegen_expr(rhs, Some(Type::Bool))?.into_int_value();
+            return Ok(self.builder.build_xor(lhs, rhs, "xor")?.into());
+        }
+
+        let (prefix, short_circuit) = match op {
+            BinaryOp::And => ("and", false),
+            _ => ("or", true),
+        };
+        let lhs_block = self.current_block();
+        let function = self.current_function();
+        let rhs_block = self
+            .context
+            .append_basic_block(function, &format!("{prefix}.rhs"));
+        let end_block = self
+            .context
+            .append_basic_block(function, &format!("{prefix}.end"));
+        if short_circuit {
+            self.builder
+                .build_conditional_branch(lhs, end_block, rhs_block)?;
+        } else {
+            self.builder
+                .build_conditional_branch(lhs, rhs_block, end_block)?;
+        }
+
+        self.builder.position_at_end(rhs_block);
+        let rhs = self.codegen_expr(rhs, Some(Type::Bool))?;
+        // `rhs` may have branched itself; the phi must name the block it ended in.
+        let rhs_end = self.current_block();
+        self.builder.build_unconditional_branch(end_block)?;
+
+        self.builder.position_at_end(end_block);
+        let bool_ty = self.context.bool_type();
+        let phi = self.builder.build_phi(bool_ty, prefix)?;
+        let short_value = bool_ty.const_int(short_circuit as u64, false);
+        phi.add_incoming(&[(&short_value, lhs_block), (&rhs, rhs_end)]);
+        Ok(phi.as_basic_value())
+    }
+
+    fn codegen_unary(
+        &self,
+        op: UnaryOp,
+        value: BasicValueEnum<'ctx>,
+        ty: Type,
+        span: Span,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        let b = &self.builder;
+        let value: BasicValueEnum = match (op, &ty) {
+            (UnaryOp::Neg, Type::Int { signed: true, .. }) => {
+                b.build_int_neg(value.into_int_value(), "neg")?.into()
+            }
+            (UnaryOp::Neg, Type::Float(_)) => {
+                b.build_float_neg(value.into_float_value(), "neg")?.into()
+            }
+            (UnaryOp::Not, Type::Bool) | (UnaryOp::BitNot, Type::Int { .. }) => {
+                b.build_not(value.into_int_value(), "not")?.into()
+            }
+            _ => return Err(cannot_apply(op.symbol(), &ty, span)),
+        };
+        Ok((value, ty))
+    }
+
+    fn codegen_binary(
+        &self,
+        op: BinaryOp,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+        ty: &Type,
+        span: Span,
+    ) -> CodegenResult<(BasicValueEnum<'ctx>, Type)> {
+        if op.unchecked().is_some() {
+            return Err(CodegenError::new(
+                format!(
+                    "checked operator `{0}` yields a value and an overflow flag; bind both with a declaration such as `{ty} result, bool overflow = a {0} b;`",
+                    op.symbol(),
+                ),
+                span,
+            ));
+        }
+
+        let result_ty = if op.is_comparison() {
+            Type::Bool
+        } else {
+            ty.clone()
+        };
+        let value = match ty {
+            Type::Float(_) => {
+                self.codegen_float_binary(op, lhs.into_float_value(), rhs.into_float_value())?
+            }
+            Type::Int { signed, .. } => {
+                self.codegen_int_binary(op, lhs.into_int_value(), rhs.into_int_value(), *signed)?
+            }
+            Type::Bool
+                if matches!(
+                    op,
+                    BinaryOp::Eq
+                        | BinaryOp::NotEq
+                        | BinaryOp::BitAnd
+                        | BinaryOp::BitOr
+                        | BinaryOp::BitXor
+                ) =>
+            {
+                self.codegen_int_binary(op, lhs.into_int_value(), rhs.into_int_value(), false)?
+            }
+            Type::Char if op.is_comparison() => {
+                self.codegen_int_binary(op, lhs.into_int_value(), rhs.into_int_value(), false)?
+            }
+            Type::Bool | Type::Char | Type::Str | Type::Struct(_) | Type::Enum(_) => None,
+        };
+        value
+            .map(|value| (value, result_ty))
+            .ok_or_else(|| cannot_apply(op.symbol(), ty, span))
+    }
+
+    /// Returns `None` for operators integers don't support.
+    fn codegen_int_binary(
+        &self,
+        op: BinaryOp,
+        lhs: IntValue<'ctx>,
+        rhs: IntValue<'ctx>,
+        signed: bool,
+    ) -> CodegenResult<Option<BasicValueEnum<'ctx>>> {
+        let b = &self.builder;
+        let cmp = |signed_pred, unsigned_pred| {
+            let pred = if signed { signed_pred } else { unsigned_pred };
+            b.build_int_compare(pred, lhs, rhs, "cmp")
+        };
+        let sign = if signed { "s" } else { "u" };
+        let value = match op {
+            // Plain arithmetic wraps in two's complement, the same as the `%` suffix.
+            BinaryOp::Add | BinaryOp::AddWrap => b.build_int_add(lhs, rhs, "add")?,
+            BinaryOp::Sub | BinaryOp::SubWrap => b.build_int_sub(lhs, rhs, "sub")?,
+            BinaryOp::Mul | BinaryOp::MulWrap => b.build_int_mul(lhs, rhs, "mul")?,
+            BinaryOp::AddSat => self
+                .call_intrinsic(&format!("llvm.{sign}add.sat"), &[lhs.into(), rhs.into()])?
+                .into_int_value(),
+            BinaryOp::SubSat => self
+                .call_intrinsic(&format!("llvm.{sign}sub.sat"), &[lhs.into(), rhs.into()])?
+                .into_int_value(),
+            BinaryOp::MulSat => self.codegen_saturating_mul(lhs, rhs, signed)?,
+            BinaryOp::Div if signed => b.build_int_signed_div(lhs, rhs, "div")?,
+            BinaryOp::Div => b.build_int_unsigned_div(lhs, rhs, "div")?,
+            BinaryOp::Rem if signed => b.build_int_signed_rem(lhs, rhs, "rem")?,
+            BinaryOp::Rem => b.build_int_unsigned_rem(lhs, rhs, "rem")?,
+            BinaryOp::Pow => self.codegen_int_pow(lhs, rhs)?,
+            BinaryOp::BitAnd => b.build_and(lhs, rhs, "and")?,
+            BinaryOp::BitOr => b.build_or(lhs, rhs, "or")?,
+            BinaryOp::BitXor => b.build_xor(lhs, rhs, "xor")?,
+            BinaryOp::Shl => b.build_left_shift(lhs, rhs, "shl")?,
+            BinaryOp::Shr => b.build_right_shift(lhs, rhs, signed, "shr")?,
+            BinaryOp::Eq => b.build_int_compare(IntPredicate::EQ, lhs, rhs, "cmp")?,
+            BinaryOp::NotEq => b.build_int_compare(IntPredicate::NE, lhs, rhs, "cmp")?,
+            BinaryOp::Lt => cmp(IntPredicate::SLT, IntPredicate::ULT)?,
+            BinaryOp::Gt => cmp(IntPredicate::SGT, IntPredicate::UGT)?,
+            BinaryOp::LtEq => cmp(IntPredicate::SLE, IntPredicate::ULE)?,
+            BinaryOp::GtEq => cmp(IntPredicate::SGE, IntPredicate::UGE)?,
+            _ => return Ok(None),
+        };
+        Ok(Some(value.into()))
+    }
+
+    /// Returns `None` for operators floats don't support.
+    fn codegen_float_binary(
+        &self,
+        op: BinaryOp,
+        lhs: FloatValue<'ctx>,
+        rhs: FloatValue<'ctx>,
+    ) -> CodegenResult<Option<BasicValueEnum<'ctx>>> {
+        let b = &self.builder;
+        let cmp = |pred| b.build_float_compare(pred, lhs, rhs, "cmp");
+        Ok(Some(match op {
+            BinaryOp::Add => b.build_float_add(lhs, rhs, "add")?.into(),
+            BinaryOp::Sub => b.build_float_sub(lhs, rhs, "sub")?.into(),
+            BinaryOp::Mul => b.build_float_mul(lhs, rhs, "mul")?.into(),
+            BinaryOp::Div => b.build_float_div(lhs, rhs, "div")?.into(),
+            BinaryOp::Rem => b.build_float_rem(lhs, rhs, "rem")?.into(),
+            BinaryOp::Pow => self.call_intrinsic("llvm.pow", &[lhs.into(), rhs.into()])?,
+            // Ordered predicates, except `!=` which must be true when either side is NaN.
+            BinaryOp::Eq => cmp(FloatPredicate::OEQ)?.into(),
+            BinaryOp::NotEq => cmp(FloatPredicate::UNE)?.into(),
+            BinaryOp::Lt => cmp(FloatPredicate::OLT)?.into(),
+            BinaryOp::Gt => cmp(FloatPredicate::OGT)?.into(),
+            BinaryOp::LtEq => cmp(FloatPredicate::OLE)?.into(),
+            BinaryOp::GtEq => cmp(FloatPredicate::OGE)?.into(),
+            _ => return Ok(None),
+        }))
+    }
+
+    /// `lhs *| rhs`: clamps to the type's bounds when the product overflows.
+    fn codegen_saturating_mul(
+        &self,
+        lhs: IntValue<'ctx>,
+        rhs: IntValue<'ctx>,
+        signed: bool,
+    ) -> CodegenResult<IntValue<'ctx>> {
+        let b = &self.builder;
+        let name = if signed {
+            "llvm.smul.with.overflow"
+        } else {
+            "llvm.umul.with.overflow"
+        };
+        let pair = self
+            .call_intrinsic(name, &[lhs.into(), rhs.into()])?
+            .into_struct_value();
+        let product = b.build_extract_value(pair, 0, "mul")?.into_int_value();
+        let overflow = b.build_extract_value(pair, 1, "ov")?.into_int_value();
+
+        let ty = lhs.get_type();
+        let bound = if signed {
+            // The exact product is negative exactly when the operand signs differ.
+            let min_bits = 1u128 << (ty.get_bit_width() - 1);
+            let max_bits = min_bits - 1;
+            let max = ty.const_int_arbitrary_precision(&[max_bits as u64, (max_bits >> 64) as u64]);
+            let min = ty.const_int_arbitrary_precision(&[min_bits as u64, (min_bits >> 64) as u64]);
+            let negative = b.build_int_compare(
+                IntPredicate::SLT,
+                b.build_xor(lhs, rhs, "sign")?,
+                ty.const_zero(),
+                "neg",
+            )?;
+            b.build_select(negative, min, max, "bound")?
+                .into_int_value()
+        } else {
+            ty.const_all_ones()
+        };
+        Ok(b.build_select(overflow, bound, product, "sat")?
+            .into_int_value())
+    }
+
+    /// `base ** exp` by square-and-multiply. The exponent is read as unsigned.
+    fn codegen_int_pow(
+        &self,
+        base: IntValue<'ctx>,
+        exp: IntValue<'ctx>,
+    ) -> CodegenResult<IntValue<'ctx>> {
+        let b = &self.builder;
+        let ty = base.get_type();
+        let entry = self.current_block();
+        let function = self.current_function();
+        let head = self.context.append_basic_block(function, "pow.head");
+        let body = self.context.append_basic_block(function, "pow.body");
+        let end = self.context.append_basic_block(function, "pow.end");
+        b.build_unconditional_branch(head)?;
+
+        b.position_at_end(head);
+        let acc = b.build_phi(ty, "pow.acc")?;
+        let square = b.build_phi(ty, "pow.base")?;
+        let rest = b.build_phi(ty, "pow.exp")?;
+        let (acc_val, square_val, rest_val) = (
+            acc.as_basic_value().into_int_value(),
+            square.as_basic_value().into_int_value(),
+            rest.as_basic_value().into_int_value(),
+        );
+        let done = b.build_int_compare(IntPredicate::EQ, rest_val, ty.const_zero(), "pow.done")?;
+        b.build_conditional_branch(done, end, body)?;
+
+        b.position_at_end(body);
+        let one = ty.const_int(1, false);
+        let odd = b.build_int_compare(
+            IntPredicate::NE,
+            b.build_and(rest_val, one, "pow.bit")?,
+            ty.const_zero(),
+            "pow.odd",
+        )?;
+        let product = b.build_int_mul(acc_val, square_val, "pow.mul")?;
+        let next_acc = b.build_select(odd, product, acc_val, "pow.acc.next")?;
+        let next_square = b.build_int_mul(square_val, square_val, "pow.sq")?;
+        let next_rest = b.build_right_shift(rest_val, one, false, "pow.shr")?;
+        b.build_unconditional_branch(head)?;
+
+        acc.add_incoming(&[(&one, entry), (&next_acc, body)]);
+        square.add_incoming(&[(&base, entry), (&next_square, body)]);
+        rest.add_incoming(&[(&exp, entry), (&next_rest, body)]);
+
+        b.position_at_end(end);
+        Ok(acc_val)
+    }
+
+    /// Calls the overloaded LLVM intrinsic `name`, instantiated for the type of `args[0]`.
+    fn call_intrinsic(
+        &self,
+        name: &str,
+        args: &[BasicValueEnum<'ctx>],
+    ) -> CodegenResult<BasicValueEnum<'ctx>> {
+        let declaration = Intrinsic::find(name)
+            .and_then(|i| i.get_declaration(&self.module, &[args[0].get_type()]))
+            .ok_or_else(|| CodegenError::backend(format!("missing LLVM intrinsic {name}")))?;
+        let args: Vec<BasicMetadataValueEnum> = args.iter().map(|&a| a.into()).collect();
+        let call = self.builder.build_call(declaration, &args, "")?;
+        Ok(call
+            .try_as_basic_value()
+            .basic()
+            .expect("intrinsic returns a value"))
+    }
+}
+
+impl From<inkwell::builder::BuilderError> for CodegenError {
+    fn from(e: inkwell::builder::BuilderError) -> Self {
+        CodegenError::backend(format!("LLVM builder failed: {e:?}"))
+    }
+}
+
+/// `_X<len><name>P<params>R<returns>`, each type written `<len><type>`:
+/// `fn foo(u32 x) -> u64` is `_X3fooP3u32R3u64`. Each part of a qualified
+/// name is written the same way, so `geo.area` is `_X3geo4areaP..`.
+fn mangle(name: &str, params: &[Type], returns: &[Type]) -> String {
+    let types = |types: &[Type]| -> String {
+        types
+            .iter()
+            .map(|ty| {
+                let ty = ty.to_string();
+                format!("{}{ty}", ty.len())
+            })
+            .collect()
+    };
+    let name: String = name
+        .split('.')
+        .map(|part| format!("{}{part}", part.len()))
+        .collect();
+    format!("_X{name}P{}R{}", types(params), types(returns))
+}
+
+fn cannot_apply(op: &str, ty: &Type, span: Span) -> CodegenError {
+    CodegenError::new(format!("cannot apply `{op}` to {ty}"), span)
 }
 
 pub fn emit_object_and_ir(
     program: &Program,
+    entry: Option<usize>,
     out_obj: &Path,
     out_ll: Option<&Path>,
-) -> Result<(), String> {
-    // 1) Init target (native backend)
-    Target::initialize_native(&InitializationConfig::default())
-        .map_err(|msg| format!("initialize_native failed: {msg}"))?;
-
-    // 2) Build IR module
+) -> CodegenResult<()> {
+    let tm = host_target_machine()?;
     let context = Context::create();
-    let cg = CodeGen::new(&context, "xenon_mvp");
-    let module = cg.compile_program(program)?;
+    let module = CodeGen::new(&context, "xenon_mvp", &tm).compile_program(program, entry)?;
 
     // Optional: write LLVM IR text for debugging
     if let Some(ll_path) = out_ll {
         module
             .print_to_file(ll_path)
-            .map_err(|e| format!("print_to_file(.ll) failed: {e}"))?;
+            .map_err(|e| CodegenError::backend(format!("print_to_file(.ll) failed: {e}")))?;
     }
 
-    // 3) Configure triple + target machine
-    let triple = TargetMachine::get_default_triple();
-    module.set_triple(&triple);
+    // Emit object file using write_to_file (TargetMachine API) :contentReference[oaicite:3]{index=3}
+    tm.write_to_file(&module, FileType::Object, out_obj)
+        .map_err(|e| CodegenError::backend(format!("write_to_file(.o) failed: {e}")))?;
+
+    Ok(())
+}
 
-    let target =
-        Target::from_triple(&triple).map_err(|e| format!("Target::from_triple failed: {e}"))?;
+/// A target machine for the host, which modules are compiled for.
+pub fn host_target_machine() -> CodegenResult<TargetMachine> {
+    Target::initialize_native(&InitializationConfig::default())
+        .map_err(|msg| CodegenError::backend(format!("initialize_native failed: {msg}")))?;
+
+    let triple = TargetMachine::get_default_triple();
+    let target = Target::from_triple(&triple)
+        .map_err(|e| CodegenError::backend(format!("Target::from_triple failed: {e}")))?;
 
     let cpu = TargetMachine::get_host_cpu_name().to_string();
     let features = TargetMachine::get_host_cpu_features().to_string();
 
-    let tm = target
+    target
         .create_target_machine(
             &triple,
             cpu.as_str(),
@@ -117,13 +2343,7 @@ pub fn emit_object_and_ir(
             RelocMode::Default,
             CodeModel::Default,
         )
-        .ok_or("create_target_machine returned None")?;
-
-    // 4) Emit object file using write_to_file (TargetMachine API) :contentReference[oaicite:3]{index=3}
-    tm.write_to_file(&module, FileType::Object, out_obj)
-        .map_err(|e| format!("write_to_file(.o) failed: {e}"))?;
-
-    Ok(())
+        .ok_or_else(|| CodegenError::backend("create_target_machine returned None"))
 }
 
 pub fn default_output_paths(out_dir: &Path) -> (PathBuf, PathBuf) {
@@ -11,28 +11,28 @@ pub fn run(cli: Cli) -> i32 {
                 return 0;
             }
             let config = CompileConfig::from(args);
-            let session = match Session::from_compile_config(config) {
+            let mut session = match Session::from_compile_config(config) {
                 Ok(session) => session,
                 Err(err) => {
                     eprintln!("Error: {err}");
                     return 2;
                 }
             };
-            compile(&session)
+            compile(&mut session)
         }
         Commands::Check(args) => {
             if handle_print_requests(&args.session.print, args.session.sysroot.as_deref()) {
                 return 0;
             }
             let config = CheckConfig::from(args);
-            let session = match Session::from_check_config(config) {
+            let mut session = match Session::from_check_config(config) {
                 Ok(session) => session,
                 Err(err) => {
                     eprintln!("Error: {err}");
                     return 2;
                 }
             };
-            check(&session)
+            check(&mut session)
         }
     }
 }
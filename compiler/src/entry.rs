@@ -0,0 +1,74 @@
+//! The program's entry point, run between module resolution and codegen.
+//!
+//! A program starts at the function written `entry fn` or marked
+//! `#[entry]`. It takes no parameters and returns nothing or one integer,
+//! which becomes the process's exit status. Codegen calls it from a
+//! generated C `main`; see docs/language/program-entry.md.
+
+use crate::ast::{Function, Program};
+use crate::config::CrateType;
+use crate::error::{EntryError, EntryResult};
+use crate::types::Type;
+
+/// The entry function of `program`, if it has one, after checking there is
+/// only one and that its signature can start a program.
+pub fn find_entry(program: &Program) -> EntryResult<Option<&Function>> {
+    Ok(entry_index(program)?.map(|index| &program.functions[index]))
+}
+
+/// Like [`find_entry`], but gives the index of the entry function in
+/// `program.functions`.
+fn entry_index(program: &Program) -> EntryResult<Option<usize>> {
+    let mut entries = program
+        .functions
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| f.is_entry());
+    let Some((index, entry)) = entries.next() else {
+        return Ok(None);
+    };
+    if let Some((_, other)) = entries.next() {
+        return Err(EntryError::new(
+            format!(
+                "function '{}' is marked as the entry point, but '{}' already is",
+                other.name, entry.name
+            ),
+            other.span,
+        ));
+    }
+    if let Some(param) = entry.params.first() {
+        return Err(EntryError::new(
+            format!("the entry function '{}' cannot take parameters", entry.name),
+            param.span,
+        ));
+    }
+    match entry.returns.as_slice() {
+        [] => {}
+        [ret] if matches!(Type::from_name(&ret.ty), Some(Type::Int { .. })) => {}
+        [ret, ..] => {
+            let types: Vec<&str> = entry.returns.iter().map(|r| r.ty.as_str()).collect();
+            return Err(EntryError::new(
+                format!(
+                    "the entry function '{}' must return nothing or an integer, not {}",
+                    entry.name,
+                    types.join(", ")
+                ),
+                ret.span,
+            ));
+        }
+    }
+    Ok(Some(index))
+}
+
+/// Checks that a crate of `crate_type` has the entry point it needs: an
+/// executable must have one. Gives the index of the entry function in
+/// `program.functions`, for codegen to call from the C `main`.
+pub fn check_entry(program: &Program, crate_type: CrateType) -> EntryResult<Option<usize>> {
+    let entry = entry_index(program)?;
+    if crate_type == CrateType::Bin && entry.is_none() {
+        return Err(EntryError::missing(
+            "crate type `bin` needs an entry function; write `entry fn main() { .. }` or mark a function `#[entry]`",
+        ));
+    }
+    Ok(entry)
+}
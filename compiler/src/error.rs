@@ -1,43 +1,268 @@
-use crate::tokens::Span;
+use crate::tokens::{IntSuffix, Span};
+
+/// Declares a diagnostic holding a message and the span of the code it is
+/// about, displayed as `<message> (span <start>..<end>)`.
+macro_rules! spanned_diagnostic {
+    ($(#[$doc:meta])* pub struct $name:ident;) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name {
+            pub message: String,
+            pub span: Span,
+        }
+
+        impl $name {
+            pub fn new(message: impl Into<String>, span: Span) -> Self {
+                Self {
+                    message: message.into(),
+                    span,
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "{} (span {}..{})",
+                    self.message, self.span.start, self.span.end
+                )
+            }
+        }
+    };
+}
+
+/// Why lexing failed at a given span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LexErrorKind {
+    /// No token starts with this character.
+    #[default]
+    UnknownCharacter,
+    /// A string literal is missing its closing quote.
+    UnterminatedString,
+    /// A `/*` comment is never closed; the span points at the opening.
+    UnterminatedBlockComment,
+    /// A char literal is missing its closing quote.
+    UnterminatedChar,
+    /// A char literal does not hold exactly one character.
+    InvalidCharLiteral,
+    /// An unknown or malformed `\` escape in a string or char literal.
+    InvalidEscape,
+    /// A byte string contains a non-ASCII character; use `\xNN` instead.
+    NonAsciiByteString,
+    /// An integer literal has no digits or a digit invalid for its radix.
+    InvalidIntLiteral,
+    /// An integer literal suffix is not a valid `iN`/`uN` type.
+    InvalidIntSuffix,
+    /// A float literal has an exponent without digits.
+    InvalidFloatLiteral,
+    /// An integer literal does not fit its suffix type, or `u128` when unsuffixed.
+    IntLiteralOutOfRange(Option<IntSuffix>),
+}
+
+impl std::fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCharacter => write!(f, "unknown character"),
+            Self::UnterminatedString => write!(f, "unterminated string literal"),
+            Self::UnterminatedBlockComment => write!(f, "unterminated block comment"),
+            Self::UnterminatedChar => write!(f, "unterminated char literal"),
+            Self::InvalidCharLiteral => {
+                write!(f, "char literal must contain exactly one character")
+            }
+            Self::InvalidEscape => write!(f, "invalid escape sequence"),
+            Self::NonAsciiByteString => write!(f, "non-ASCII character in byte string"),
+            Self::InvalidIntLiteral => write!(f, "invalid integer literal"),
+            Self::InvalidIntSuffix => write!(
+                f,
+                "invalid integer suffix, expected iN or uN with N in 1..={}",
+                IntSuffix::MAX_BITS
+            ),
+            Self::InvalidFloatLiteral => write!(f, "invalid float literal"),
+            Self::IntLiteralOutOfRange(Some(suffix)) => {
+                write!(f, "integer literal out of range for {}", suffix.type_name())
+            }
+            Self::IntLiteralOutOfRange(None) => write!(f, "integer literal out of range"),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LexError {
+    pub kind: LexErrorKind,
     pub span: Span,
 }
 
 impl std::fmt::Display for LexError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Lexing error at span {:?}", self.span)
+        write!(
+            f,
+            "{} (span {}..{})",
+            self.kind, self.span.start, self.span.end
+        )
     }
 }
 impl std::error::Error for LexError {}
 pub type LexResult<T> = Result<T, LexError>;
 
+/// Suspicious but valid source found while lexing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexWarning {
+    /// An identifier mixes scripts (e.g. Latin and Cyrillic), so it may be
+    /// confused with a different identifier that looks the same.
+    MixedScriptIdentifier { name: String, span: Span },
+}
+
+impl LexWarning {
+    pub fn span(&self) -> Span {
+        match self {
+            LexWarning::MixedScriptIdentifier { span, .. } => *span,
+        }
+    }
+
+    pub fn shift(&mut self, base: usize) {
+        match self {
+            LexWarning::MixedScriptIdentifier { span, .. } => *span = span.shifted(base),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            LexWarning::MixedScriptIdentifier { name, .. } => {
+                format!("identifier `{name}` mixes scripts and may be confusable")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for LexWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.span();
+        write!(f, "{} (span {}..{})", self.message(), span.start, span.end)
+    }
+}
+
+spanned_diagnostic! {
+    pub struct ParseError;
+}
+
+impl std::error::Error for ParseError {}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+spanned_diagnostic! {
+    /// Error raised while resolving calls against the signatures they name.
+    pub struct ResolveError;
+}
+
+impl std::error::Error for ResolveError {}
+
+pub type ResolveResult<T> = Result<T, ResolveError>;
+
+spanned_diagnostic! {
+    /// Error raised by the semantic checks on a resolved program, such as a
+    /// call no single overload fits.
+    pub struct SemaError;
+}
+
+impl std::error::Error for SemaError {}
+
+pub type SemaResult<T> = Result<T, SemaError>;
+
+spanned_diagnostic! {
+    /// Suspicious but valid code found by the semantic checks, such as a
+    /// `match` arm that can never run.
+    pub struct SemaWarning;
+}
+
+spanned_diagnostic! {
+    /// Error raised while finding the modules a program imports, or resolving
+    /// the names one module uses from another.
+    pub struct ModuleError;
+}
+
+impl std::error::Error for ModuleError {}
+
+pub type ModuleResult<T> = Result<T, ModuleError>;
+
+/// Error raised checking a program's entry point. A missing entry function
+/// has nothing to point at, so it has no span.
 #[derive(Debug)]
-pub struct ParseError {
+pub struct EntryError {
     pub message: String,
-    pub span: Span,
+    pub span: Option<Span>,
 }
 
-impl ParseError {
+impl EntryError {
     pub fn new(message: impl Into<String>, span: Span) -> Self {
         Self {
             message: message.into(),
-            span,
+            span: Some(span),
+        }
+    }
+
+    pub fn missing(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: None,
         }
     }
 }
 
-impl std::fmt::Display for ParseError {
+impl std::fmt::Display for EntryError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} (span {}..{})",
-            self.message, self.span.start, self.span.end
-        )
+        match self.span {
+            Some(span) => write!(f, "{} (span {}..{})", self.message, span.start, span.end),
+            None => write!(f, "{}", self.message),
+        }
     }
 }
 
-impl std::error::Error for ParseError {}
+impl std::error::Error for EntryError {}
 
-pub type ParseResult<T> = Result<T, ParseError>;
+pub type EntryResult<T> = Result<T, EntryError>;
+
+spanned_diagnostic! {
+    /// An attribute the compiler doesn't know, or one written in the wrong
+    /// form or in the wrong place.
+    pub struct AttributeError;
+}
+
+impl std::error::Error for AttributeError {}
+
+/// Error raised while lowering to LLVM. Errors caused by the program carry
+/// the span of the offending code; backend failures have none.
+#[derive(Debug)]
+pub struct CodegenError {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl CodegenError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span: Some(span),
+        }
+    }
+
+    pub fn backend(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: None,
+        }
+    }
+}
+
+impl std::fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} (span {}..{})", self.message, span.start, span.end),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {}
+
+pub type CodegenResult<T> = Result<T, CodegenError>;
@@ -0,0 +1,185 @@
+//! Exhaustiveness and reachability of `match` arms.
+//!
+//! Follows Maranget's "Warnings for pattern matching": a pattern is *useful*
+//! after some rows of patterns when some value matches it but none of the
+//! rows. An arm is unreachable when its pattern is not useful after the arms
+//! before it, and a match is exhaustive when `_` is not useful after all of
+//! its arms. The search for a useful `_` also builds an example of a value
+//! no arm matches, which is what diagnostics show.
+
+/// The values of a matched type, as far as patterns can tell them apart.
+#[derive(Debug, Clone)]
+pub enum Space {
+    /// An enum: its name, and each variant's name and fields.
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<Space>)>,
+    },
+    /// Integers, chars and structs: too many values to list, so only a
+    /// pattern matching anything covers them all.
+    Open,
+}
+
+/// A pattern checked against the type it matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pat {
+    /// `_` or a binding.
+    Wild,
+    /// A variant, by index, with one pattern per field.
+    Variant(usize, Vec<Pat>),
+    /// An integer or char literal, by its bits.
+    Literal(u128),
+}
+
+/// Whether some value matches `pat` but none of `earlier`.
+pub fn is_useful(space: &Space, earlier: &[Pat], pat: &Pat) -> bool {
+    let rows: Vec<Vec<Pat>> = earlier.iter().map(|p| vec![p.clone()]).collect();
+    useful(&rows, std::slice::from_ref(pat), &[space])
+}
+
+/// Values that none of `arms` match, written as patterns, one per variant
+/// of an enum left uncovered. Empty when the arms are exhaustive.
+pub fn missing_patterns(space: &Space, arms: &[Pat]) -> Vec<String> {
+    let rows: Vec<Vec<Pat>> = arms.iter().map(|p| vec![p.clone()]).collect();
+    match space {
+        Space::Enum { name, variants } => variants
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (variant, fields))| {
+                let spaces: Vec<&Space> = fields.iter().collect();
+                let witness = witness(&specialize(&rows, index, fields.len()), &spaces)?;
+                Some(render_variant(name, variant, &witness))
+            })
+            .collect(),
+        Space::Open => witness(&rows, &[space])
+            .map(|mut w| w.remove(0))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn useful(rows: &[Vec<Pat>], q: &[Pat], spaces: &[&Space]) -> bool {
+    let Some((head, rest)) = q.split_first() else {
+        return rows.is_empty();
+    };
+    match head {
+        Pat::Variant(index, fields) => {
+            let field_spaces = variant_fields(spaces[0], *index);
+            let q: Vec<Pat> = fields.iter().chain(rest).cloned().collect();
+            let spaces: Vec<&Space> = field_spaces
+                .iter()
+                .chain(spaces[1..].iter().copied())
+                .collect();
+            useful(&specialize(rows, *index, fields.len()), &q, &spaces)
+        }
+        Pat::Literal(value) => {
+            let rows: Vec<Vec<Pat>> = rows
+                .iter()
+                .filter(|row| matches!(&row[0], Pat::Wild) || row[0] == Pat::Literal(*value))
+                .map(|row| row[1..].to_vec())
+                .collect();
+            useful(&rows, rest, &spaces[1..])
+        }
+        Pat::Wild => match spaces[0] {
+            Space::Enum { variants, .. } if covers_all_variants(rows, variants.len()) => {
+                variants.iter().enumerate().any(|(index, (_, fields))| {
+                    let q: Vec<Pat> = std::iter::repeat_n(Pat::Wild, fields.len())
+                        .chain(rest.iter().cloned())
+                        .collect();
+                    let spaces: Vec<&Space> =
+                        fields.iter().chain(spaces[1..].iter().copied()).collect();
+                    useful(&specialize(rows, index, fields.len()), &q, &spaces)
+                })
+            }
+            _ => useful(&default_rows(rows), rest, &spaces[1..]),
+        },
+    }
+}
+
+/// A list of patterns, one per column, that no row matches, or `None` when
+/// the rows cover every value.
+fn witness(rows: &[Vec<Pat>], spaces: &[&Space]) -> Option<Vec<String>> {
+    let Some((space, rest)) = spaces.split_first() else {
+        return rows.is_empty().then(Vec::new);
+    };
+    let Space::Enum { name, variants } = space else {
+        let mut w = witness(&default_rows(rows), rest)?;
+        w.insert(0, "_".to_string());
+        return Some(w);
+    };
+    if covers_all_variants(rows, variants.len()) {
+        return variants
+            .iter()
+            .enumerate()
+            .find_map(|(index, (variant, fields))| {
+                let spaces: Vec<&Space> = fields.iter().chain(rest.iter().copied()).collect();
+                let mut w = witness(&specialize(rows, index, fields.len()), &spaces)?;
+                let rest = w.split_off(fields.len());
+                let mut result = vec![render_variant(name, variant, &w)];
+                result.extend(rest);
+                Some(result)
+            });
+    }
+    let mut w = witness(&default_rows(rows), rest)?;
+    let head = if rows.iter().all(|row| matches!(row[0], Pat::Wild)) {
+        "_".to_string()
+    } else {
+        let (variant, fields) = (0..variants.len())
+            .find(|&index| !rows.iter().any(|row| row[0].is_variant(index)))
+            .map(|index| &variants[index])
+            .expect("some variant is not covered");
+        render_variant(name, variant, &vec!["_".to_string(); fields.len()])
+    };
+    w.insert(0, head);
+    Some(w)
+}
+
+impl Pat {
+    fn is_variant(&self, index: usize) -> bool {
+        matches!(self, Pat::Variant(i, _) if *i == index)
+    }
+}
+
+fn variant_fields(space: &Space, index: usize) -> &[Space] {
+    match space {
+        Space::Enum { variants, .. } => &variants[index].1,
+        Space::Open => unreachable!("variant patterns are only checked against enums"),
+    }
+}
+
+/// Whether every variant heads some row, so that `_` need not be tried
+/// against the rows that only match anything.
+fn covers_all_variants(rows: &[Vec<Pat>], count: usize) -> bool {
+    (0..count).all(|index| rows.iter().any(|row| row[0].is_variant(index)))
+}
+
+/// The rows that can match variant `index` in the first column, with that
+/// column replaced by the variant's `arity` fields.
+fn specialize(rows: &[Vec<Pat>], index: usize, arity: usize) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let fields = match &row[0] {
+                Pat::Wild => vec![Pat::Wild; arity],
+                Pat::Variant(i, fields) if *i == index => fields.clone(),
+                _ => return None,
+            };
+            Some(fields.into_iter().chain(row[1..].iter().cloned()).collect())
+        })
+        .collect()
+}
+
+/// The rows matching anything in the first column, without that column.
+fn default_rows(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter(|row| matches!(row[0], Pat::Wild))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+fn render_variant(enum_name: &str, variant: &str, fields: &[String]) -> String {
+    if fields.is_empty() {
+        format!("{enum_name}.{variant}")
+    } else {
+        format!("{enum_name}.{variant}({})", fields.join(", "))
+    }
+}
@@ -1,6 +1,19 @@
-use crate::error::{LexError, LexResult};
-use crate::tokens::{Token, tokenize};
+use crate::error::LexResult;
+use crate::tokens::{
+    Lexed, LosslessLexed, Token, tokenize, tokenize_lossless, tokenize_recovering,
+};
 
 pub fn lex(input: &str) -> LexResult<Vec<Token>> {
-    tokenize(input).map_err(|span| LexError { span })
+    tokenize(input)
+}
+
+/// Lexes the whole input, collecting every error instead of stopping at the first.
+pub fn lex_recovering(input: &str) -> Lexed {
+    tokenize_recovering(input)
+}
+
+/// Lexes keeping whitespace and comments, for tools that must reproduce the
+/// source. Like [`lex_recovering`], errors come back as error tokens.
+pub fn lex_lossless(input: &str) -> LosslessLexed {
+    tokenize_lossless(input)
 }
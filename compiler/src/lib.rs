@@ -1,12 +1,20 @@
 pub mod ast;
+pub mod attributes;
 pub mod cli;
 pub mod codegen;
 pub mod config;
 pub mod driver;
+pub mod entry;
 pub mod error;
+pub mod exhaustiveness;
 pub mod lexer;
 pub mod link;
+pub mod modules;
 pub mod parser;
 pub mod pipeline;
+pub mod resolve;
+pub mod sema;
 pub mod session;
+pub mod source_map;
 pub mod tokens;
+pub mod types;
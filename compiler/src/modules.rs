@@ -0,0 +1,636 @@
+//! Modules, run between parsing and call resolution.
+//!
+//! Every source file is a module with its own namespace. The first file
+//! given to the compiler is the root module; any other file is a module
+//! named after it, so `util.xe` is `util`. `import a.b;` loads `a/b.xe`,
+//! looked up next to the root file and then in each include path (`-I`),
+//! and binds it to `b`, or to `c` with `import a.b as c;`. The module's
+//! `pub` items are then available as `c.helper`, `c.Point` and
+//! `c.Shape.Circle`.
+//!
+//! [`resolve_modules`] merges every module into one [`Program`] for the
+//! later passes, naming the items of a module after it (`a.b.helper`) and
+//! rewriting every reference to them, so that modules can define items with
+//! the same names. Items of the root module keep their names.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::ast::{
+    ArmBody, Block, EnumDecl, Expr, ExprKind, Function, Loop, LoopKind, Match, Pattern,
+    PatternKind, Program, Stmt, StmtKind, StructDecl,
+};
+use crate::error::{LexError, LexWarning, ModuleError, ModuleResult, ParseError};
+use crate::lexer::lex_recovering;
+use crate::parser::Parser;
+use crate::source_map::{FileId, SourceFile, SourceMap};
+use crate::tokens::Span;
+use crate::types::Type;
+
+/// A parsed source file.
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// The path it is imported by, `a.b`, or the name of its file without
+    /// the extension for a file given to the compiler.
+    pub name: String,
+    pub file: FileId,
+    pub program: Program,
+}
+
+/// The modules of a program, with the problems found loading them.
+#[derive(Debug, Default)]
+pub struct LoadedModules {
+    /// The root module first, then the other files given to the compiler,
+    /// then imported files in the order they were first imported.
+    pub modules: Vec<Module>,
+    pub lex_errors: Vec<LexError>,
+    pub lex_warnings: Vec<LexWarning>,
+    pub parse_errors: Vec<ParseError>,
+    pub errors: Vec<ModuleError>,
+}
+
+impl LoadedModules {
+    fn add(&mut self, name: String, file: &SourceFile) {
+        let lexed = lex_recovering(&file.content).shifted(file.start_pos);
+        let (program, parse_errors) = Parser::new(&lexed.tokens).parse_program();
+        self.lex_errors.extend(lexed.errors);
+        self.lex_warnings.extend(lexed.warnings);
+        self.parse_errors.extend(parse_errors);
+        self.modules.push(Module {
+            name,
+            file: file.id,
+            program,
+        });
+    }
+}
+
+/// Lexes and parses every file of `source_map`, then every file they import,
+/// transitively, adding imported files to `source_map`. Each file is loaded
+/// once however many modules import it, so modules may import each other.
+pub fn load_modules(source_map: &mut SourceMap, include_path: &[PathBuf]) -> LoadedModules {
+    let mut loaded = LoadedModules::default();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    let files: Vec<FileId> = source_map.files().iter().map(|f| f.id).collect();
+    for id in files {
+        let file = source_map.file(id);
+        let name = file
+            .path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        if by_name.contains_key(&name) {
+            loaded.errors.push(ModuleError::new(
+                format!("module '{name}' is defined by more than one file"),
+                Span::from(file.start_pos..file.start_pos),
+            ));
+            continue;
+        }
+        by_name.insert(name.clone(), loaded.modules.len());
+        loaded.add(name, file);
+    }
+
+    let root_dir = source_map
+        .files()
+        .first()
+        .and_then(|f| f.path.parent())
+        .map(Path::to_path_buf);
+    let dirs: Vec<PathBuf> = root_dir.into_iter().chain(include_path.to_vec()).collect();
+    let mut next = 0;
+    while next < loaded.modules.len() {
+        let imports = loaded.modules[next].program.imports.clone();
+        next += 1;
+        for import in imports {
+            let name = import.dotted_path();
+            if by_name.contains_key(&name) {
+                continue;
+            }
+            let relative = import.path.iter().collect::<PathBuf>().with_extension("xe");
+            let candidates: Vec<PathBuf> = dirs.iter().map(|dir| dir.join(&relative)).collect();
+            let Some(path) = candidates.iter().find(|path| path.is_file()) else {
+                let searched: Vec<String> = candidates
+                    .iter()
+                    .map(|path| format!("{}", path.display()))
+                    .collect();
+                loaded.errors.push(ModuleError::new(
+                    format!(
+                        "cannot find module '{name}'; looked for {}",
+                        searched.join(", ")
+                    ),
+                    import.path_span,
+                ));
+                continue;
+            };
+            match std::fs::read_to_string(path) {
+                Ok(content) => {
+                    let id = source_map.add_file(path.clone(), content);
+                    by_name.insert(name.clone(), loaded.modules.len());
+                    loaded.add(name, source_map.file(id));
+                }
+                Err(e) => loaded.errors.push(ModuleError::new(
+                    format!("couldn't read {path:?}: {e}"),
+                    import.path_span,
+                )),
+            }
+        }
+    }
+    loaded
+}
+
+/// Resolves the names every module uses and merges the modules into one
+/// program. `modules[0]` is the root module.
+pub fn resolve_modules(modules: Vec<Module>) -> ModuleResult<Program> {
+    let mut scopes = modules
+        .iter()
+        .enumerate()
+        .map(|(index, module)| ModuleScope::new(module, index == 0))
+        .collect::<ModuleResult<Vec<_>>>()?;
+    let by_name: HashMap<&str, usize> = modules
+        .iter()
+        .enumerate()
+        .map(|(index, module)| (module.name.as_str(), index))
+        .collect();
+    for (scope, module) in scopes.iter_mut().zip(&modules) {
+        for import in &module.program.imports {
+            let name = import.dotted_path();
+            let index = *by_name.get(name.as_str()).ok_or_else(|| {
+                ModuleError::new(format!("cannot find module '{name}'"), import.path_span)
+            })?;
+            let binding = import.binding();
+            if scope.imports.insert(binding.to_string(), index).is_some() {
+                return Err(ModuleError::new(
+                    format!("the name '{binding}' is imported more than once"),
+                    import.path_span,
+                ));
+            }
+        }
+    }
+
+    let mut program = Program {
+        imports: Vec::new(),
+        structs: Vec::new(),
+        enums: Vec::new(),
+        functions: Vec::new(),
+    };
+    for (module, scope) in modules.into_iter().zip(&scopes) {
+        let mut resolver = NameResolver {
+            modules: &scopes,
+            module: scope,
+            locals: Vec::new(),
+        };
+        for mut s in module.program.structs {
+            resolver.resolve_struct(&mut s)?;
+            program.structs.push(s);
+        }
+        for mut e in module.program.enums {
+            resolver.resolve_enum(&mut e)?;
+            program.enums.push(e);
+        }
+        for mut f in module.program.functions {
+            resolver.resolve_function(&mut f)?;
+            program.functions.push(f);
+        }
+    }
+    Ok(program)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeKind {
+    Struct,
+    Enum,
+}
+
+impl TypeKind {
+    fn describe(self) -> &'static str {
+        match self {
+            TypeKind::Struct => "struct",
+            TypeKind::Enum => "enum",
+        }
+    }
+}
+
+/// The items a module defines and the modules it imports.
+struct ModuleScope {
+    name: String,
+    /// Put before the names of the module's items in the merged program:
+    /// `a.b.`, or nothing for the root module.
+    prefix: String,
+    /// Whether each function is `pub`, by name.
+    functions: HashMap<String, bool>,
+    /// Each struct and enum by name, with whether it is `pub`.
+    types: HashMap<String, (TypeKind, bool)>,
+    /// The index of each imported module, by the name it is bound to.
+    imports: HashMap<String, usize>,
+}
+
+impl ModuleScope {
+    fn new(module: &Module, root: bool) -> ModuleResult<Self> {
+        let mut functions = HashMap::new();
+        for f in &module.program.functions {
+            if let Some(public) = functions.insert(f.name.clone(), f.public)
+                && public != f.public
+            {
+                return Err(ModuleError::new(
+                    format!(
+                        "the overloads of function '{}' must either all be `pub` or none be",
+                        f.name
+                    ),
+                    f.span,
+                ));
+            }
+        }
+        let mut types = HashMap::new();
+        for s in &module.program.structs {
+            types.insert(s.name.clone(), (TypeKind::Struct, s.public));
+        }
+        for e in &module.program.enums {
+            types.insert(e.name.clone(), (TypeKind::Enum, e.public));
+        }
+        Ok(Self {
+            name: module.name.clone(),
+            prefix: if root {
+                String::new()
+            } else {
+                format!("{}.", module.name)
+            },
+            functions,
+            types,
+            imports: HashMap::new(),
+        })
+    }
+}
+
+/// Rewrites the names one module uses to their names in the merged program.
+struct NameResolver<'a> {
+    modules: &'a [ModuleScope],
+    module: &'a ModuleScope,
+    /// Locals in scope, innermost scope last. A local hides an enum or an
+    /// imported module of the same name before a `.`.
+    locals: Vec<HashSet<String>>,
+}
+
+impl NameResolver<'_> {
+    fn resolve_struct(&mut self, s: &mut StructDecl) -> ModuleResult<()> {
+        for field in &mut s.fields {
+            field.ty = self.resolve_type(&field.ty, field.ty_span)?;
+        }
+        s.name = format!("{}{}", self.module.prefix, s.name);
+        Ok(())
+    }
+
+    fn resolve_enum(&mut self, e: &mut EnumDecl) -> ModuleResult<()> {
+        for variant in &mut e.variants {
+            for (ty, span) in &mut variant.fields {
+                *ty = self.resolve_type(ty, *span)?;
+            }
+        }
+        e.name = format!("{}{}", self.module.prefix, e.name);
+        Ok(())
+    }
+
+    fn resolve_function(&mut self, f: &mut Function) -> ModuleResult<()> {
+        self.locals = vec![HashSet::new()];
+        for param in &mut f.params {
+            param.ty = self.resolve_type(&param.ty, param.ty_span)?;
+            if let Some(default) = &mut param.default {
+                self.resolve_expr(default)?;
+            }
+            self.declare(&param.name);
+        }
+        for ret in &mut f.returns {
+            ret.ty = self.resolve_type(&ret.ty, ret.ty_span)?;
+            if let Some(name) = &ret.name {
+                self.declare(name);
+            }
+        }
+        self.resolve_block(&mut f.body)?;
+        f.name = format!("{}{}", self.module.prefix, f.name);
+        Ok(())
+    }
+
+    fn declare(&mut self, name: &str) {
+        self.locals
+            .last_mut()
+            .expect("inside a function")
+            .insert(name.to_string());
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.locals.iter().any(|scope| scope.contains(name))
+    }
+
+    /// The module imported as `name`, unless a local hides it.
+    fn imported(&self, name: &str) -> Option<&ModuleScope> {
+        if self.is_local(name) {
+            return None;
+        }
+        self.module.imports.get(name).map(|&i| &self.modules[i])
+    }
+
+    /// A built-in type, or a struct or enum: `Point` of this module, or
+    /// `geo.Point` of an imported one.
+    fn resolve_type(&self, name: &str, span: Span) -> ModuleResult<String> {
+        if Type::from_name(name).is_some() {
+            return Ok(name.to_string());
+        }
+        match self.type_item(name, span)? {
+            Some((name, _)) => Ok(name),
+            None => Err(ModuleError::new(format!("Unknown type '{name}'"), span)),
+        }
+    }
+
+    /// The merged name and kind of the struct or enum `name` refers to, or
+    /// `None` for an unqualified name this module does not define.
+    fn type_item(&self, name: &str, span: Span) -> ModuleResult<Option<(String, TypeKind)>> {
+        let Some((module_name, item)) = name.split_once('.') else {
+            return Ok(self
+                .module
+                .types
+                .get(name)
+                .map(|&(kind, _)| (format!("{}{name}", self.module.prefix), kind)));
+        };
+        let module = self.module.imports.get(module_name).ok_or_else(|| {
+            ModuleError::new(
+                format!("cannot find module '{module_name}' in this scope"),
+                span,
+            )
+        })?;
+        let module = &self.modules[*module];
+        match module.types.get(item) {
+            Some(&(kind, true)) => Ok(Some((format!("{}{item}", module.prefix), kind))),
+            Some(&(kind, false)) => Err(ModuleError::new(
+                format!(
+                    "{} '{item}' is private to module '{}'",
+                    kind.describe(),
+                    module.name
+                ),
+                span,
+            )),
+            None => Err(ModuleError::new(
+                format!("cannot find type '{item}' in module '{}'", module.name),
+                span,
+            )),
+        }
+    }
+
+    /// The merged name of function `item` of `module`, which must be `pub`
+    /// unless it is this module.
+    fn function_item(&self, module: &ModuleScope, item: &str, span: Span) -> ModuleResult<String> {
+        match module.functions.get(item) {
+            Some(&public) if public || std::ptr::eq(module, self.module) => {
+                Ok(format!("{}{item}", module.prefix))
+            }
+            Some(_) => Err(ModuleError::new(
+                format!("function '{item}' is private to module '{}'", module.name),
+                span,
+            )),
+            None if std::ptr::eq(module, self.module) => Err(ModuleError::new(
+                format!("cannot find function '{item}' in this scope"),
+                span,
+            )),
+            None => Err(ModuleError::new(
+                format!("cannot find function '{item}' in module '{}'", module.name),
+                span,
+            )),
+        }
+    }
+
+    fn resolve_block(&mut self, block: &mut Block) -> ModuleResult<()> {
+        self.locals.push(HashSet::new());
+        let result = block
+            .stmts
+            .iter_mut()
+            .try_for_each(|stmt| self.resolve_stmt(stmt));
+        self.locals.pop();
+        result
+    }
+
+    fn resolve_stmt(&mut self, stmt: &mut Stmt) -> ModuleResult<()> {
+        match &mut stmt.kind {
+            StmtKind::VarDecl(decl) => {
+                decl.ty = self.resolve_type(&decl.ty, decl.ty_span)?;
+                if let Some(init) = &mut decl.init {
+                    self.resolve_expr(init)?;
+                }
+                self.declare(&decl.name);
+                Ok(())
+            }
+            StmtKind::Destructure(decl) => {
+                self.resolve_expr(&mut decl.init)?;
+                for binding in &mut decl.bindings {
+                    binding.ty = self.resolve_type(&binding.ty, binding.ty_span)?;
+                    self.declare(&binding.name);
+                }
+                Ok(())
+            }
+            StmtKind::Assign { target, value, .. } => {
+                self.resolve_expr(target)?;
+                self.resolve_expr(value)
+            }
+            StmtKind::Increment(expr) | StmtKind::Decrement(expr) | StmtKind::Expr(expr) => {
+                self.resolve_expr(expr)
+            }
+            StmtKind::Return(values) => values.iter_mut().try_for_each(|v| self.resolve_expr(v)),
+            StmtKind::Block(block) => self.resolve_block(block),
+            StmtKind::Loop(lp) => self.resolve_loop(lp),
+            StmtKind::Break { value, .. } => match value {
+                Some(value) => self.resolve_expr(value),
+                None => Ok(()),
+            },
+            StmtKind::Continue { .. } | StmtKind::Error => Ok(()),
+            StmtKind::If {
+                cond,
+                then_block,
+                else_branch,
+            } => {
+                self.resolve_expr(cond)?;
+                self.resolve_block(then_block)?;
+                match else_branch {
+                    Some(else_branch) => self.resolve_stmt(else_branch),
+                    None => Ok(()),
+                }
+            }
+            StmtKind::Match(m) => self.resolve_match(m),
+        }
+    }
+
+    fn resolve_loop(&mut self, lp: &mut Loop) -> ModuleResult<()> {
+        // Variables declared in a `for` header are scoped to the loop.
+        self.locals.push(HashSet::new());
+        let result = self.resolve_loop_parts(lp);
+        self.locals.pop();
+        result?;
+        if let Some(default) = &mut lp.default {
+            self.resolve_expr(default)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_loop_parts(&mut self, lp: &mut Loop) -> ModuleResult<()> {
+        match &mut lp.kind {
+            LoopKind::While(cond) | LoopKind::DoWhile(cond) => self.resolve_expr(cond)?,
+            LoopKind::For { init, cond, update } => {
+                if let Some(init) = init {
+                    self.resolve_stmt(init)?;
+                }
+                if let Some(cond) = cond {
+                    self.resolve_expr(cond)?;
+                }
+                if let Some(update) = update {
+                    self.resolve_stmt(update)?;
+                }
+            }
+            LoopKind::Infinite => {}
+        }
+        self.resolve_block(&mut lp.body)?;
+        if let Some(else_block) = &mut lp.else_block {
+            self.resolve_block(else_block)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_match(&mut self, m: &mut Match) -> ModuleResult<()> {
+        self.resolve_expr(&mut m.scrutinee)?;
+        for arm in &mut m.arms {
+            self.locals.push(HashSet::new());
+            let result =
+                self.resolve_pattern(&mut arm.pattern)
+                    .and_then(|()| match &mut arm.body {
+                        ArmBody::Expr(e) => self.resolve_expr(e),
+                        ArmBody::Block(block) => self.resolve_block(block),
+                    });
+            self.locals.pop();
+            result?;
+        }
+        Ok(())
+    }
+
+    fn resolve_pattern(&mut self, pattern: &mut Pattern) -> ModuleResult<()> {
+        match &mut pattern.kind {
+            PatternKind::Wildcard | PatternKind::Int { .. } | PatternKind::Char(_) => Ok(()),
+            PatternKind::Binding(name) => {
+                self.declare(name);
+                Ok(())
+            }
+            PatternKind::Variant {
+                enum_name, fields, ..
+            } => {
+                *enum_name = match self.type_item(enum_name, pattern.span)? {
+                    Some((name, _)) => name,
+                    None => {
+                        return Err(ModuleError::new(
+                            format!("cannot find enum '{enum_name}' in this scope"),
+                            pattern.span,
+                        ));
+                    }
+                };
+                fields
+                    .iter_mut()
+                    .try_for_each(|field| self.resolve_pattern(field))
+            }
+        }
+    }
+
+    fn resolve_expr(&mut self, e: &mut Expr) -> ModuleResult<()> {
+        match &mut e.kind {
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::Str(_)
+            | ExprKind::ByteStr(_)
+            | ExprKind::Char(_)
+            | ExprKind::Ident(_) => Ok(()),
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.resolve_expr(lhs)?;
+                self.resolve_expr(rhs)
+            }
+            ExprKind::Unary { operand, .. } => self.resolve_expr(operand),
+            ExprKind::Call { callee, args, .. } => {
+                for arg in args.iter_mut() {
+                    self.resolve_expr(&mut arg.value)?;
+                }
+                self.resolve_callee(callee)
+            }
+            ExprKind::Loop(lp) => self.resolve_loop(lp),
+            ExprKind::If {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                self.resolve_expr(cond)?;
+                self.resolve_expr(then_expr)?;
+                self.resolve_expr(else_expr)
+            }
+            ExprKind::StructLit { name, fields } => {
+                *name = match self.type_item(name, e.span)? {
+                    Some((name, _)) => name,
+                    None => {
+                        return Err(ModuleError::new(
+                            format!("cannot find struct '{name}' in this scope"),
+                            e.span,
+                        ));
+                    }
+                };
+                fields
+                    .iter_mut()
+                    .try_for_each(|field| self.resolve_expr(&mut field.value))
+            }
+            ExprKind::Field { base, .. } => self.resolve_field_base(base),
+            ExprKind::Match(m) => self.resolve_match(m),
+        }
+    }
+
+    /// `helper(..)` calls a function of this module and `geo.area(..)` one
+    /// of an imported module. Functions are never hidden by locals.
+    fn resolve_callee(&mut self, callee: &mut Expr) -> ModuleResult<()> {
+        let name = match &callee.kind {
+            ExprKind::Ident(name) => self.function_item(self.module, name, callee.span)?,
+            ExprKind::Field {
+                base,
+                name,
+                name_span,
+            } => match &base.kind {
+                ExprKind::Ident(module) if self.imported(module).is_some() => {
+                    let module = self.imported(module).expect("checked above");
+                    self.function_item(module, name, *name_span)?
+                }
+                _ => return self.resolve_expr(callee),
+            },
+            _ => return self.resolve_expr(callee),
+        };
+        callee.kind = ExprKind::Ident(name);
+        Ok(())
+    }
+
+    /// The `base` of `base.name`, where `Shape` in `Shape.Circle` is an enum
+    /// of this module and `geo.Shape` one of an imported module.
+    fn resolve_field_base(&mut self, base: &mut Expr) -> ModuleResult<()> {
+        let name = match &base.kind {
+            ExprKind::Ident(name) if !self.is_local(name) => {
+                if self.imported(name).is_some() {
+                    return Ok(());
+                }
+                match self.type_item(name, base.span)? {
+                    Some((name, TypeKind::Enum)) => name,
+                    _ => {
+                        return Err(ModuleError::new(
+                            format!("cannot find value '{name}' in this scope"),
+                            base.span,
+                        ));
+                    }
+                }
+            }
+            ExprKind::Field {
+                base: module, name, ..
+            } if matches!(&module.kind, ExprKind::Ident(m) if self.imported(m).is_some()) => {
+                let ExprKind::Ident(module) = &module.kind else {
+                    unreachable!("matched above");
+                };
+                let path = format!("{module}.{name}");
+                self.type_item(&path, base.span)?
+                    .expect("qualified names resolve or fail")
+                    .0
+            }
+            _ => return self.resolve_expr(base),
+        };
+        base.kind = ExprKind::Ident(name);
+        Ok(())
+    }
+}
@@ -5,6 +5,14 @@ use crate::tokens::{Span, Token, TokenKind};
 pub struct Parser<'a> {
     tokens: &'a [Token],
     position: usize,
+    /// Labels of the loops whose bodies are being parsed, innermost last.
+    /// `break x` breaks out of loop `x` if it is one of these, and
+    /// otherwise breaks with the value of `x`.
+    labels: Vec<String>,
+    /// A label that was just parsed and will be claimed by the next loop.
+    pending_label: Option<Label>,
+    /// Errors recovered from so far; see [`Parser::parse_program`].
+    errors: Vec<ParseError>,
 }
 
 impl<'a> Parser<'a> {
@@ -12,6 +20,9 @@ impl<'a> Parser<'a> {
         Self {
             tokens,
             position: 0,
+            labels: Vec::new(),
+            pending_label: None,
+            errors: Vec::new(),
         }
     }
 
@@ -34,10 +45,40 @@ impl<'a> Parser<'a> {
     }
 
     fn peek(&self) -> Option<&'a Token> {
-        self.tokens.get(self.position)
+        self.tokens[self.position..]
+            .iter()
+            .find(|t| !is_ignored(t.kind))
+    }
+
+    /// Kind of the `n`th upcoming token, skipping ignored ones like [`Self::peek`].
+    fn peek_kind_at(&self, n: usize) -> Option<TokenKind> {
+        self.tokens[self.position..]
+            .iter()
+            .filter(|t| !is_ignored(t.kind))
+            .nth(n)
+            .map(|t| t.kind)
+    }
+
+    /// Span of the last consumed token.
+    fn prev_span(&self) -> Span {
+        self.tokens[..self.position]
+            .last()
+            .map(|t| t.span)
+            .unwrap_or(Span { start: 0, end: 0 })
+    }
+
+    fn at(&self, kind: TokenKind) -> bool {
+        self.peek().is_some_and(|t| t.kind == kind)
     }
 
     fn advance(&mut self) -> Option<&'a Token> {
+        while self
+            .tokens
+            .get(self.position)
+            .is_some_and(|t| is_ignored(t.kind))
+        {
+            self.position += 1;
+        }
         if self.position < self.tokens.len() {
             let idx = self.position;
             self.position += 1;
@@ -67,47 +108,1449 @@ impl<'a> Parser<'a> {
         }
     }
 
-    pub fn parse_program(&mut self) -> ParseResult<Program> {
-        let mut functions = Vec::new();
+    /// Parses every item, recovering from syntax errors so they can all be
+    /// reported at once. A statement that fails to parse becomes a
+    /// [`StmtKind::Error`] and parsing resumes after its `;`, before the `}`
+    /// closing its block, or at the next item. Struct fields, enum variants
+    /// and parameters that fail to parse are skipped the same way, and a
+    /// function whose header breaks off before its body gets a body holding
+    /// a single [`StmtKind::Error`], so the rest of the item is kept. Only an
+    /// item that breaks off before its name is dropped, and parsing resumes
+    /// at the next item.
+    pub fn parse_program(&mut self) -> (Program, Vec<ParseError>) {
+        let mut program = Program {
+            imports: Vec::new(),
+            structs: Vec::new(),
+            enums: Vec::new(),
+            functions: Vec::new(),
+        };
         while self.peek().is_some() {
-            functions.push(self.parse_function()?);
+            if let Err(e) = self.parse_item(&mut program) {
+                self.errors.push(e);
+                while self.peek().is_some_and(|t| !starts_item(t.kind)) {
+                    self.advance();
+                }
+            }
+        }
+        (program, std::mem::take(&mut self.errors))
+    }
+
+    /// An import, or a function, struct or enum with its attributes and an
+    /// optional `pub`. A function may also be marked `entry`.
+    fn parse_item(&mut self, program: &mut Program) -> ParseResult<()> {
+        if self.at(TokenKind::Import) {
+            program.imports.push(self.parse_import()?);
+            return Ok(());
+        }
+        let attrs = self.parse_attributes()?;
+        let public = self.at(TokenKind::Pub);
+        if public {
+            self.advance();
+        }
+        if self.at(TokenKind::Enum) {
+            program.enums.push(self.parse_enum(attrs, public)?);
+        } else if self.at(TokenKind::Struct) {
+            program.structs.push(self.parse_struct(attrs, public)?);
+        } else {
+            program.functions.push(self.parse_function(attrs, public)?);
+        }
+        Ok(())
+    }
+
+    /// `import <name> {. <name>} [as <alias>];`
+    fn parse_import(&mut self) -> ParseResult<Import> {
+        self.expect(TokenKind::Import)?;
+        let first = self.expect(TokenKind::Ident)?;
+        let mut path = vec![first.ident_value()?.to_string()];
+        let mut path_span = first.span;
+        while self.at(TokenKind::Period) {
+            self.advance();
+            let token = self.expect(TokenKind::Ident)?;
+            path.push(token.ident_value()?.to_string());
+            path_span = path_span.to(token.span);
+        }
+        let alias = if self.at(TokenKind::As) {
+            self.advance();
+            Some(self.expect(TokenKind::Ident)?.ident_value()?.to_string())
+        } else {
+            None
+        };
+        self.expect(TokenKind::Semicolon)?;
+        Ok(Import {
+            path,
+            alias,
+            path_span,
+        })
+    }
+
+    /// A type name, optionally qualified by the module it comes from:
+    /// `u32`, `geo.Point`.
+    fn parse_type(&mut self) -> ParseResult<(String, Span)> {
+        let token = self.expect(TokenKind::Ident)?;
+        let mut name = token.ident_value()?.to_string();
+        let mut span = token.span;
+        if self.at(TokenKind::Period) {
+            self.advance();
+            let item = self.expect(TokenKind::Ident)?;
+            name = format!("{name}.{}", item.ident_value()?);
+            span = span.to(item.span);
+        }
+        Ok((name, span))
+    }
+
+    /// `{#[<path> <args>]}`
+    fn parse_attributes(&mut self) -> ParseResult<Vec<Attribute>> {
+        let mut attrs = Vec::new();
+        while self.at(TokenKind::Hash) {
+            let hash = self.expect(TokenKind::Hash)?;
+            self.expect(TokenKind::LBracket)?;
+            let (name, name_span) = self.parse_attr_path()?;
+            let (args, _) = self.parse_attr_args(name_span)?;
+            let close = self.expect(TokenKind::RBracket)?;
+            attrs.push(Attribute {
+                name,
+                name_span,
+                args,
+                span: hash.span.to(close.span),
+            });
+        }
+        Ok(attrs)
+    }
+
+    /// `<name> {. <name>}`. `entry` is a keyword, but `#[entry]` names the
+    /// same thing.
+    fn parse_attr_path(&mut self) -> ParseResult<(String, Span)> {
+        let (first, mut span) = self.parse_attr_segment()?;
+        let mut name = first.to_string();
+        while self.at(TokenKind::Period) {
+            self.advance();
+            let (item, item_span) = self.parse_attr_segment()?;
+            name = format!("{name}.{item}");
+            span = span.to(item_span);
+        }
+        Ok((name, span))
+    }
+
+    fn parse_attr_segment(&mut self) -> ParseResult<(&'a str, Span)> {
+        let token = self.expect([TokenKind::Ident, TokenKind::Entry])?;
+        match token.kind {
+            TokenKind::Entry => Ok(("entry", token.span)),
+            _ => Ok((token.ident_value()?, token.span)),
         }
-        Ok(Program { functions })
     }
 
-    fn parse_function(&mut self) -> ParseResult<Function> {
+    /// `[= <literal> | (<item> {, <item>} [,])]`, where an item is a
+    /// literal or a path with its own arguments. Returns the arguments with
+    /// the span from `start` through them.
+    fn parse_attr_args(&mut self, start: Span) -> ParseResult<(AttrArgs, Span)> {
+        if self.at(TokenKind::Eq) {
+            self.advance();
+            let lit = self.parse_attr_lit()?;
+            let span = start.to(lit.span);
+            return Ok((AttrArgs::Value(lit), span));
+        }
+        if !self.at(TokenKind::LParen) {
+            return Ok((AttrArgs::None, start));
+        }
+        self.advance();
+        let mut items = Vec::new();
+        while self.peek().is_some_and(|t| t.kind != TokenKind::RParen) {
+            if self.at(TokenKind::Int) || self.at(TokenKind::Str) {
+                items.push(MetaItem::Lit(self.parse_attr_lit()?));
+            } else {
+                let (name, name_span) = self.parse_attr_path()?;
+                let (args, span) = self.parse_attr_args(name_span)?;
+                items.push(MetaItem::Meta { name, args, span });
+            }
+            if self.at(TokenKind::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        let close = self.expect(TokenKind::RParen)?;
+        Ok((AttrArgs::List(items), start.to(close.span)))
+    }
+
+    fn parse_attr_lit(&mut self) -> ParseResult<AttrLit> {
+        let token = self.expect([TokenKind::Int, TokenKind::Str])?;
+        let kind = match token.kind {
+            TokenKind::Int => AttrLitKind::Int(token.int_literal()?),
+            _ => AttrLitKind::Str(token.str_value()?.to_string()),
+        };
+        Ok(AttrLit {
+            kind,
+            span: token.span,
+        })
+    }
+
+    /// `struct <name> { {<type> <name>;} }`
+    fn parse_struct(&mut self, attrs: Vec<Attribute>, public: bool) -> ParseResult<StructDecl> {
+        self.expect(TokenKind::Struct)?;
+        let name_token = self.expect(TokenKind::Ident)?;
+        self.expect(TokenKind::LBrace)?;
+        let fields = self.parse_members(TokenKind::Semicolon, Self::parse_field);
+        Ok(StructDecl {
+            attrs,
+            public,
+            name: name_token.ident_value()?.to_string(),
+            fields,
+            span: name_token.span,
+        })
+    }
+
+    /// `<type> <name>;` in a struct body, after any attributes.
+    fn parse_field(&mut self) -> ParseResult<Field> {
+        let attrs = self.parse_attributes()?;
+        let (ty, ty_span) = self.parse_type()?;
+        let field_token = self.expect(TokenKind::Ident)?;
+        self.expect(TokenKind::Semicolon)?;
+        Ok(Field {
+            attrs,
+            ty,
+            ty_span,
+            name: field_token.ident_value()?.to_string(),
+            span: ty_span.to(field_token.span),
+        })
+    }
+
+    /// `enum <name> { [<variant> {, <variant>} [,]] }`, each `<name>` or
+    /// `<name>( [<type> {, <type>} [,]] )`.
+    fn parse_enum(&mut self, attrs: Vec<Attribute>, public: bool) -> ParseResult<EnumDecl> {
+        self.expect(TokenKind::Enum)?;
+        let name_token = self.expect(TokenKind::Ident)?;
+        self.expect(TokenKind::LBrace)?;
+        let variants = self.parse_members(TokenKind::Comma, Self::parse_variant);
+        Ok(EnumDecl {
+            attrs,
+            public,
+            name: name_token.ident_value()?.to_string(),
+            variants,
+            span: name_token.span,
+        })
+    }
+
+    /// A variant after any attributes, with the `,` after it unless it is
+    /// the last one.
+    fn parse_variant(&mut self) -> ParseResult<Variant> {
+        let attrs = self.parse_attributes()?;
+        let variant_token = self.expect(TokenKind::Ident)?;
+        let mut fields = Vec::new();
+        if self.at(TokenKind::LParen) {
+            self.advance();
+            while self.peek().is_some_and(|t| t.kind != TokenKind::RParen) {
+                fields.push(self.parse_type()?);
+                if self.at(TokenKind::Comma) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            self.expect(TokenKind::RParen)?;
+        }
+        if self.at(TokenKind::Comma) {
+            self.advance();
+        } else if !self.at(TokenKind::RBrace) {
+            // Without a `,` the body must end here.
+            self.expect(TokenKind::RBrace)?;
+        }
+        Ok(Variant {
+            attrs,
+            name: variant_token.ident_value()?.to_string(),
+            fields,
+            span: variant_token.span,
+        })
+    }
+
+    /// The members of a struct or enum body through its closing `}`. A
+    /// member that fails to parse is reported and skipped through the
+    /// `end` token that ends it, or up to the `}` or the next item; a
+    /// missing `}` is reported unless that member already was.
+    fn parse_members<T>(
+        &mut self,
+        end: TokenKind,
+        parse_member: fn(&mut Self) -> ParseResult<T>,
+    ) -> Vec<T> {
+        let mut members = Vec::new();
+        let mut failed = false;
+        while !self.at(TokenKind::RBrace) && self.peek().is_some() && !self.at_item() {
+            let start = self.position;
+            match parse_member(self) {
+                Ok(member) => {
+                    members.push(member);
+                    failed = false;
+                }
+                Err(e) => {
+                    self.errors.push(e);
+                    failed = true;
+                    self.skip_member(start, &[end]);
+                    // Step over the `end` token, or a stray closing bracket.
+                    if !self.at(TokenKind::RBrace) && !self.at_item() {
+                        self.advance();
+                    }
+                }
+            }
+        }
+        if let Err(e) = self.expect(TokenKind::RBrace)
+            && !failed
+        {
+            self.errors.push(e);
+        }
+        members
+    }
+
+    /// `[entry] fn <name>(<params>) -> <returns> <block>`; an entry function
+    /// may leave out `-> <returns>` to return nothing.
+    fn parse_function(&mut self, attrs: Vec<Attribute>, public: bool) -> ParseResult<Function> {
+        let entry = self.at(TokenKind::Entry);
+        if entry {
+            self.advance();
+        }
         self.expect(TokenKind::Fn)?;
-        let name = self.expect(TokenKind::Ident)?.ident_value()?.to_string();
+        let name_token = self.expect(TokenKind::Ident)?;
+        let name = name_token.ident_value()?.to_string();
+
+        let (params, mut failed) = self.parse_params();
+
+        let is_entry = entry || attrs.iter().any(|attr| attr.name == "entry");
+        let returns = if is_entry && self.at(TokenKind::LBrace) {
+            Vec::new()
+        } else {
+            let start = self.position;
+            match self
+                .expect(TokenKind::Arrow)
+                .and_then(|_| self.parse_returns())
+            {
+                Ok(returns) => returns,
+                Err(e) => {
+                    if !failed {
+                        self.errors.push(e);
+                    }
+                    failed = true;
+                    self.skip_member(start, &[TokenKind::LBrace, TokenKind::Semicolon]);
+                    Vec::new()
+                }
+            }
+        };
+
+        let body = match self.parse_block() {
+            Ok(body) => body,
+            Err(e) => {
+                if !failed {
+                    self.errors.push(e);
+                }
+                // No body to parse: the rest of the header becomes an error
+                // node, through a `;` that ends it.
+                let start = self.position;
+                self.skip_member(start, &[TokenKind::Semicolon]);
+                if self.at(TokenKind::Semicolon) {
+                    self.advance();
+                }
+                let span = self.skipped_span(start);
+                Block {
+                    stmts: vec![Stmt::new(StmtKind::Error, span)],
+                    span,
+                }
+            }
+        };
+
+        Ok(Function {
+            attrs,
+            public,
+            entry,
+            name,
+            params,
+            returns,
+            body,
+            span: name_token.span,
+        })
+    }
+
+    /// `( [<param> {, <param>} [,]] )`, each `<type> <name> [= <default>]`.
+    /// A parameter that fails to parse is reported and skipped through its
+    /// `,`, stopping early at a `->`, `{` or `;` that shows the list has
+    /// ended. Returns the parameters and whether an error was reported.
+    fn parse_params(&mut self) -> (Vec<Param>, bool) {
+        const LIST_ENDS: [TokenKind; 3] =
+            [TokenKind::Arrow, TokenKind::LBrace, TokenKind::Semicolon];
+        let mut params = Vec::new();
+        if let Err(e) = self.expect(TokenKind::LParen) {
+            self.errors.push(e);
+            let start = self.position;
+            self.skip_member(start, &LIST_ENDS);
+            return (params, true);
+        }
+        let mut failed = false;
+        while !self.at(TokenKind::RParen) && self.peek().is_some() && !self.at_item() {
+            let start = self.position;
+            match self.parse_param() {
+                Ok(param) => {
+                    params.push(param);
+                    failed = false;
+                }
+                Err(e) => {
+                    self.errors.push(e);
+                    failed = true;
+                    self.skip_member(
+                        start,
+                        &[
+                            TokenKind::Comma,
+                            TokenKind::Arrow,
+                            TokenKind::LBrace,
+                            TokenKind::Semicolon,
+                        ],
+                    );
+                }
+            }
+            if self.at(TokenKind::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        if let Err(e) = self.expect(TokenKind::RParen) {
+            if !failed {
+                self.errors.push(e);
+            }
+            failed = true;
+            let start = self.position;
+            self.skip_member(start, &LIST_ENDS);
+            if self.at(TokenKind::RParen) {
+                self.advance();
+            }
+        }
+        (params, failed)
+    }
+
+    /// `<type> <name> [= <default>]`
+    fn parse_param(&mut self) -> ParseResult<Param> {
+        let (ty, ty_span) = self.parse_type()?;
+        let name_token = self.expect(TokenKind::Ident)?;
+        let default = if self.at(TokenKind::Eq) {
+            self.advance();
+            Some(self.parse_expr(0)?)
+        } else {
+            None
+        };
+        Ok(Param {
+            ty,
+            ty_span,
+            name: name_token.ident_value()?.to_string(),
+            span: ty_span.to(name_token.span),
+            default,
+        })
+    }
+
+    /// `<type> [<name>] {, <type> [<name>]}`, after the `->`.
+    fn parse_returns(&mut self) -> ParseResult<Vec<ReturnValue>> {
+        let mut returns = Vec::new();
+        loop {
+            let (ty, ty_span) = self.parse_type()?;
+            let name_token = if self.at(TokenKind::Ident) {
+                self.advance()
+            } else {
+                None
+            };
+            returns.push(ReturnValue {
+                ty,
+                ty_span,
+                name: name_token
+                    .map(|t| t.ident_value().map(str::to_string))
+                    .transpose()?,
+                span: ty_span.to(name_token.map_or(ty_span, |t| t.span)),
+            });
+            if !self.at(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        if let Some(odd) = returns
+            .iter()
+            .find(|r| r.name.is_some() != returns[0].name.is_some())
+        {
+            return Err(ParseError::new(
+                "Either all return values are named or none are",
+                odd.span,
+            ));
+        }
+        Ok(returns)
+    }
+
+    /// A block, recovering from errors in its statements. A missing `}` is
+    /// reported, and the block ends where the next item or the input does.
+    fn parse_block(&mut self) -> ParseResult<Block> {
+        let open = self.expect(TokenKind::LBrace)?;
+        let mut stmts = Vec::new();
+        while !self.at(TokenKind::RBrace) && self.peek().is_some() && !self.at_item() {
+            let start = self.position;
+            match self.parse_stmt() {
+                Ok(Some(stmt)) => stmts.push(stmt),
+                Ok(None) => {}
+                Err(e) => {
+                    self.errors.push(e);
+                    stmts.push(self.recover_stmt(start));
+                }
+            }
+        }
+        let close = match self.expect(TokenKind::RBrace) {
+            Ok(close) => close.span,
+            Err(e) => {
+                self.errors.push(e);
+                self.prev_span()
+            }
+        };
+        Ok(Block {
+            stmts,
+            span: open.span.to(close),
+        })
+    }
+
+    /// Skips the rest of a statement that failed to parse, through its `;`,
+    /// or up to the `}` closing the enclosing block or the next item. Braces
+    /// opened along the way are skipped with their contents.
+    fn recover_stmt(&mut self, start: usize) -> Stmt {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            match token.kind {
+                _ if self.at_item() => break,
+                TokenKind::RBrace if depth == 0 => break,
+                TokenKind::Semicolon if depth == 0 => {
+                    self.advance();
+                    break;
+                }
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth -= 1,
+                _ => {}
+            }
+            self.advance();
+        }
+        if self.position == start {
+            // Always make progress, whatever the statement failed on.
+            self.advance();
+        }
+        Stmt::new(StmtKind::Error, self.skipped_span(start))
+    }
+
+    /// Span from the token at `start` through the last consumed one, or an
+    /// empty span where the next token starts when nothing was consumed.
+    fn skipped_span(&self, start: usize) -> Span {
+        let start_span = self.tokens[start..]
+            .iter()
+            .find(|t| !is_ignored(t.kind))
+            .map_or_else(|| self.eof_span(), |t| t.span);
+        if self.position == start {
+            return Span {
+                start: start_span.start,
+                end: start_span.start,
+            };
+        }
+        start_span.to(self.prev_span())
+    }
+
+    /// Skips the rest of an item member that failed to parse after starting
+    /// at `start`, stopping before one of `stops`, before a bracket closing
+    /// what encloses the member, or at the next item. Brackets opened along
+    /// the way, including those the member opened before failing, are
+    /// skipped with their contents.
+    fn skip_member(&mut self, start: usize, stops: &[TokenKind]) {
+        let mut depth = 0usize;
+        for token in &self.tokens[start..self.position] {
+            match token.kind {
+                TokenKind::LParen | TokenKind::LBrace | TokenKind::LBracket => depth += 1,
+                TokenKind::RParen | TokenKind::RBrace | TokenKind::RBracket => {
+                    depth = depth.saturating_sub(1);
+                }
+                _ => {}
+            }
+        }
+        while let Some(token) = self.peek() {
+            match token.kind {
+                _ if self.at_item() => break,
+                kind if depth == 0 && stops.contains(&kind) => break,
+                TokenKind::RParen | TokenKind::RBrace | TokenKind::RBracket if depth == 0 => break,
+                TokenKind::LParen | TokenKind::LBrace | TokenKind::LBracket => depth += 1,
+                TokenKind::RParen | TokenKind::RBrace | TokenKind::RBracket => depth -= 1,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
 
+    /// Whether the next tokens start an item: a token in [`starts_item`],
+    /// after any attributes, which statements can have too.
+    fn at_item(&self) -> bool {
+        let mut n = 0;
+        while self.peek_kind_at(n) == Some(TokenKind::Hash) {
+            // Attribute arguments never contain a `]`.
+            while !matches!(self.peek_kind_at(n), Some(TokenKind::RBracket) | None) {
+                n += 1;
+            }
+            n += 1;
+        }
+        self.peek_kind_at(n)
+            .is_some_and(|kind| kind != TokenKind::Hash && starts_item(kind))
+    }
+
+    /// Parses one statement with its attributes; a lone `;` is an empty
+    /// statement and yields `None`.
+    fn parse_stmt(&mut self) -> ParseResult<Option<Stmt>> {
+        let attrs = self.parse_attributes()?;
+        let Some(mut stmt) = self.parse_bare_stmt()? else {
+            return match attrs.first() {
+                Some(attr) => Err(ParseError::new(
+                    "Expected a statement after the attribute, found ';'",
+                    attr.span,
+                )),
+                None => Ok(None),
+            };
+        };
+        stmt.attrs = attrs;
+        Ok(Some(stmt))
+    }
+
+    fn parse_bare_stmt(&mut self) -> ParseResult<Option<Stmt>> {
+        let Some(first) = self.peek() else {
+            return Err(self.error("Expected statement, found end of input"));
+        };
+        let start = first.span;
+        let kind = match first.kind {
+            TokenKind::Semicolon => {
+                self.advance();
+                return Ok(None);
+            }
+            TokenKind::LBrace => {
+                let block = self.parse_block()?;
+                let span = block.span;
+                return Ok(Some(Stmt::new(StmtKind::Block(block), span)));
+            }
+            TokenKind::If => return self.parse_if_stmt().map(Some),
+            TokenKind::Match => {
+                let (m, span) = self.parse_match(false)?;
+                return Ok(Some(Stmt::new(StmtKind::Match(m), span)));
+            }
+            TokenKind::Return => {
+                self.advance();
+                let mut values = vec![self.parse_expr(0)?];
+                while self.at(TokenKind::Comma) {
+                    self.advance();
+                    values.push(self.parse_expr(0)?);
+                }
+                StmtKind::Return(values)
+            }
+            TokenKind::While | TokenKind::For | TokenKind::Loop => {
+                let kind = StmtKind::Loop(self.parse_loop()?);
+                return Ok(Some(Stmt::new(kind, start.to(self.prev_span()))));
+            }
+            // Unlike the other loops, `do { .. } while (c)` ends with a `;`.
+            TokenKind::Do => StmtKind::Loop(self.parse_loop()?),
+            TokenKind::Ident if self.peek_kind_at(1) == Some(TokenKind::Colon) => {
+                return self.parse_labeled_stmt().map(Some);
+            }
+            TokenKind::Break => {
+                self.advance();
+                let label = self.parse_break_label()?;
+                let value = if self.at(TokenKind::Semicolon) {
+                    None
+                } else {
+                    Some(self.parse_expr(0)?)
+                };
+                StmtKind::Break { label, value }
+            }
+            TokenKind::Continue => {
+                self.advance();
+                let label = self.parse_break_label()?;
+                if let Some(t) = self
+                    .peek()
+                    .filter(|t| t.kind == TokenKind::Ident && label.is_none())
+                {
+                    return Err(ParseError::new(
+                        format!("Unknown loop label '{}'", t.ident_value()?),
+                        t.span,
+                    ));
+                }
+                StmtKind::Continue { label }
+            }
+            _ => self.parse_simple_stmt()?,
+        };
+        let end = self.expect_semicolon()?;
+        Ok(Some(Stmt::new(kind, start.to(end.span))))
+    }
+
+    /// `<label>: <loop>`, or `<label>: <type> <name> = <loop>;` to name a
+    /// loop used as a value.
+    fn parse_labeled_stmt(&mut self) -> ParseResult<Stmt> {
+        let name_token = self.expect(TokenKind::Ident)?;
+        self.expect(TokenKind::Colon)?;
+        let label = Label {
+            name: name_token.ident_value()?.to_string(),
+            span: name_token.span,
+        };
+
+        self.pending_label = Some(label.clone());
+        let stmt = self.parse_stmt();
+        self.pending_label = None;
+        let stmt = stmt?;
+
+        let labels_loop = |lp: &Loop| lp.label.as_ref().is_some_and(|l| l.span == label.span);
+        let labeled = match stmt.as_ref().map(|s| &s.kind) {
+            Some(StmtKind::Loop(lp)) => labels_loop(lp),
+            Some(StmtKind::VarDecl(VarDecl {
+                init: Some(init), ..
+            })) => matches!(&init.kind, ExprKind::Loop(lp) if labels_loop(lp)),
+            _ => false,
+        };
+        match stmt {
+            Some(stmt) if labeled => {
+                let span = label.span.to(stmt.span);
+                Ok(Stmt::new(stmt.kind, span))
+            }
+            _ => Err(ParseError::new(
+                format!(
+                    "Label '{}' must be followed by a loop, or by a declaration initialized with one",
+                    label.name
+                ),
+                label.span,
+            )),
+        }
+    }
+
+    /// The label after `break`/`continue`, if the next identifier names an
+    /// enclosing loop.
+    fn parse_break_label(&mut self) -> ParseResult<Option<Label>> {
+        let Some(token) = self.peek().filter(|t| t.kind == TokenKind::Ident) else {
+            return Ok(None);
+        };
+        let name = token.ident_value()?;
+        if !self.labels.iter().any(|l| l == name) {
+            return Ok(None);
+        }
+        self.advance();
+        Ok(Some(Label {
+            name: name.to_string(),
+            span: token.span,
+        }))
+    }
+
+    /// Declarations, assignments, `++`/`--` and expression statements: the
+    /// statements allowed in a `for` header. Stops before the `;`.
+    fn parse_simple_stmt(&mut self) -> ParseResult<StmtKind> {
+        match self.peek().map(|t| t.kind) {
+            Some(kind @ (TokenKind::PlusPlus | TokenKind::MinusMinus)) => {
+                self.advance();
+                let target = self.parse_expr(0)?;
+                Ok(if kind == TokenKind::PlusPlus {
+                    StmtKind::Increment(target)
+                } else {
+                    StmtKind::Decrement(target)
+                })
+            }
+            // Two identifiers in a row only occur in declarations: `u32 x`,
+            // or `geo.Point p` with a qualified type.
+            Some(TokenKind::Ident)
+                if self.peek_kind_at(1) == Some(TokenKind::Ident)
+                    || (self.peek_kind_at(1) == Some(TokenKind::Period)
+                        && self.peek_kind_at(2) == Some(TokenKind::Ident)
+                        && self.peek_kind_at(3) == Some(TokenKind::Ident)) =>
+            {
+                self.parse_decl()
+            }
+            _ => self.parse_expr_stmt(),
+        }
+    }
+
+    /// Any loop form with its optional `else` block. A `do .. while` loop
+    /// stops before its final `;`.
+    fn parse_loop(&mut self) -> ParseResult<Loop> {
+        let label = self.pending_label.take();
+        let keyword = self.advance().expect("caller peeked a loop keyword");
+
+        // The label is visible in the body only; `else` is outside the loop.
+        if let Some(label) = &label {
+            self.labels.push(label.name.clone());
+        }
+        let parts = self.parse_loop_parts(keyword.kind);
+        if label.is_some() {
+            self.labels.pop();
+        }
+        let (kind, body) = parts?;
+
+        let else_block = if self.at(TokenKind::Else) {
+            if matches!(kind, LoopKind::DoWhile(_) | LoopKind::Infinite) {
+                return Err(self.error(format!(
+                    "A `{}` loop always runs its body, so it cannot have an `else` block",
+                    loop_keyword(&kind)
+                )));
+            }
+            self.advance();
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
+
+        Ok(Loop {
+            label,
+            kind,
+            body,
+            else_block,
+            default: None,
+        })
+    }
+
+    fn parse_loop_parts(&mut self, keyword: TokenKind) -> ParseResult<(LoopKind, Block)> {
+        let kind = match keyword {
+            TokenKind::While => LoopKind::While(self.parse_paren_cond()?),
+            TokenKind::For => self.parse_for_header()?,
+            TokenKind::Loop => LoopKind::Infinite,
+            TokenKind::Do => {
+                let body = self.parse_block()?;
+                self.expect(TokenKind::While)?;
+                let cond = self.parse_paren_cond()?;
+                return Ok((LoopKind::DoWhile(cond), body));
+            }
+            _ => unreachable!("not a loop keyword: {keyword:?}"),
+        };
+        let body = self.parse_block()?;
+        Ok((kind, body))
+    }
+
+    /// A loop used as a value. Loops that can end on their own need a
+    /// trailing `break <value>` giving their result in that case.
+    fn parse_loop_expr(&mut self) -> ParseResult<Expr> {
+        let start = self.peek().expect("caller peeked a loop keyword").span;
+        let mut lp = self.parse_loop()?;
+        if self.at(TokenKind::Break) {
+            if matches!(lp.kind, LoopKind::Infinite) {
+                return Err(self.error(
+                    "A `loop` only ends through `break`, so it cannot have a default value",
+                ));
+            }
+            self.advance();
+            lp.default = Some(Box::new(self.parse_expr(0)?));
+        } else if !matches!(lp.kind, LoopKind::Infinite) {
+            return Err(self.error(format!(
+                "A `{}` loop used as a value needs a trailing `break <value>` for when it ends without a `break`",
+                loop_keyword(&lp.kind)
+            )));
+        }
+        Ok(Expr::new(
+            ExprKind::Loop(Box::new(lp)),
+            start.to(self.prev_span()),
+        ))
+    }
+
+    /// `( [init] ; [cond] ; [update] )`
+    fn parse_for_header(&mut self) -> ParseResult<LoopKind> {
         self.expect(TokenKind::LParen)?;
+        let init = if self.at(TokenKind::Semicolon) {
+            None
+        } else {
+            Some(Box::new(self.parse_spanned_simple_stmt()?))
+        };
+        self.expect(TokenKind::Semicolon)?;
+        let cond = if self.at(TokenKind::Semicolon) {
+            None
+        } else {
+            Some(self.parse_expr(0)?)
+        };
+        self.expect(TokenKind::Semicolon)?;
+        let update = if self.at(TokenKind::RParen) {
+            None
+        } else {
+            Some(Box::new(self.parse_spanned_simple_stmt()?))
+        };
         self.expect(TokenKind::RParen)?;
+        Ok(LoopKind::For { init, cond, update })
+    }
+
+    fn parse_spanned_simple_stmt(&mut self) -> ParseResult<Stmt> {
+        let start = self
+            .peek()
+            .map(|t| t.span)
+            .unwrap_or_else(|| self.eof_span());
+        let kind = self.parse_simple_stmt()?;
+        Ok(Stmt::new(kind, start.to(self.prev_span())))
+    }
 
-        self.expect(TokenKind::Arrow)?;
-        let return_type = self.expect(TokenKind::Ident)?.ident_value()?.to_string();
+    /// `if (<cond>) <block> [else (<block> | <if stmt>)]`
+    fn parse_if_stmt(&mut self) -> ParseResult<Stmt> {
+        let if_token = self.expect(TokenKind::If)?;
+        let cond = self.parse_paren_cond()?;
+        let then_block = self.parse_block()?;
+        let mut span = if_token.span.to(then_block.span);
 
+        let else_branch = if self.at(TokenKind::Else) {
+            self.advance();
+            let branch = if self.at(TokenKind::If) {
+                self.parse_if_stmt()?
+            } else {
+                let block = self.parse_block()?;
+                let span = block.span;
+                Stmt::new(StmtKind::Block(block), span)
+            };
+            span = span.to(branch.span);
+            Some(Box::new(branch))
+        } else {
+            None
+        };
+
+        let kind = StmtKind::If {
+            cond,
+            then_block,
+            else_branch,
+        };
+        Ok(Stmt::new(kind, span))
+    }
+
+    /// `match (<scrutinee>) { [<arm> {, <arm>} [,]] }`, each arm
+    /// `<pattern> => <body>`. The `,` after an arm whose body is a block may
+    /// be left out. A `match` used as a value (`value`) needs an expression
+    /// as every arm's body.
+    fn parse_match(&mut self, value: bool) -> ParseResult<(Match, Span)> {
+        let match_token = self.expect(TokenKind::Match)?;
+        let scrutinee = self.parse_paren_cond()?;
         self.expect(TokenKind::LBrace)?;
-        let body = self.parse_body_mvp()?;
-        self.expect(TokenKind::RBrace)?;
+        let mut arms = Vec::new();
+        while self.peek().is_some_and(|t| t.kind != TokenKind::RBrace) {
+            let pattern = self.parse_pattern()?;
+            self.expect(TokenKind::FatArrow)?;
+            let body = if self.at(TokenKind::LBrace) {
+                if value {
+                    return Err(self.error(
+                        "A `match` used as a value needs an expression as each arm's body, not a block",
+                    ));
+                }
+                ArmBody::Block(self.parse_block()?)
+            } else {
+                ArmBody::Expr(self.parse_expr(0)?)
+            };
+            let is_block = matches!(body, ArmBody::Block(_));
+            arms.push(MatchArm { pattern, body });
+            if self.at(TokenKind::Comma) {
+                self.advance();
+            } else if !is_block {
+                break;
+            }
+        }
+        let close = self.expect(TokenKind::RBrace)?;
+        Ok((Match { scrutinee, arms }, match_token.span.to(close.span)))
+    }
 
-        Ok(Function {
+    /// `_`, a name, an optionally negated integer literal, a char literal,
+    /// or `<enum>.<variant>` with an optional `(<pattern> {, <pattern>})`. The
+    /// enum may be qualified by its module: `geo.Shape.Circle(r)`.
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let token = self.expect([
+            TokenKind::Ident,
+            TokenKind::Int,
+            TokenKind::Minus,
+            TokenKind::Char,
+        ])?;
+        let (kind, span) = match token.kind {
+            TokenKind::Minus => {
+                let int_token = self.expect(TokenKind::Int)?;
+                let lit = int_token.int_literal()?;
+                let kind = PatternKind::Int {
+                    negative: true,
+                    lit,
+                };
+                (kind, token.span.to(int_token.span))
+            }
+            TokenKind::Int => {
+                let kind = PatternKind::Int {
+                    negative: false,
+                    lit: token.int_literal()?,
+                };
+                (kind, token.span)
+            }
+            TokenKind::Char => (PatternKind::Char(token.char_value()?), token.span),
+            TokenKind::Ident if self.at(TokenKind::Period) => {
+                self.advance();
+                let mut enum_name = token.ident_value()?.to_string();
+                let mut variant_token = self.expect(TokenKind::Ident)?;
+                if self.at(TokenKind::Period) {
+                    self.advance();
+                    enum_name = format!("{enum_name}.{}", variant_token.ident_value()?);
+                    variant_token = self.expect(TokenKind::Ident)?;
+                }
+                let mut span = token.span.to(variant_token.span);
+                let mut fields = Vec::new();
+                if self.at(TokenKind::LParen) {
+                    self.advance();
+                    while self.peek().is_some_and(|t| t.kind != TokenKind::RParen) {
+                        fields.push(self.parse_pattern()?);
+                        if self.at(TokenKind::Comma) {
+                            self.advance();
+                        } else {
+                            break;
+                        }
+                    }
+                    span = span.to(self.expect(TokenKind::RParen)?.span);
+                }
+                let kind = PatternKind::Variant {
+                    enum_name,
+                    variant: variant_token.ident_value()?.to_string(),
+                    fields,
+                };
+                (kind, span)
+            }
+            TokenKind::Ident => match token.ident_value()? {
+                "_" => (PatternKind::Wildcard, token.span),
+                name => (PatternKind::Binding(name.to_string()), token.span),
+            },
+            _ => unreachable!(),
+        };
+        Ok(Pattern { kind, span })
+    }
+
+    /// `( <expr> )` after `if`.
+    fn parse_paren_cond(&mut self) -> ParseResult<Expr> {
+        self.expect(TokenKind::LParen)?;
+        let cond = self.parse_expr(0)?;
+        self.expect(TokenKind::RParen)?;
+        Ok(cond)
+    }
+
+    /// `<modifier>* <type> <name> [= <init>]`, without the `;`.
+    /// A variable declaration, or a destructuring one when a `,` follows the
+    /// first name.
+    fn parse_decl(&mut self) -> ParseResult<StmtKind> {
+        // Modifiers, the type and the name, each parsed as a possibly
+        // qualified type name; only the type may actually be qualified.
+        let mut words = Vec::new();
+        while self.at(TokenKind::Ident) {
+            words.push(self.parse_type()?);
+        }
+        let (name, name_span) = words
+            .pop()
+            .expect("declarations start with two identifiers");
+        let (ty, ty_span) = words
+            .pop()
+            .expect("declarations start with two identifiers");
+        if name.contains('.') {
+            return Err(ParseError::new(
+                format!("Expected a variable name, found '{name}'"),
+                name_span,
+            ));
+        }
+
+        let mut modifiers = Vec::new();
+        for (name, span) in words {
+            let modifier = Modifier::from_name(&name)
+                .ok_or_else(|| ParseError::new(format!("Unknown modifier '{name}'"), span))?;
+            if modifiers.contains(&modifier) {
+                return Err(ParseError::new(
+                    format!("Duplicate modifier '{name}'"),
+                    span,
+                ));
+            }
+            modifiers.push(modifier);
+        }
+
+        if self.at(TokenKind::Comma) {
+            let mut bindings = vec![Binding {
+                ty,
+                ty_span,
+                name,
+                name_span,
+            }];
+            while self.at(TokenKind::Comma) {
+                self.advance();
+                let (ty, ty_span) = self.parse_type()?;
+                let name_token = self.expect(TokenKind::Ident)?;
+                bindings.push(Binding {
+                    ty,
+                    ty_span,
+                    name: name_token.ident_value()?.to_string(),
+                    name_span: name_token.span,
+                });
+            }
+            self.expect(TokenKind::Eq)?;
+            return Ok(StmtKind::Destructure(DestructureDecl {
+                modifiers,
+                bindings,
+                init: self.parse_expr(0)?,
+            }));
+        }
+
+        let init = if self.at(TokenKind::Eq) {
+            self.advance();
+            Some(self.parse_expr(0)?)
+        } else {
+            None
+        };
+        Ok(StmtKind::VarDecl(VarDecl {
+            modifiers,
+            ty,
+            ty_span,
             name,
-            return_type,
-            body,
+            name_span,
+            init,
+        }))
+    }
+
+    /// An expression statement, assignment or postfix `++`/`--`, without the `;`.
+    fn parse_expr_stmt(&mut self) -> ParseResult<StmtKind> {
+        let expr = self.parse_expr(0)?;
+        let Some(kind) = self.peek().map(|t| t.kind) else {
+            return Ok(StmtKind::Expr(expr));
+        };
+        if kind == TokenKind::Eq || compound_assign_op(kind).is_some() {
+            self.advance();
+            let value = self.parse_expr(0)?;
+            return Ok(StmtKind::Assign {
+                target: expr,
+                op: compound_assign_op(kind),
+                value,
+            });
+        }
+        Ok(match kind {
+            TokenKind::PlusPlus => {
+                self.advance();
+                StmtKind::Increment(expr)
+            }
+            TokenKind::MinusMinus => {
+                self.advance();
+                StmtKind::Decrement(expr)
+            }
+            _ => StmtKind::Expr(expr),
         })
     }
 
-    fn parse_body_mvp(&mut self) -> ParseResult<Vec<Expr>> {
-        self.expect(TokenKind::Return)?;
+    /// Like `expect(Semicolon)`, with a clearer message for `++`/`--` used
+    /// inside an expression.
+    fn expect_semicolon(&mut self) -> ParseResult<&'a Token> {
+        match self.peek() {
+            Some(t) if matches!(t.kind, TokenKind::PlusPlus | TokenKind::MinusMinus) => {
+                Err(ParseError::new(
+                    "`++` and `--` are statements and cannot be used inside an expression",
+                    t.span,
+                ))
+            }
+            _ => self.expect(TokenKind::Semicolon),
+        }
+    }
+
+    pub fn parse_expression(&mut self) -> ParseResult<Expr> {
+        self.parse_expr(0)
+    }
 
-        let token = self.expect([TokenKind::Int, TokenKind::Ident])?;
-        let expr = match token.kind {
-            TokenKind::Int => Expr::Int(token.int_value()?),
-            TokenKind::Ident => Expr::Ident(token.ident_value()?.to_string()),
+    /// Precedence climbing over binary operators binding at least as tight
+    /// as `min_prec`; see [`binary_op`] for the table.
+    fn parse_expr(&mut self, min_prec: u8) -> ParseResult<Expr> {
+        let mut lhs = self.parse_unary()?;
+
+        while let Some((op, prec)) = self.peek().and_then(|t| binary_op(t.kind)) {
+            if prec < min_prec {
+                break;
+            }
+            self.advance();
+            // `**` is right-associative; everything else groups to the left.
+            let next_prec = if op == BinaryOp::Pow { prec } else { prec + 1 };
+            let rhs = self.parse_expr(next_prec)?;
+            let span = lhs.span.to(rhs.span);
+            let kind = ExprKind::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+            lhs = Expr::new(kind, span);
+        }
+
+        // The postfix `a if (c) else b` binds loosest of all.
+        if min_prec == 0 && self.at(TokenKind::If) {
+            self.advance();
+            let cond = self.parse_paren_cond()?;
+            self.expect(TokenKind::Else)?;
+            let else_expr = self.parse_expr(0)?;
+            let span = lhs.span.to(else_expr.span);
+            let kind = ExprKind::If {
+                cond: Box::new(cond),
+                then_expr: Box::new(lhs),
+                else_expr: Box::new(else_expr),
+            };
+            lhs = Expr::new(kind, span);
+        }
+
+        Ok(lhs)
+    }
+
+    /// `if (<cond>) <expr> else <expr>`; the `else` arm extends as far right
+    /// as possible.
+    fn parse_if_expr(&mut self) -> ParseResult<Expr> {
+        let if_token = self.expect(TokenKind::If)?;
+        let cond = self.parse_paren_cond()?;
+        let then_expr = self.parse_expr(0)?;
+        self.expect(TokenKind::Else)?;
+        let else_expr = self.parse_expr(0)?;
+        let span = if_token.span.to(else_expr.span);
+        let kind = ExprKind::If {
+            cond: Box::new(cond),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+        };
+        Ok(Expr::new(kind, span))
+    }
+
+    /// Prefix `-`, `!` and `~`. They bind tighter than every binary operator
+    /// except `**`, so `-x ** 2` is `-(x ** 2)`. A prefix `if` expression
+    /// also starts here.
+    fn parse_unary(&mut self) -> ParseResult<Expr> {
+        let op = match self.peek().map(|t| t.kind) {
+            Some(TokenKind::Minus) => UnaryOp::Neg,
+            Some(TokenKind::Bang) => UnaryOp::Not,
+            Some(TokenKind::Tilde) => UnaryOp::BitNot,
+            Some(TokenKind::If) => return self.parse_if_expr(),
+            Some(TokenKind::Match) => {
+                let (m, span) = self.parse_match(true)?;
+                return Ok(Expr::new(ExprKind::Match(Box::new(m)), span));
+            }
+            Some(TokenKind::While | TokenKind::For | TokenKind::Loop | TokenKind::Do) => {
+                return self.parse_loop_expr();
+            }
+            _ => return self.parse_postfix(),
+        };
+        let op_token = self.advance().expect("peeked a token");
+        let operand = self.parse_expr(POW_PREC)?;
+        let span = op_token.span.to(operand.span);
+        let kind = ExprKind::Unary {
+            op,
+            operand: Box::new(operand),
+        };
+        Ok(Expr::new(kind, span))
+    }
+
+    /// A primary expression followed by any number of call argument lists
+    /// and `.field` accesses.
+    fn parse_postfix(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.at(TokenKind::Period) {
+                self.advance();
+                let name_token = self.expect(TokenKind::Ident)?;
+                let span = expr.span.to(name_token.span);
+                // `geo.Point { .. }`, a struct of another module.
+                if let ExprKind::Ident(module) = &expr.kind
+                    && self.at(TokenKind::LBrace)
+                {
+                    let name = format!("{module}.{}", name_token.ident_value()?);
+                    return self.parse_struct_lit(name, span);
+                }
+                let kind = ExprKind::Field {
+                    base: Box::new(expr),
+                    name: name_token.ident_value()?.to_string(),
+                    name_span: name_token.span,
+                };
+                expr = Expr::new(kind, span);
+                continue;
+            }
+            if !self.at(TokenKind::LParen) {
+                break;
+            }
+            self.advance();
+            let mut args = Vec::new();
+            while self.peek().is_some_and(|t| t.kind != TokenKind::RParen) {
+                args.push(self.parse_arg()?);
+                if self.peek().is_some_and(|t| t.kind == TokenKind::Comma) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            let close = self.expect(TokenKind::RParen)?;
+            let span = expr.span.to(close.span);
+            let kind = ExprKind::Call {
+                callee: Box::new(expr),
+                args,
+                overload: None,
+            };
+            expr = Expr::new(kind, span);
+        }
+        Ok(expr)
+    }
+
+    /// `{ [<name> = <value> {, <name> = <value>} [,]] }` after the struct
+    /// name, which is at `name_span`.
+    fn parse_struct_lit(&mut self, name: String, name_span: Span) -> ParseResult<Expr> {
+        self.expect(TokenKind::LBrace)?;
+        let mut fields = Vec::new();
+        while self.peek().is_some_and(|t| t.kind != TokenKind::RBrace) {
+            let field_token = self.expect(TokenKind::Ident)?;
+            self.expect(TokenKind::Eq)?;
+            fields.push(FieldInit {
+                name: field_token.ident_value()?.to_string(),
+                name_span: field_token.span,
+                value: self.parse_expr(0)?,
+            });
+            if self.at(TokenKind::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        let close = self.expect(TokenKind::RBrace)?;
+        let kind = ExprKind::StructLit { name, fields };
+        Ok(Expr::new(kind, name_span.to(close.span)))
+    }
+
+    /// `<value>` or `<name> = <value>`.
+    fn parse_arg(&mut self) -> ParseResult<Arg> {
+        if self.at(TokenKind::Ident) && self.peek_kind_at(1) == Some(TokenKind::Eq) {
+            let name_token = self.expect(TokenKind::Ident)?;
+            self.advance();
+            let value = self.parse_expr(0)?;
+            return Ok(Arg {
+                name: Some(name_token.ident_value()?.to_string()),
+                span: name_token.span.to(value.span),
+                value,
+            });
+        }
+        let value = self.parse_expr(0)?;
+        Ok(Arg {
+            name: None,
+            span: value.span,
+            value,
+        })
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<Expr> {
+        let token = self.expect([
+            TokenKind::Int,
+            TokenKind::Float,
+            TokenKind::Str,
+            TokenKind::ByteStr,
+            TokenKind::Char,
+            TokenKind::Ident,
+            TokenKind::LParen,
+        ])?;
+        let kind = match token.kind {
+            TokenKind::Int => ExprKind::Int(token.int_literal()?),
+            TokenKind::Float => ExprKind::Float(token.float_literal()?.clone()),
+            TokenKind::Str => ExprKind::Str(token.str_value()?.to_string()),
+            TokenKind::ByteStr => ExprKind::ByteStr(token.byte_str_value()?.to_vec()),
+            TokenKind::Char => ExprKind::Char(token.char_value()?),
+            // A name followed by `{` only occurs in struct literals; blocks
+            // never follow an expression.
+            TokenKind::Ident if self.at(TokenKind::LBrace) => {
+                return self.parse_struct_lit(token.ident_value()?.to_string(), token.span);
+            }
+            TokenKind::Ident => ExprKind::Ident(token.ident_value()?.to_string()),
+            TokenKind::LParen => {
+                let inner = self.parse_expr(0)?;
+                let close = self.expect(TokenKind::RParen)?;
+                // Keep the parentheses in the span so diagnostics cover them.
+                return Ok(Expr::new(inner.kind, token.span.to(close.span)));
+            }
             _ => unreachable!(),
         };
+        Ok(Expr::new(kind, token.span))
+    }
+}
 
-        self.expect(TokenKind::Semicolon)?;
+/// Tokens the parser never sees: doc comments are kept by the lexer for
+/// tooling, and error tokens were already reported by the lexer.
+fn is_ignored(kind: TokenKind) -> bool {
+    kind.is_doc_comment() || kind == TokenKind::Error
+}
 
-        Ok(vec![Expr::Return(Box::new(expr))])
+/// Tokens an item can start with, where parsing resumes after an error.
+fn starts_item(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Fn
+            | TokenKind::Struct
+            | TokenKind::Enum
+            | TokenKind::Hash
+            | TokenKind::Import
+            | TokenKind::Pub
+            | TokenKind::Entry
+    )
+}
+
+fn loop_keyword(kind: &LoopKind) -> &'static str {
+    match kind {
+        LoopKind::While(_) => "while",
+        LoopKind::For { .. } => "for",
+        LoopKind::DoWhile(_) => "do",
+        LoopKind::Infinite => "loop",
     }
 }
+
+/// Operator applied by a compound assignment token such as `+=`.
+fn compound_assign_op(kind: TokenKind) -> Option<BinaryOp> {
+    Some(match kind {
+        TokenKind::PlusEq => BinaryOp::Add,
+        TokenKind::MinusEq => BinaryOp::Sub,
+        TokenKind::StarEq => BinaryOp::Mul,
+        TokenKind::SlashEq => BinaryOp::Div,
+        TokenKind::PercentEq => BinaryOp::Rem,
+        TokenKind::StarStarEq => BinaryOp::Pow,
+        TokenKind::AmpEq => BinaryOp::BitAnd,
+        TokenKind::PipeEq => BinaryOp::BitOr,
+        TokenKind::CaretEq => BinaryOp::BitXor,
+        TokenKind::ShlEq => BinaryOp::Shl,
+        TokenKind::ShrEq => BinaryOp::Shr,
+        _ => return None,
+    })
+}
+
+/// Precedence of `**`, which also bounds the operand of a prefix operator.
+const POW_PREC: u8 = 12;
+
+/// Binary operator and precedence for a token; higher binds tighter.
+///
+/// | Prec | Operators                                  | Associativity |
+/// | ---- | ------------------------------------------ | ------------- |
+/// | 12   | `**`                                       | right         |
+/// | 11   | prefix `-` `!` `~`                         | (unary)       |
+/// | 10   | `*` `/` `%` and `*` with overflow suffixes | left          |
+/// | 9    | `+` `-` and their overflow suffixes        | left          |
+/// | 8    | `<<` `>>`                                  | left          |
+/// | 7    | `&`                                        | left          |
+/// | 6    | `^`                                        | left          |
+/// | 5    | `\|`                                       | left          |
+/// | 4    | `==` `!=` `<` `>` `<=` `>=`                | left          |
+/// | 3    | `&&`                                       | left          |
+/// | 2    | `^^`                                       | left          |
+/// | 1    | `\|\|`                                     | left          |
+///
+/// Calls `f(...)` and parentheses bind tightest of all.
+fn binary_op(kind: TokenKind) -> Option<(BinaryOp, u8)> {
+    Some(match kind {
+        TokenKind::OrOr => (BinaryOp::Or, 1),
+        TokenKind::CaretCaret => (BinaryOp::Xor, 2),
+        TokenKind::AndAnd => (BinaryOp::And, 3),
+        TokenKind::EqEq => (BinaryOp::Eq, 4),
+        TokenKind::NotEq => (BinaryOp::NotEq, 4),
+        TokenKind::Lt => (BinaryOp::Lt, 4),
+        TokenKind::Gt => (BinaryOp::Gt, 4),
+        TokenKind::LtEq => (BinaryOp::LtEq, 4),
+        TokenKind::GtEq => (BinaryOp::GtEq, 4),
+        TokenKind::Pipe => (BinaryOp::BitOr, 5),
+        TokenKind::Caret => (BinaryOp::BitXor, 6),
+        TokenKind::Amp => (BinaryOp::BitAnd, 7),
+        TokenKind::Shl => (BinaryOp::Shl, 8),
+        TokenKind::Shr => (BinaryOp::Shr, 8),
+        TokenKind::Plus => (BinaryOp::Add, 9),
+        TokenKind::PlusWrap => (BinaryOp::AddWrap, 9),
+        TokenKind::PlusSat => (BinaryOp::AddSat, 9),
+        TokenKind::PlusChecked => (BinaryOp::AddChecked, 9),
+        TokenKind::PlusWrapChecked => (BinaryOp::AddWrapChecked, 9),
+        TokenKind::PlusSatChecked => (BinaryOp::AddSatChecked, 9),
+        TokenKind::Minus => (BinaryOp::Sub, 9),
+        TokenKind::MinusWrap => (BinaryOp::SubWrap, 9),
+        TokenKind::MinusSat => (BinaryOp::SubSat, 9),
+        TokenKind::MinusChecked => (BinaryOp::SubChecked, 9),
+        TokenKind::MinusWrapChecked => (BinaryOp::SubWrapChecked, 9),
+        TokenKind::MinusSatChecked => (BinaryOp::SubSatChecked, 9),
+        TokenKind::Star => (BinaryOp::Mul, 10),
+        TokenKind::StarWrap => (BinaryOp::MulWrap, 10),
+        TokenKind::StarSat => (BinaryOp::MulSat, 10),
+        TokenKind::StarChecked => (BinaryOp::MulChecked, 10),
+        TokenKind::StarWrapChecked => (BinaryOp::MulWrapChecked, 10),
+        TokenKind::StarSatChecked => (BinaryOp::MulSatChecked, 10),
+        TokenKind::Slash => (BinaryOp::Div, 10),
+        TokenKind::Percent => (BinaryOp::Rem, 10),
+        TokenKind::StarStar => (BinaryOp::Pow, POW_PREC),
+        _ => return None,
+    })
+}
@@ -1,27 +1,20 @@
 use std::path::PathBuf;
 
-use crate::lexer::lex;
-use crate::parser::Parser;
+use crate::ast::Program;
+use crate::attributes::check_attributes;
+use crate::entry::check_entry;
+use crate::error::{LexError, LexWarning, ParseError, SemaWarning};
+use crate::modules::{load_modules, resolve_modules};
+use crate::resolve::resolve_calls;
+use crate::sema::check_program;
 use crate::session::Session;
-use crate::tokens::Token;
 
 use crate::codegen::{default_output_paths, emit_object_and_ir};
 use crate::link::link_executable;
 
-pub fn compile(session: &Session) -> i32 {
-    let mut tokens: Vec<Token> = Vec::new();
-    for source in &session.source {
-        println!("Compiling source file: {:?}", source.path);
-        let source_tokens = match lex(&source.content) {
-            Ok(tokens) => tokens,
-            Err(err) => {
-                eprintln!("Lex error in {:?}: {err}", source.path);
-                return 1;
-            }
-        };
-        println!("Tokens: {:?}", source_tokens);
-        tokens.extend(source_tokens);
-    }
+pub fn compile(session: &mut Session) -> i32 {
+    let program = load_program(session);
+    let session = &*session;
 
     println!("Stage: {:?}", session.stop_after);
     println!("Emit: {:?}", session.compile_emit);
@@ -80,13 +73,8 @@ pub fn compile(session: &Session) -> i32 {
         println!("Link preference: static");
     }
 
-    let mut parser = Parser::new(&tokens);
-    let program = match parser.parse_program() {
-        Ok(p) => p,
-        Err(e) => {
-            eprintln!("Parse error: {e}");
-            return 1;
-        }
+    let Some((program, entry)) = program else {
+        return 1;
     };
 
     // Output dir choice
@@ -98,8 +86,8 @@ pub fn compile(session: &Session) -> i32 {
     let (obj_path, ll_path) = default_output_paths(&out_dir);
     let exe_path = out_dir.join("out");
 
-    if let Err(e) = emit_object_and_ir(&program, &obj_path, Some(&ll_path)) {
-        eprintln!("Codegen error: {e}");
+    if let Err(e) = emit_object_and_ir(&program, entry, &obj_path, Some(&ll_path)) {
+        eprintln!("{}", session.source_map.render("error", &e.message, e.span));
         return 1;
     }
 
@@ -115,18 +103,9 @@ pub fn compile(session: &Session) -> i32 {
     0
 }
 
-pub fn check(session: &Session) -> i32 {
-    for source in &session.source {
-        println!("Compiling source file: {:?}", source.path);
-        let tokens = match lex(&source.content) {
-            Ok(tokens) => tokens,
-            Err(err) => {
-                eprintln!("Lex error in {:?}: {err}", source.path);
-                return 1;
-            }
-        };
-        println!("Tokens: {:?}", tokens);
-    }
+pub fn check(session: &mut Session) -> i32 {
+    let has_errors = load_program(session).is_none();
+    let session = &*session;
 
     println!("Stage: {:?}", session.stop_after);
     println!("Emit: {:?}", session.check_emit);
@@ -139,7 +118,137 @@ pub fn check(session: &Session) -> i32 {
         println!("Dep-info: {:?}", dep_info);
     }
 
-    0
+    if has_errors { 1 } else { 0 }
+}
+
+/// Loads the source files and every module they import, merges them into one
+/// program, checks its attributes and that it has the entry point its crate
+/// type needs, resolves the arguments of its calls and picks the overload
+/// each call refers to. Gives the program and the index of its entry
+/// function. Every problem found is printed, and there is no program when
+/// any of them is an error.
+fn load_program(session: &mut Session) -> Option<(Program, Option<usize>)> {
+    let loaded = load_modules(&mut session.source_map, &session.include_path);
+    let session = &*session;
+    for module in &loaded.modules {
+        let path = &session.source_map.file(module.file).path;
+        println!("Compiling source file: {path:?} (module {})", module.name);
+    }
+    let mut has_errors = report_lex_errors(session, &loaded.lex_errors);
+    has_errors |= report_lex_warnings(session, &loaded.lex_warnings);
+    // Every file is parsed even after lex errors so its parse errors are
+    // reported too, but name resolution needs clean input.
+    has_errors |= report_parse_errors(session, &loaded.parse_errors);
+    for e in &loaded.errors {
+        eprintln!(
+            "{}",
+            session.source_map.render("error", &e.message, Some(e.span))
+        );
+    }
+    if has_errors || !loaded.errors.is_empty() {
+        return None;
+    }
+    let mut program = match resolve_modules(loaded.modules) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                session.source_map.render("error", &e.message, Some(e.span))
+            );
+            return None;
+        }
+    };
+    let attribute_errors = check_attributes(&program);
+    for e in &attribute_errors {
+        eprintln!(
+            "{}",
+            session.source_map.render("error", &e.message, Some(e.span))
+        );
+    }
+    if !attribute_errors.is_empty() {
+        return None;
+    }
+    let entry = match check_entry(&program, session.crate_type) {
+        Ok(entry) => entry,
+        Err(e) => {
+            eprintln!("{}", session.source_map.render("error", &e.message, e.span));
+            return None;
+        }
+    };
+    if let Err(e) = resolve_calls(&mut program) {
+        eprintln!(
+            "{}",
+            session.source_map.render("error", &e.message, Some(e.span))
+        );
+        return None;
+    }
+    match check_program(&mut program) {
+        Ok(warnings) => {
+            if report_sema_warnings(session, &warnings) {
+                return None;
+            }
+        }
+        Err(e) => {
+            eprintln!(
+                "{}",
+                session.source_map.render("error", &e.message, Some(e.span))
+            );
+            return None;
+        }
+    }
+    Some((program, entry))
+}
+
+/// Prints lex errors; returns whether there were any.
+fn report_lex_errors(session: &Session, errors: &[LexError]) -> bool {
+    for err in errors {
+        let message = err.kind.to_string();
+        eprintln!(
+            "{}",
+            session.source_map.render("error", &message, Some(err.span))
+        );
+    }
+    !errors.is_empty()
+}
+
+/// Prints parse errors; returns whether there were any.
+fn report_parse_errors(session: &Session, errors: &[ParseError]) -> bool {
+    for err in errors {
+        eprintln!(
+            "{}",
+            session
+                .source_map
+                .render("error", &err.message, Some(err.span))
+        );
+    }
+    !errors.is_empty()
+}
+
+/// Prints lex warnings; returns whether they count as errors (`-D warnings`).
+fn report_lex_warnings(session: &Session, warnings: &[LexWarning]) -> bool {
+    for warning in warnings {
+        let message = warning.message();
+        eprintln!(
+            "{}",
+            session
+                .source_map
+                .render("warning", &message, Some(warning.span()))
+        );
+    }
+    session.warnings_as_errors && !warnings.is_empty()
+}
+
+/// Prints the semantic checks' warnings; returns whether they count as errors.
+fn report_sema_warnings(session: &Session, warnings: &[SemaWarning]) -> bool {
+    for warning in warnings {
+        eprintln!(
+            "{}",
+            session
+                .source_map
+                .render("warning", &warning.message, Some(warning.span))
+        );
+    }
+    session.warnings_as_errors && !warnings.is_empty()
 }
 
 fn print_common_session_data(session: &Session) {
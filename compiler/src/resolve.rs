@@ -0,0 +1,499 @@
+//! Call resolution, run between parsing and codegen.
+//!
+//! Binds each call's arguments to the parameters of the function it names:
+//! named arguments are moved to their parameter's position and left-out
+//! arguments are replaced by a copy of the parameter's default. Since the
+//! copy is evaluated in the caller, a default may not use the function's
+//! parameters or any other variable it does not declare itself. When only
+//! one overload of the function can take the arguments, the call is
+//! rewritten to one positional argument per parameter of that overload.
+//! Otherwise picking the overload needs types, so [`crate::sema`] binds
+//! the arguments with [`bind_args`] once it has picked one.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{
+    Arg, ArmBody, Block, Expr, ExprKind, Loop, LoopKind, Match, Param, Pattern, PatternKind,
+    Program, Stmt, StmtKind,
+};
+use crate::error::{ResolveError, ResolveResult};
+use crate::tokens::Span;
+
+pub fn resolve_calls(program: &mut Program) -> ResolveResult<()> {
+    let mut resolver = CallResolver::default();
+    for f in &program.functions {
+        resolver
+            .overloads
+            .entry(f.name.clone())
+            .or_default()
+            .push(f.params.clone());
+    }
+    let enums: HashSet<&str> = program.enums.iter().map(|e| e.name.as_str()).collect();
+    for f in &program.functions {
+        for param in &f.params {
+            let Some(default) = &param.default else {
+                continue;
+            };
+            let mut vars = FreeVars {
+                enums: &enums,
+                scopes: vec![HashSet::new()],
+            };
+            if let Some((var, span)) = vars.in_expr(default) {
+                return Err(ResolveError::new(
+                    format!(
+                        "the default value of parameter '{}' of function '{}' cannot use '{var}': defaults are evaluated at each call, where the function's parameters and locals are not in scope",
+                        param.name, f.name
+                    ),
+                    span,
+                ));
+            }
+        }
+    }
+    for f in &mut program.functions {
+        for param in &mut f.params {
+            if let Some(default) = &mut param.default {
+                resolver.resolve_expr(default)?;
+            }
+        }
+        resolver.resolve_block(&mut f.body)?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct CallResolver {
+    /// The parameters of each overload of each function.
+    overloads: HashMap<String, Vec<Vec<Param>>>,
+    /// Defaults being resolved after being copied into a call, by function,
+    /// overload and parameter index, to catch a default that calls its own
+    /// function without that argument.
+    expanding: Vec<(String, usize, usize)>,
+}
+
+/// Where the value of a parameter comes from in a call.
+#[derive(Debug, Clone, Copy)]
+pub enum ArgSource<'a> {
+    Given(&'a Arg),
+    Default(&'a Param),
+}
+
+impl<'a> ArgSource<'a> {
+    pub fn value(self) -> &'a Expr {
+        match self {
+            ArgSource::Given(arg) => &arg.value,
+            ArgSource::Default(param) => param.default.as_ref().expect("bound to its default"),
+        }
+    }
+}
+
+/// Matches `args` to the parameters of function `name`, giving the source of
+/// each parameter's value in parameter order.
+pub fn bind_args<'a>(
+    name: &str,
+    params: &'a [Param],
+    args: &'a [Arg],
+    span: Span,
+) -> ResolveResult<Vec<ArgSource<'a>>> {
+    let positional = args.iter().take_while(|a| a.name.is_none()).count();
+    if positional > params.len() {
+        return Err(ResolveError::new(
+            format!(
+                "function '{name}' takes {} argument{} but {} {} supplied",
+                params.len(),
+                if params.len() == 1 { "" } else { "s" },
+                args.len(),
+                if args.len() == 1 { "was" } else { "were" },
+            ),
+            span,
+        ));
+    }
+
+    let mut slots: Vec<Option<ArgSource>> = vec![None; params.len()];
+    for (i, arg) in args.iter().enumerate() {
+        let index = match &arg.name {
+            None if i < positional => i,
+            None => {
+                return Err(ResolveError::new(
+                    "positional arguments must come before named arguments",
+                    arg.span,
+                ));
+            }
+            Some(arg_name) => params
+                .iter()
+                .position(|p| &p.name == arg_name)
+                .ok_or_else(|| {
+                    ResolveError::new(
+                        format!("function '{name}' has no parameter named '{arg_name}'"),
+                        arg.span,
+                    )
+                })?,
+        };
+        if slots[index].is_some() {
+            return Err(ResolveError::new(
+                format!(
+                    "argument '{}' is supplied more than once",
+                    params[index].name
+                ),
+                arg.span,
+            ));
+        }
+        slots[index] = Some(ArgSource::Given(arg));
+    }
+
+    slots
+        .into_iter()
+        .zip(params)
+        .map(|(slot, param)| match slot {
+            Some(source) => Ok(source),
+            None if param.default.is_some() => Ok(ArgSource::Default(param)),
+            None => Err(ResolveError::new(
+                format!(
+                    "missing argument for parameter '{}' of function '{name}'",
+                    param.name
+                ),
+                span,
+            )),
+        })
+        .collect()
+}
+
+impl CallResolver {
+    fn resolve_block(&mut self, block: &mut Block) -> ResolveResult<()> {
+        block
+            .stmts
+            .iter_mut()
+            .try_for_each(|stmt| self.resolve_stmt(stmt))
+    }
+
+    fn resolve_stmt(&mut self, stmt: &mut Stmt) -> ResolveResult<()> {
+        match &mut stmt.kind {
+            StmtKind::VarDecl(decl) => match &mut decl.init {
+                Some(init) => self.resolve_expr(init),
+                None => Ok(()),
+            },
+            StmtKind::Destructure(decl) => self.resolve_expr(&mut decl.init),
+            StmtKind::Assign { target, value, .. } => {
+                self.resolve_expr(target)?;
+                self.resolve_expr(value)
+            }
+            StmtKind::Increment(expr) | StmtKind::Decrement(expr) | StmtKind::Expr(expr) => {
+                self.resolve_expr(expr)
+            }
+            StmtKind::Return(values) => values.iter_mut().try_for_each(|v| self.resolve_expr(v)),
+            StmtKind::Block(block) => self.resolve_block(block),
+            StmtKind::Loop(lp) => self.resolve_loop(lp),
+            StmtKind::Break { value, .. } => match value {
+                Some(value) => self.resolve_expr(value),
+                None => Ok(()),
+            },
+            StmtKind::Continue { .. } | StmtKind::Error => Ok(()),
+            StmtKind::If {
+                cond,
+                then_block,
+                else_branch,
+            } => {
+                self.resolve_expr(cond)?;
+                self.resolve_block(then_block)?;
+                match else_branch {
+                    Some(else_branch) => self.resolve_stmt(else_branch),
+                    None => Ok(()),
+                }
+            }
+            StmtKind::Match(m) => self.resolve_match(m),
+        }
+    }
+
+    fn resolve_match(&mut self, m: &mut Match) -> ResolveResult<()> {
+        self.resolve_expr(&mut m.scrutinee)?;
+        m.arms.iter_mut().try_for_each(|arm| match &mut arm.body {
+            ArmBody::Expr(e) => self.resolve_expr(e),
+            ArmBody::Block(block) => self.resolve_block(block),
+        })
+    }
+
+    fn resolve_loop(&mut self, lp: &mut Loop) -> ResolveResult<()> {
+        match &mut lp.kind {
+            LoopKind::While(cond) | LoopKind::DoWhile(cond) => self.resolve_expr(cond)?,
+            LoopKind::For { init, cond, update } => {
+                if let Some(init) = init {
+                    self.resolve_stmt(init)?;
+                }
+                if let Some(cond) = cond {
+                    self.resolve_expr(cond)?;
+                }
+                if let Some(update) = update {
+                    self.resolve_stmt(update)?;
+                }
+            }
+            LoopKind::Infinite => {}
+        }
+        self.resolve_block(&mut lp.body)?;
+        if let Some(else_block) = &mut lp.else_block {
+            self.resolve_block(else_block)?;
+        }
+        if let Some(default) = &mut lp.default {
+            self.resolve_expr(default)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_expr(&mut self, e: &mut Expr) -> ResolveResult<()> {
+        match &mut e.kind {
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::Str(_)
+            | ExprKind::ByteStr(_)
+            | ExprKind::Char(_)
+            | ExprKind::Ident(_) => Ok(()),
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.resolve_expr(lhs)?;
+                self.resolve_expr(rhs)
+            }
+            ExprKind::Unary { operand, .. } => self.resolve_expr(operand),
+            ExprKind::Call { callee, args, .. } => {
+                self.resolve_expr(callee)?;
+                for arg in args.iter_mut() {
+                    self.resolve_expr(&mut arg.value)?;
+                }
+                if let ExprKind::Ident(name) = &callee.kind {
+                    self.resolve_call(name, args, e.span)?;
+                }
+                Ok(())
+            }
+            ExprKind::Loop(lp) => self.resolve_loop(lp),
+            ExprKind::If {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                self.resolve_expr(cond)?;
+                self.resolve_expr(then_expr)?;
+                self.resolve_expr(else_expr)
+            }
+            ExprKind::StructLit { fields, .. } => fields
+                .iter_mut()
+                .try_for_each(|field| self.resolve_expr(&mut field.value)),
+            ExprKind::Field { base, .. } => self.resolve_expr(base),
+            ExprKind::Match(m) => self.resolve_match(m),
+        }
+    }
+
+    /// Rewrites `args` to one positional argument per parameter of the only
+    /// overload of `name` that can take them. Calls to unknown functions are
+    /// left for codegen to report.
+    fn resolve_call(&mut self, name: &str, args: &mut Vec<Arg>, span: Span) -> ResolveResult<()> {
+        let Some(overloads) = self.overloads.get(name) else {
+            return Ok(());
+        };
+        let overload_count = overloads.len();
+        let mut viable = Vec::new();
+        let mut first_error = None;
+        for (overload, params) in overloads.iter().enumerate() {
+            match bind_args(name, params, args, span) {
+                Ok(sources) => {
+                    let mut filled = Vec::with_capacity(sources.len());
+                    let mut defaulted = Vec::new();
+                    for (index, source) in sources.into_iter().enumerate() {
+                        if let ArgSource::Default(param) = source {
+                            defaulted.push((index, param.name.clone()));
+                        }
+                        let value = source.value().clone();
+                        filled.push(Arg {
+                            name: None,
+                            span: value.span,
+                            value,
+                        });
+                    }
+                    viable.push((overload, filled, defaulted));
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if viable.is_empty() {
+            return Err(match first_error {
+                Some(e) if overload_count == 1 => e,
+                _ => ResolveError::new(
+                    format!("no overload of function '{name}' takes these arguments"),
+                    span,
+                ),
+            });
+        }
+
+        // A copied default may itself contain calls that need defaults.
+        for (overload, filled, defaulted) in &mut viable {
+            for (index, param_name) in defaulted.iter() {
+                let key = (name.to_string(), *overload, *index);
+                if self.expanding.contains(&key) {
+                    return Err(ResolveError::new(
+                        format!(
+                            "the default value of parameter '{param_name}' of function '{name}' depends on itself"
+                        ),
+                        filled[*index].span,
+                    ));
+                }
+                self.expanding.push(key);
+                let result = self.resolve_expr(&mut filled[*index].value);
+                self.expanding.pop();
+                result?;
+            }
+        }
+
+        if let [(_, filled, _)] = &mut viable[..] {
+            *args = std::mem::take(filled);
+        }
+        Ok(())
+    }
+}
+
+/// Finds the first variable an expression uses without declaring it itself.
+/// Callees and the enums before a `.` are items, not variables.
+struct FreeVars<'a> {
+    enums: &'a HashSet<&'a str>,
+    /// Variables declared so far, innermost scope last.
+    scopes: Vec<HashSet<&'a str>>,
+}
+
+impl<'a> FreeVars<'a> {
+    fn declare(&mut self, name: &'a str) {
+        self.scopes.last_mut().expect("inside a scope").insert(name);
+    }
+
+    fn scoped(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Option<(&'a str, Span)>,
+    ) -> Option<(&'a str, Span)> {
+        self.scopes.push(HashSet::new());
+        let found = f(self);
+        self.scopes.pop();
+        found
+    }
+
+    fn in_block(&mut self, block: &'a Block) -> Option<(&'a str, Span)> {
+        self.scoped(|vars| block.stmts.iter().find_map(|stmt| vars.in_stmt(stmt)))
+    }
+
+    fn in_stmt(&mut self, stmt: &'a Stmt) -> Option<(&'a str, Span)> {
+        match &stmt.kind {
+            StmtKind::VarDecl(decl) => {
+                let found = decl.init.as_ref().and_then(|init| self.in_expr(init));
+                self.declare(&decl.name);
+                found
+            }
+            StmtKind::Destructure(decl) => {
+                let found = self.in_expr(&decl.init);
+                for binding in &decl.bindings {
+                    self.declare(&binding.name);
+                }
+                found
+            }
+            StmtKind::Assign { target, value, .. } => {
+                self.in_expr(target).or_else(|| self.in_expr(value))
+            }
+            StmtKind::Increment(expr) | StmtKind::Decrement(expr) | StmtKind::Expr(expr) => {
+                self.in_expr(expr)
+            }
+            StmtKind::Return(values) => values.iter().find_map(|v| self.in_expr(v)),
+            StmtKind::Block(block) => self.in_block(block),
+            StmtKind::Loop(lp) => self.in_loop(lp),
+            StmtKind::Break { value, .. } => value.as_ref().and_then(|v| self.in_expr(v)),
+            StmtKind::Continue { .. } | StmtKind::Error => None,
+            StmtKind::If {
+                cond,
+                then_block,
+                else_branch,
+            } => self
+                .in_expr(cond)
+                .or_else(|| self.in_block(then_block))
+                .or_else(|| else_branch.as_ref().and_then(|e| self.in_stmt(e))),
+            StmtKind::Match(m) => self.in_match(m),
+        }
+    }
+
+    fn in_loop(&mut self, lp: &'a Loop) -> Option<(&'a str, Span)> {
+        // Variables declared in a `for` header are scoped to the loop.
+        self.scoped(|vars| {
+            let found = match &lp.kind {
+                LoopKind::While(cond) | LoopKind::DoWhile(cond) => vars.in_expr(cond),
+                LoopKind::For { init, cond, update } => init
+                    .as_ref()
+                    .and_then(|init| vars.in_stmt(init))
+                    .or_else(|| cond.as_ref().and_then(|cond| vars.in_expr(cond)))
+                    .or_else(|| update.as_ref().and_then(|update| vars.in_stmt(update))),
+                LoopKind::Infinite => None,
+            };
+            found
+                .or_else(|| vars.in_block(&lp.body))
+                .or_else(|| lp.else_block.as_ref().and_then(|b| vars.in_block(b)))
+        })
+        .or_else(|| lp.default.as_ref().and_then(|d| self.in_expr(d)))
+    }
+
+    fn in_match(&mut self, m: &'a Match) -> Option<(&'a str, Span)> {
+        self.in_expr(&m.scrutinee).or_else(|| {
+            m.arms.iter().find_map(|arm| {
+                self.scoped(|vars| {
+                    vars.bind_pattern(&arm.pattern);
+                    match &arm.body {
+                        ArmBody::Expr(e) => vars.in_expr(e),
+                        ArmBody::Block(block) => vars.in_block(block),
+                    }
+                })
+            })
+        })
+    }
+
+    fn bind_pattern(&mut self, pattern: &'a Pattern) {
+        match &pattern.kind {
+            PatternKind::Binding(name) => self.declare(name),
+            PatternKind::Variant { fields, .. } => {
+                fields.iter().for_each(|field| self.bind_pattern(field));
+            }
+            PatternKind::Wildcard | PatternKind::Int { .. } | PatternKind::Char(_) => {}
+        }
+    }
+
+    fn in_expr(&mut self, e: &'a Expr) -> Option<(&'a str, Span)> {
+        match &e.kind {
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::Str(_)
+            | ExprKind::ByteStr(_)
+            | ExprKind::Char(_) => None,
+            ExprKind::Ident(name) => {
+                let declared = self
+                    .scopes
+                    .iter()
+                    .any(|scope| scope.contains(name.as_str()));
+                (!declared).then_some((name.as_str(), e.span))
+            }
+            ExprKind::Binary { lhs, rhs, .. } => self.in_expr(lhs).or_else(|| self.in_expr(rhs)),
+            ExprKind::Unary { operand, .. } => self.in_expr(operand),
+            ExprKind::Call { callee, args, .. } => {
+                let found = match &callee.kind {
+                    ExprKind::Ident(_) => None,
+                    _ => self.in_expr(callee),
+                };
+                found.or_else(|| args.iter().find_map(|arg| self.in_expr(&arg.value)))
+            }
+            ExprKind::Loop(lp) => self.in_loop(lp),
+            ExprKind::If {
+                cond,
+                then_expr,
+                else_expr,
+            } => self
+                .in_expr(cond)
+                .or_else(|| self.in_expr(then_expr))
+                .or_else(|| self.in_expr(else_expr)),
+            ExprKind::StructLit { fields, .. } => {
+                fields.iter().find_map(|field| self.in_expr(&field.value))
+            }
+            ExprKind::Field { base, .. } => match &base.kind {
+                ExprKind::Ident(name) if self.enums.contains(name.as_str()) => None,
+                _ => self.in_expr(base),
+            },
+            ExprKind::Match(m) => self.in_match(m),
+        }
+    }
+}
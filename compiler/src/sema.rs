@@ -0,0 +1,1114 @@
+//! Semantic checks, run on a resolved program before codegen so `check`
+//! reports the same problems `compile` does.
+//!
+//! Types every function body the way codegen lowers it: from context where
+//! there is one, otherwise from the expression itself. That picks the
+//! overload each call refers to, which is recorded on the call along with
+//! its arguments in parameter order, so codegen only ever calls the chosen
+//! overload. Overloads are narrowed down by whether they can take the
+//! arguments at all, then by the types of the arguments whose type is
+//! known, then by the types the caller expects back.
+//!
+//! The patterns of every `match` are checked against the type they match,
+//! and a `match` is rejected unless they cover every value of it; arms that
+//! can never run are warned about.
+//!
+//! Every `break` must target an enclosing loop. In a loop used as a value it
+//! carries a value, and the values of all its `break`s and its default
+//! share one type; elsewhere it carries none.
+//!
+//! Mistakes outside these checks, such as a mismatched type or an unknown
+//! variable, are left for codegen to report: checking a function stops at
+//! the first one, and a program using an unknown type isn't checked at all.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{
+    Arg, ArmBody, Block, Expr, ExprKind, FieldInit, Function, Label, Loop, LoopKind, Match, Param,
+    Pattern, PatternKind, Program, Stmt, StmtKind, UnaryOp,
+};
+use crate::error::{SemaError, SemaResult, SemaWarning};
+use crate::exhaustiveness::{self, Pat, Space};
+use crate::resolve::bind_args;
+use crate::tokens::{FloatLiteral, IntLiteral, IntSuffix, Span};
+use crate::types::{FloatKind, Type};
+
+/// Checks every function of `program`, recording the overload each call
+/// picks. Returns the warnings raised, or the first error found.
+pub fn check_program(program: &mut Program) -> SemaResult<Vec<SemaWarning>> {
+    let Some(mut checker) = Checker::new(program) else {
+        return Ok(Vec::new());
+    };
+    // The functions of a name are its overloads in program order.
+    let mut overloads: HashMap<String, usize> = HashMap::new();
+    for f in &mut program.functions {
+        let index = overloads.entry(f.name.clone()).or_default();
+        let sig = checker.functions[&f.name][*index].clone();
+        *index += 1;
+        match checker.check_function(f, &sig) {
+            Ok(()) | Err(Stop::Untyped) => {}
+            Err(Stop::Error(e)) => return Err(e),
+        }
+    }
+    Ok(checker.warnings)
+}
+
+/// Why checking a function ended early.
+enum Stop {
+    /// A mistake this pass reports.
+    Error(SemaError),
+    /// A mistake codegen reports, after which nothing more is known.
+    Untyped,
+}
+
+impl From<SemaError> for Stop {
+    fn from(e: SemaError) -> Self {
+        Stop::Error(e)
+    }
+}
+
+type Check<T> = Result<T, Stop>;
+
+#[derive(Debug, Clone)]
+struct Signature {
+    /// The declared parameters, for binding named arguments and defaults.
+    decls: Vec<Param>,
+    params: Vec<Type>,
+    returns: Vec<Type>,
+}
+
+impl Signature {
+    /// `fn foo(u32 x, u32 y = ..) -> u32`, for diagnostics that list overloads.
+    fn describe(&self, name: &str) -> String {
+        let params: Vec<String> = self
+            .decls
+            .iter()
+            .zip(&self.params)
+            .map(|(decl, ty)| match decl.default {
+                Some(_) => format!("{ty} {} = ..", decl.name),
+                None => format!("{ty} {}", decl.name),
+            })
+            .collect();
+        let returns: Vec<String> = self.returns.iter().map(Type::to_string).collect();
+        format!("fn {name}({}) -> {}", params.join(", "), returns.join(", "))
+    }
+}
+
+/// An enclosing loop of the statement being checked.
+struct LoopTarget {
+    label: Option<String>,
+    /// Set when the loop is used as a value.
+    value: Option<LoopValue>,
+}
+
+/// The value of a loop used as a value.
+struct LoopValue {
+    /// Known from context, or from the first `break` that carries a value.
+    ty: Option<Type>,
+}
+
+struct Checker {
+    /// The fields of every struct by name, in declaration order.
+    structs: HashMap<String, Vec<(String, Type)>>,
+    /// The variants of every enum by name, each with its field types.
+    enums: HashMap<String, Vec<(String, Vec<Type>)>>,
+    /// The overloads of every function by name, in program order.
+    functions: HashMap<String, Vec<Signature>>,
+    /// Locals of the function being checked, innermost scope last.
+    scopes: Vec<HashMap<String, Type>>,
+    loops: Vec<LoopTarget>,
+    return_types: Vec<Type>,
+    warnings: Vec<SemaWarning>,
+}
+
+impl Checker {
+    /// The declarations of `program`, unless one names an unknown type.
+    fn new(program: &Program) -> Option<Self> {
+        let mut checker = Checker {
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            functions: HashMap::new(),
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            return_types: Vec::new(),
+            warnings: Vec::new(),
+        };
+        // Every name first, so fields can have the type of one declared later.
+        for decl in &program.structs {
+            checker.structs.insert(decl.name.clone(), Vec::new());
+        }
+        for decl in &program.enums {
+            checker.enums.insert(decl.name.clone(), Vec::new());
+        }
+        for decl in &program.structs {
+            let fields = decl
+                .fields
+                .iter()
+                .map(|f| Some((f.name.clone(), checker.resolve_type(&f.ty)?)))
+                .collect::<Option<_>>()?;
+            checker.structs.insert(decl.name.clone(), fields);
+        }
+        for decl in &program.enums {
+            let variants = decl
+                .variants
+                .iter()
+                .map(|v| {
+                    let fields = v
+                        .fields
+                        .iter()
+                        .map(|(ty, _)| checker.resolve_type(ty))
+                        .collect::<Option<_>>()?;
+                    Some((v.name.clone(), fields))
+                })
+                .collect::<Option<_>>()?;
+            checker.enums.insert(decl.name.clone(), variants);
+        }
+        for f in &program.functions {
+            let sig = Signature {
+                decls: f.params.clone(),
+                params: f
+                    .params
+                    .iter()
+                    .map(|p| checker.resolve_type(&p.ty))
+                    .collect::<Option<_>>()?,
+                returns: f
+                    .returns
+                    .iter()
+                    .map(|r| checker.resolve_type(&r.ty))
+                    .collect::<Option<_>>()?,
+            };
+            checker
+                .functions
+                .entry(f.name.clone())
+                .or_default()
+                .push(sig);
+        }
+        Some(checker)
+    }
+
+    fn resolve_type(&self, name: &str) -> Option<Type> {
+        if let Some(ty) = Type::from_name(name) {
+            return Some(ty);
+        }
+        if self.structs.contains_key(name) {
+            return Some(Type::Struct(Rc::from(name)));
+        }
+        self.enums
+            .contains_key(name)
+            .then(|| Type::Enum(Rc::from(name)))
+    }
+
+    fn check_function(&mut self, f: &mut Function, sig: &Signature) -> Check<()> {
+        // Parameters and named return values share the body's outermost scope.
+        self.scopes = vec![HashMap::new()];
+        for (param, ty) in f.params.iter().zip(&sig.params) {
+            self.declare_once(&param.name, ty)?;
+        }
+        for (ret, ty) in f.returns.iter().zip(&sig.returns) {
+            if let Some(name) = &ret.name {
+                self.declare_once(name, ty)?;
+            }
+        }
+        self.loops.clear();
+        self.return_types = sig.returns.clone();
+        self.check_stmts(&mut f.body.stmts)
+    }
+
+    /// Declares a local in the innermost scope, which must not have it yet.
+    fn declare_once(&mut self, name: &str, ty: &Type) -> Check<()> {
+        let scope = self.scopes.last_mut().expect("inside a function body");
+        match scope.insert(name.to_string(), ty.clone()) {
+            Some(_) => Err(Stop::Untyped),
+            None => Ok(()),
+        }
+    }
+
+    fn find_local(&self, name: &str) -> Option<Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+    }
+
+    fn scoped<T>(
+        &mut self,
+        scope: HashMap<String, Type>,
+        f: impl FnOnce(&mut Self) -> Check<T>,
+    ) -> Check<T> {
+        self.scopes.push(scope);
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn check_block(&mut self, block: &mut Block) -> Check<()> {
+        self.scoped(HashMap::new(), |checker| {
+            checker.check_stmts(&mut block.stmts)
+        })
+    }
+
+    fn check_stmts(&mut self, stmts: &mut [Stmt]) -> Check<()> {
+        stmts.iter_mut().try_for_each(|stmt| self.check_stmt(stmt))
+    }
+
+    fn check_stmt(&mut self, stmt: &mut Stmt) -> Check<()> {
+        let span = stmt.span;
+        match &mut stmt.kind {
+            StmtKind::VarDecl(decl) => {
+                let ty = self.resolve_type(&decl.ty).ok_or(Stop::Untyped)?;
+                let scope = self.scopes.last().expect("inside a function body");
+                if scope.contains_key(&decl.name) {
+                    return Err(Stop::Untyped);
+                }
+                // The initializer can't see the variable it initializes.
+                if let Some(init) = &mut decl.init {
+                    self.check_expr(init, Some(ty.clone()))?;
+                }
+                self.declare_once(&decl.name, &ty)
+            }
+            StmtKind::Destructure(decl) => {
+                let mut types = Vec::with_capacity(decl.bindings.len());
+                for (i, binding) in decl.bindings.iter().enumerate() {
+                    let scope = self.scopes.last().expect("inside a function body");
+                    if scope.contains_key(&binding.name)
+                        || decl.bindings[..i].iter().any(|b| b.name == binding.name)
+                    {
+                        return Err(Stop::Untyped);
+                    }
+                    types.push(self.resolve_type(&binding.ty).ok_or(Stop::Untyped)?);
+                }
+                self.check_multi_expr(&mut decl.init, &types)?;
+                for (binding, ty) in decl.bindings.iter().zip(&types) {
+                    self.declare_once(&binding.name, ty)?;
+                }
+                Ok(())
+            }
+            StmtKind::Assign { target, value, .. } => {
+                let ty = self.place_type(target)?.ok_or(Stop::Untyped)?;
+                self.check_expr(value, Some(ty))
+            }
+            StmtKind::Increment(target) | StmtKind::Decrement(target) => {
+                self.place_type(target)?.ok_or(Stop::Untyped)?;
+                Ok(())
+            }
+            StmtKind::Expr(expr) => {
+                self.typed_expr(expr, None)?;
+                Ok(())
+            }
+            StmtKind::Return(exprs) => {
+                let returns = self.return_types.clone();
+                let count = exprs.len();
+                match exprs.as_mut_slice() {
+                    [expr] if returns.len() > 1 => self.check_multi_expr(expr, &returns),
+                    _ if count != returns.len() => Err(Stop::Untyped),
+                    exprs => exprs
+                        .iter_mut()
+                        .zip(returns)
+                        .try_for_each(|(expr, ty)| self.check_expr(expr, Some(ty))),
+                }
+            }
+            StmtKind::Block(block) => self.check_block(block),
+            StmtKind::Loop(lp) => {
+                self.check_loop(lp, None)?;
+                Ok(())
+            }
+            StmtKind::Break { label, value } => {
+                let idx = self.resolve_loop(label.as_ref(), "break", span)?;
+                let slot_ty = self.loops[idx].value.as_ref().map(|v| v.ty.clone());
+                match (value, slot_ty) {
+                    (Some(value), Some(ty)) => {
+                        let found = self.typed_expr(value, ty)?;
+                        let slot = self.loops[idx].value.as_mut().expect("checked above");
+                        Ok(merge_value_type(&mut slot.ty, found, value.span)?)
+                    }
+                    (Some(value), None) => Err(Stop::Error(SemaError::new(
+                        "`break` with a value is only allowed in a loop used as a value",
+                        value.span,
+                    ))),
+                    (None, Some(_)) => Err(Stop::Error(SemaError::new(
+                        "`break` needs a value inside a loop used as a value",
+                        span,
+                    ))),
+                    (None, None) => Ok(()),
+                }
+            }
+            StmtKind::Continue { label } => {
+                self.resolve_loop(label.as_ref(), "continue", span)?;
+                Ok(())
+            }
+            StmtKind::Error => Err(Stop::Untyped),
+            StmtKind::If {
+                cond,
+                then_block,
+                else_branch,
+            } => {
+                self.check_expr(cond, Some(Type::Bool))?;
+                self.check_block(then_block)?;
+                match else_branch {
+                    Some(else_branch) => self.check_stmt(else_branch),
+                    None => Ok(()),
+                }
+            }
+            StmtKind::Match(m) => {
+                self.check_match(m, false, None)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Index in `self.loops` of the loop a `break`/`continue` targets.
+    fn resolve_loop(&self, label: Option<&Label>, keyword: &str, span: Span) -> SemaResult<usize> {
+        let found = match label {
+            None => self.loops.len().checked_sub(1),
+            Some(label) => self
+                .loops
+                .iter()
+                .rposition(|l| l.label.as_deref() == Some(label.name.as_str())),
+        };
+        found.ok_or_else(|| match label {
+            None => SemaError::new(format!("`{keyword}` outside of a loop"), span),
+            Some(label) => SemaError::new(
+                format!("use of undeclared label '{}'", label.name),
+                label.span,
+            ),
+        })
+    }
+
+    /// Checks a loop used as a value and gives the type of its value: the
+    /// context's, else its default's, else that of its first `break` value.
+    fn check_loop_expr(
+        &mut self,
+        lp: &mut Loop,
+        expected: Option<Type>,
+        span: Span,
+    ) -> Check<Type> {
+        let ty = expected.or_else(|| lp.default.as_deref().and_then(|d| self.natural_type(d)));
+        let value = self
+            .check_loop(lp, Some(LoopValue { ty }))?
+            .expect("a value loop keeps its value");
+        Ok(value.ty.ok_or_else(|| {
+            SemaError::new(
+                "cannot infer the type of a loop that never produces a value",
+                span,
+            )
+        })?)
+    }
+
+    /// Checks the parts of a loop in the order codegen lowers them, and
+    /// returns the value of a value loop.
+    fn check_loop(&mut self, lp: &mut Loop, value: Option<LoopValue>) -> Check<Option<LoopValue>> {
+        // A `for` header's declarations are scoped to the loop.
+        self.scoped(HashMap::new(), |checker| {
+            checker.check_loop_parts(lp, value)
+        })
+    }
+
+    fn check_loop_parts(
+        &mut self,
+        lp: &mut Loop,
+        value: Option<LoopValue>,
+    ) -> Check<Option<LoopValue>> {
+        if let LoopKind::For {
+            init: Some(init), ..
+        } = &mut lp.kind
+        {
+            self.check_stmt(init)?;
+        }
+        match &mut lp.kind {
+            LoopKind::While(cond)
+            | LoopKind::For {
+                cond: Some(cond), ..
+            } => self.check_expr(cond, Some(Type::Bool))?,
+            LoopKind::For { .. } | LoopKind::DoWhile(_) | LoopKind::Infinite => {}
+        }
+
+        self.loops.push(LoopTarget {
+            label: lp.label.as_ref().map(|l| l.name.clone()),
+            value,
+        });
+        let body = self.check_block(&mut lp.body);
+        let mut value = self.loops.pop().expect("pushed above").value;
+        body?;
+
+        match &mut lp.kind {
+            LoopKind::For {
+                update: Some(update),
+                ..
+            } => self.check_stmt(update)?,
+            LoopKind::DoWhile(cond) => self.check_expr(cond, Some(Type::Bool))?,
+            LoopKind::For { .. } | LoopKind::While(_) | LoopKind::Infinite => {}
+        }
+        if let Some(else_block) = &mut lp.else_block {
+            self.check_block(else_block)?;
+        }
+        if let (Some(default), Some(slot)) = (&mut lp.default, value.as_mut()) {
+            let found = self.typed_expr(default, slot.ty.clone())?;
+            merge_value_type(&mut slot.ty, found, default.span)?;
+        }
+        Ok(value)
+    }
+
+    /// Checks a `match`: its patterns against the scrutinee's type, that
+    /// they cover every value, and its arms. Arms that can never run are
+    /// warned about. With `value`, gives the type of the arms, typed by
+    /// `expected` or else by the first arm.
+    fn check_match(
+        &mut self,
+        m: &mut Match,
+        value: bool,
+        expected: Option<Type>,
+    ) -> Check<Option<Type>> {
+        let ty = self.typed_expr(&mut m.scrutinee, None)?;
+        let mut pats = Vec::with_capacity(m.arms.len());
+        let mut bindings = Vec::with_capacity(m.arms.len());
+        for arm in &m.arms {
+            let mut bound = Vec::new();
+            pats.push(self.check_pattern(&arm.pattern, &ty, &mut bound)?);
+            bindings.push(bound);
+        }
+        let space = self.pattern_space(&ty);
+        let missing = exhaustiveness::missing_patterns(&space, &pats);
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing.iter().map(|p| format!("`{p}`")).collect();
+            return Err(Stop::Error(SemaError::new(
+                format!(
+                    "non-exhaustive match on {ty}: {} not covered",
+                    join_list(&missing)
+                ),
+                m.scrutinee.span,
+            )));
+        }
+        for (i, arm) in m.arms.iter().enumerate() {
+            if !exhaustiveness::is_useful(&space, &pats[..i], &pats[i]) {
+                self.warnings
+                    .push(SemaWarning::new("unreachable pattern", arm.pattern.span));
+            }
+        }
+
+        let mut value_ty = expected;
+        for (arm, bound) in m.arms.iter_mut().zip(bindings) {
+            self.scoped(bound.into_iter().collect(), |checker| match &mut arm.body {
+                ArmBody::Block(block) => checker.check_block(block),
+                ArmBody::Expr(e) if value => {
+                    let found = checker.typed_expr(e, value_ty.clone())?;
+                    match &value_ty {
+                        Some(expected) if *expected != found => Err(Stop::Untyped),
+                        _ => {
+                            value_ty = Some(found);
+                            Ok(())
+                        }
+                    }
+                }
+                ArmBody::Expr(e) => {
+                    checker.typed_expr(e, None)?;
+                    Ok(())
+                }
+            })?;
+        }
+        if !value {
+            return Ok(None);
+        }
+        value_ty.map(Some).ok_or(Stop::Untyped)
+    }
+
+    /// Checks `pattern` against the type `ty` it matches, and adds the names
+    /// it binds, with their types, to `bindings`, which must not have them yet.
+    fn check_pattern(
+        &self,
+        pattern: &Pattern,
+        ty: &Type,
+        bindings: &mut Vec<(String, Type)>,
+    ) -> SemaResult<Pat> {
+        match &pattern.kind {
+            PatternKind::Wildcard => Ok(Pat::Wild),
+            PatternKind::Binding(name) => {
+                if bindings.iter().any(|(bound, _)| bound == name) {
+                    return Err(SemaError::new(
+                        format!("identifier '{name}' is bound more than once in the same pattern"),
+                        pattern.span,
+                    ));
+                }
+                bindings.push((name.clone(), ty.clone()));
+                Ok(Pat::Wild)
+            }
+            PatternKind::Int { .. } | PatternKind::Char(_) => {
+                Ok(Pat::Literal(pattern_literal(pattern, ty)?))
+            }
+            PatternKind::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                let Some(variants) = self.enums.get(enum_name) else {
+                    return Err(SemaError::new(
+                        format!("cannot find enum '{enum_name}' in this scope"),
+                        pattern.span,
+                    ));
+                };
+                if !matches!(ty, Type::Enum(name) if **name == **enum_name) {
+                    return Err(SemaError::new(
+                        format!("mismatched types: expected {ty}, found {enum_name}"),
+                        pattern.span,
+                    ));
+                }
+                let index = variants
+                    .iter()
+                    .position(|(name, _)| name == variant)
+                    .ok_or_else(|| {
+                        SemaError::new(
+                            format!("enum '{enum_name}' has no variant named '{variant}'"),
+                            pattern.span,
+                        )
+                    })?;
+                let field_types = &variants[index].1;
+                if fields.len() != field_types.len() {
+                    return Err(SemaError::new(
+                        format!(
+                            "variant '{enum_name}.{variant}' has {} field{} but the pattern has {}",
+                            field_types.len(),
+                            if field_types.len() == 1 { "" } else { "s" },
+                            fields.len(),
+                        ),
+                        pattern.span,
+                    ));
+                }
+                let fields = fields
+                    .iter()
+                    .zip(field_types)
+                    .map(|(field, ty)| self.check_pattern(field, ty, bindings))
+                    .collect::<SemaResult<Vec<_>>>()?;
+                Ok(Pat::Variant(index, fields))
+            }
+        }
+    }
+
+    /// The values of `ty` as the exhaustiveness check sees them.
+    fn pattern_space(&self, ty: &Type) -> Space {
+        let Type::Enum(name) = ty else {
+            return Space::Open;
+        };
+        let variants = self.enums[&**name]
+            .iter()
+            .map(|(variant, fields)| {
+                let fields = fields.iter().map(|ty| self.pattern_space(ty)).collect();
+                (variant.clone(), fields)
+            })
+            .collect();
+        Space::Enum {
+            name: name.to_string(),
+            variants,
+        }
+    }
+
+    fn variant_fields(&self, enum_name: &str, variant: &str) -> Option<&[Type]> {
+        self.enums
+            .get(enum_name)?
+            .iter()
+            .find(|(name, _)| name == variant)
+            .map(|(_, fields)| &fields[..])
+    }
+
+    /// Checks `e` and that its type is `expected`, when there is one.
+    fn check_expr(&mut self, e: &mut Expr, expected: Option<Type>) -> Check<()> {
+        let ty = self.typed_expr(e, expected.clone())?;
+        match expected {
+            Some(expected) if expected != ty => Err(Stop::Untyped),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks `e` and gives its type, using `expected` to type unsuffixed
+    /// literals and to pick overloads.
+    fn typed_expr(&mut self, e: &mut Expr, expected: Option<Type>) -> Check<Type> {
+        if let Some((enum_name, variant)) = self.variant_path(e) {
+            return self.check_variant(&enum_name, &variant, &mut []);
+        }
+        let span = e.span;
+        match &mut e.kind {
+            ExprKind::Int(lit) => int_type(lit, expected),
+            ExprKind::Float(lit) => float_type(lit, expected),
+            ExprKind::Str(_) | ExprKind::ByteStr(_) => Ok(Type::Str),
+            ExprKind::Char(_) => Ok(Type::Char),
+            ExprKind::Ident(name) => self.find_local(name).ok_or(Stop::Untyped),
+            ExprKind::Binary { op, lhs, rhs } if op.is_logical() => {
+                self.check_expr(lhs, Some(Type::Bool))?;
+                self.check_expr(rhs, Some(Type::Bool))?;
+                Ok(Type::Bool)
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                // Operands share one type; take it from context, then from either side.
+                let operand_ty = if op.is_comparison() {
+                    self.natural_type(lhs).or_else(|| self.natural_type(rhs))
+                } else {
+                    expected
+                        .or_else(|| self.natural_type(lhs))
+                        .or_else(|| self.natural_type(rhs))
+                };
+                let ty = self.typed_expr(lhs, operand_ty)?;
+                self.check_expr(rhs, Some(ty.clone()))?;
+                if op.unchecked().is_some() {
+                    return Err(Stop::Untyped);
+                }
+                Ok(if op.is_comparison() { Type::Bool } else { ty })
+            }
+            ExprKind::Unary { op, operand } => {
+                let operand_ty = match op {
+                    UnaryOp::Not => Some(Type::Bool),
+                    UnaryOp::Neg | UnaryOp::BitNot => {
+                        expected.or_else(|| self.natural_type(operand))
+                    }
+                };
+                self.typed_expr(operand, operand_ty)
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                overload,
+            } => {
+                if let Some((enum_name, variant)) = self.variant_path(callee) {
+                    return self.check_variant(&enum_name, &variant, args);
+                }
+                let expected = expected.map(|ty| [ty]);
+                let returns = self.check_call(
+                    callee,
+                    args,
+                    overload,
+                    expected.as_ref().map(|e| &e[..]),
+                    span,
+                )?;
+                match <[Type; 1]>::try_from(returns) {
+                    Ok([ty]) => Ok(ty),
+                    Err(_) => Err(Stop::Untyped),
+                }
+            }
+            ExprKind::If {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                self.check_expr(cond, Some(Type::Bool))?;
+                // Both arms share a type: the context's, else whichever arm knows its own.
+                let arm_ty = expected
+                    .or_else(|| self.natural_type(then_expr))
+                    .or_else(|| self.natural_type(else_expr));
+                let then_ty = self.typed_expr(then_expr, arm_ty)?;
+                self.check_expr(else_expr, Some(then_ty.clone()))?;
+                Ok(then_ty)
+            }
+            ExprKind::Loop(lp) => self.check_loop_expr(lp, expected, span),
+            ExprKind::StructLit { name, fields } => self.check_struct_lit(name, fields),
+            ExprKind::Match(m) => Ok(self
+                .check_match(m, true, expected)?
+                .expect("a match used as a value has one")),
+            ExprKind::Field { base, name, .. } => {
+                let ty = self.typed_expr(base, None)?;
+                self.field(&ty, name).ok_or(Stop::Untyped)
+            }
+        }
+    }
+
+    /// Checks the values of a struct literal against its fields.
+    fn check_struct_lit(&mut self, name: &str, inits: &mut [FieldInit]) -> Check<Type> {
+        let fields = self.structs.get(name).cloned().ok_or(Stop::Untyped)?;
+        let mut given = vec![false; fields.len()];
+        for init in inits {
+            let index = fields
+                .iter()
+                .position(|(f, _)| *f == init.name)
+                .ok_or(Stop::Untyped)?;
+            if std::mem::replace(&mut given[index], true) {
+                return Err(Stop::Untyped);
+            }
+            self.check_expr(&mut init.value, Some(fields[index].1.clone()))?;
+        }
+        if given.contains(&false) {
+            return Err(Stop::Untyped);
+        }
+        Ok(Type::Struct(Rc::from(name)))
+    }
+
+    /// `Enum.Variant` as `(enum, variant)`, unless the name before the `.`
+    /// is a local rather than an enum.
+    fn variant_path(&self, e: &Expr) -> Option<(String, String)> {
+        let ExprKind::Field { base, name, .. } = &e.kind else {
+            return None;
+        };
+        let ExprKind::Ident(enum_name) = &base.kind else {
+            return None;
+        };
+        (self.enums.contains_key(enum_name) && self.find_local(enum_name).is_none())
+            .then(|| (enum_name.clone(), name.clone()))
+    }
+
+    /// Checks the values of `Enum.Variant(args)` against its fields.
+    fn check_variant(&mut self, enum_name: &str, variant: &str, args: &mut [Arg]) -> Check<Type> {
+        let fields = self
+            .variant_fields(enum_name, variant)
+            .ok_or(Stop::Untyped)?
+            .to_vec();
+        if args.iter().any(|a| a.name.is_some()) || args.len() != fields.len() {
+            return Err(Stop::Untyped);
+        }
+        for (arg, ty) in args.iter_mut().zip(fields) {
+            self.check_expr(&mut arg.value, Some(ty))?;
+        }
+        Ok(Type::Enum(Rc::from(enum_name)))
+    }
+
+    /// The type of `place` when it names a local or a field of one.
+    fn place_type(&self, place: &Expr) -> Check<Option<Type>> {
+        match &place.kind {
+            ExprKind::Ident(name) => self.find_local(name).map(Some).ok_or(Stop::Untyped),
+            ExprKind::Field { base, name, .. } => match self.place_type(base)? {
+                Some(base) => self.field(&base, name).map(Some).ok_or(Stop::Untyped),
+                None => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Type of field `name` of a value of type `ty`.
+    fn field(&self, ty: &Type, name: &str) -> Option<Type> {
+        let Type::Struct(struct_name) = ty else {
+            return None;
+        };
+        self.structs[&**struct_name]
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, ty)| ty.clone())
+    }
+
+    /// Picks the overload a call refers to, records it on the call with the
+    /// arguments in parameter order, checks the arguments against it and
+    /// returns its return types.
+    fn check_call(
+        &mut self,
+        callee: &Expr,
+        args: &mut Vec<Arg>,
+        overload: &mut Option<usize>,
+        expected: Option<&[Type]>,
+        span: Span,
+    ) -> Check<Vec<Type>> {
+        let (index, values) = self.select_overload(callee, args, expected, span)?;
+        let ExprKind::Ident(name) = &callee.kind else {
+            unreachable!("only named functions are called");
+        };
+        let sig = self.functions[name][index].clone();
+        *overload = Some(index);
+        *args = values
+            .into_iter()
+            .map(|value| Arg {
+                name: None,
+                span: value.span,
+                value,
+            })
+            .collect();
+        for (arg, ty) in args.iter_mut().zip(sig.params) {
+            self.check_expr(&mut arg.value, Some(ty))?;
+        }
+        Ok(sig.returns)
+    }
+
+    /// The index of the overload a call refers to among the functions of its
+    /// name, with the call's arguments in parameter order.
+    fn select_overload(
+        &self,
+        callee: &Expr,
+        args: &[Arg],
+        expected: Option<&[Type]>,
+        span: Span,
+    ) -> SemaResult<(usize, Vec<Expr>)> {
+        let ExprKind::Ident(name) = &callee.kind else {
+            return Err(SemaError::new(
+                "only named functions can be called",
+                callee.span,
+            ));
+        };
+        let overloads = self.functions.get(name).ok_or_else(|| {
+            SemaError::new(
+                format!("cannot find function '{name}' in this scope"),
+                callee.span,
+            )
+        })?;
+
+        let mut viable = Vec::new();
+        for (index, sig) in overloads.iter().enumerate() {
+            match bind_args(name, &sig.decls, args, span) {
+                Ok(sources) => viable.push((index, sig, sources)),
+                Err(e) if overloads.len() == 1 => return Err(SemaError::new(e.message, e.span)),
+                Err(_) => {}
+            }
+        }
+        if viable.is_empty() {
+            return Err(no_overload(name, "takes these arguments", overloads, span));
+        }
+
+        viable.retain(|(_, sig, sources)| {
+            sources
+                .iter()
+                .zip(&sig.params)
+                .all(|(source, ty)| self.fits(source.value(), ty))
+        });
+        if viable.is_empty() {
+            return Err(no_overload(
+                name,
+                "matches the argument types",
+                overloads,
+                span,
+            ));
+        }
+        if let Some(expected) = expected
+            && viable.len() > 1
+            && viable.iter().any(|(_, sig, _)| sig.returns == expected)
+        {
+            viable.retain(|(_, sig, _)| sig.returns == expected);
+        }
+
+        match &viable[..] {
+            [(index, _, sources)] => {
+                Ok((*index, sources.iter().map(|s| s.value().clone()).collect()))
+            }
+            _ => {
+                let candidates: Vec<String> = viable
+                    .iter()
+                    .map(|(_, sig, _)| format!("`{}`", sig.describe(name)))
+                    .collect();
+                Err(SemaError::new(
+                    format!(
+                        "call to function '{name}' is ambiguous between {}; give the arguments explicit types",
+                        join_list(&candidates)
+                    ),
+                    span,
+                ))
+            }
+        }
+    }
+
+    /// Whether `e` can be used as a `ty` without knowing more: its type is
+    /// `ty`, or it is an untyped literal that can take that type.
+    fn fits(&self, e: &Expr, ty: &Type) -> bool {
+        if let Some(natural) = self.natural_type(e) {
+            return natural == *ty;
+        }
+        match &e.kind {
+            ExprKind::Int(_) => matches!(ty, Type::Int { .. }),
+            ExprKind::Float(_) => matches!(ty, Type::Float(_)),
+            ExprKind::Unary { operand, .. } => self.fits(operand, ty),
+            ExprKind::Binary { lhs, rhs, .. } => self.fits(lhs, ty) && self.fits(rhs, ty),
+            ExprKind::If {
+                then_expr,
+                else_expr,
+                ..
+            } => self.fits(then_expr, ty) && self.fits(else_expr, ty),
+            _ => true,
+        }
+    }
+
+    /// Type an expression has on its own, without outside context.
+    fn natural_type(&self, e: &Expr) -> Option<Type> {
+        match &e.kind {
+            ExprKind::Int(lit) => lit.suffix.map(Type::from),
+            ExprKind::Float(lit) => lit.suffix.map(Type::from),
+            ExprKind::Binary { op, .. } if op.is_comparison() || op.is_logical() => {
+                Some(Type::Bool)
+            }
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.natural_type(lhs).or_else(|| self.natural_type(rhs))
+            }
+            ExprKind::Unary {
+                op: UnaryOp::Not, ..
+            } => Some(Type::Bool),
+            ExprKind::Unary { operand, .. } => self.natural_type(operand),
+            ExprKind::If {
+                then_expr,
+                else_expr,
+                ..
+            } => self
+                .natural_type(then_expr)
+                .or_else(|| self.natural_type(else_expr)),
+            ExprKind::Loop(lp) => lp.default.as_deref().and_then(|d| self.natural_type(d)),
+            ExprKind::Match(m) => m.arms.iter().find_map(|arm| match &arm.body {
+                ArmBody::Expr(e) => self.natural_type(e),
+                ArmBody::Block(_) => None,
+            }),
+            ExprKind::Call { callee, .. } if self.variant_path(callee).is_some() => {
+                self.natural_type(callee)
+            }
+            ExprKind::Call { callee, args, .. } => {
+                let (index, _) = self.select_overload(callee, args, None, e.span).ok()?;
+                let ExprKind::Ident(name) = &callee.kind else {
+                    return None;
+                };
+                match &self.functions[name][index].returns[..] {
+                    [ty] => Some(ty.clone()),
+                    _ => None,
+                }
+            }
+            ExprKind::Str(_) | ExprKind::ByteStr(_) => Some(Type::Str),
+            ExprKind::Char(_) => Some(Type::Char),
+            ExprKind::Ident(name) => self.find_local(name),
+            ExprKind::StructLit { name, .. } => self
+                .structs
+                .contains_key(name)
+                .then(|| Type::Struct(Rc::from(name.as_str()))),
+            ExprKind::Field { .. } => match self.variant_path(e) {
+                Some((enum_name, _)) => Some(Type::Enum(Rc::from(enum_name))),
+                None => {
+                    let ExprKind::Field { base, name, .. } = &e.kind else {
+                        unreachable!("matched above");
+                    };
+                    self.field(&self.natural_type(base)?, name)
+                }
+            },
+        }
+    }
+
+    /// Checks an expression yielding several values: a call to a function
+    /// with several return values, or a checked operator.
+    fn check_multi_expr(&mut self, e: &mut Expr, expected: &[Type]) -> Check<()> {
+        let span = e.span;
+        let found = match &mut e.kind {
+            ExprKind::Call {
+                callee,
+                args,
+                overload,
+            } => self.check_call(callee, args, overload, Some(expected), span)?,
+            ExprKind::Binary { op, lhs, rhs } if op.unchecked().is_some() => {
+                let ty = self.typed_expr(lhs, Some(expected[0].clone()))?;
+                self.check_expr(rhs, Some(ty.clone()))?;
+                vec![ty, Type::Bool]
+            }
+            _ => return Err(Stop::Untyped),
+        };
+        if found != expected {
+            return Err(Stop::Untyped);
+        }
+        Ok(())
+    }
+}
+
+/// Adds a value of type `found`, at `span`, to the values of a loop used as
+/// a value, whose type is `ty` once known.
+fn merge_value_type(ty: &mut Option<Type>, found: Type, span: Span) -> SemaResult<()> {
+    match ty {
+        Some(expected) if *expected != found => Err(SemaError::new(
+            format!("mismatched types: expected {expected}, found {found}"),
+            span,
+        )),
+        _ => {
+            *ty = Some(found);
+            Ok(())
+        }
+    }
+}
+
+/// The bits of the value an integer or char pattern matches, as a value of
+/// the matched type `ty` stores them.
+fn pattern_literal(pattern: &Pattern, ty: &Type) -> SemaResult<u128> {
+    match &pattern.kind {
+        PatternKind::Int { negative, lit } => {
+            let suffix = match (lit.suffix, ty) {
+                (Some(suffix), _) => suffix,
+                (None, Type::Int { signed, bits }) => IntSuffix {
+                    signed: *signed,
+                    bits: *bits,
+                },
+                (None, other) => {
+                    return Err(SemaError::new(
+                        format!("mismatched types: expected {other}, found integer literal"),
+                        pattern.span,
+                    ));
+                }
+            };
+            if lit.value > suffix.max_literal() {
+                return Err(SemaError::new(
+                    format!(
+                        "integer literal {} out of range for {}",
+                        lit.value,
+                        suffix.type_name()
+                    ),
+                    pattern.span,
+                ));
+            }
+            let found = Type::from(suffix);
+            if found != *ty {
+                return Err(SemaError::new(
+                    format!("mismatched types: expected {ty}, found {found}"),
+                    pattern.span,
+                ));
+            }
+            if *negative && !suffix.signed {
+                return Err(SemaError::new(
+                    format!("cannot apply `-` to {ty}"),
+                    pattern.span,
+                ));
+            }
+            let value = if *negative {
+                lit.value.wrapping_neg()
+            } else {
+                lit.value
+            };
+            Ok(value & (u128::MAX >> (128 - suffix.bits)))
+        }
+        PatternKind::Char(c) => {
+            if *ty != Type::Char {
+                return Err(SemaError::new(
+                    format!("mismatched types: expected {ty}, found char"),
+                    pattern.span,
+                ));
+            }
+            Ok(*c as u128)
+        }
+        _ => unreachable!("only literal patterns compare with a constant"),
+    }
+}
+
+/// The type of an integer literal: its suffix's, else the expected integer
+/// type, else `i32`.
+fn int_type(lit: &IntLiteral, expected: Option<Type>) -> Check<Type> {
+    match (lit.suffix, expected) {
+        (Some(suffix), _) => Ok(Type::from(suffix)),
+        (None, Some(ty @ Type::Int { .. })) => Ok(ty),
+        (None, Some(_)) => Err(Stop::Untyped),
+        (None, None) => Ok(Type::Int {
+            signed: true,
+            bits: 32,
+        }),
+    }
+}
+
+/// The type of a float literal: its suffix's, else the expected float type,
+/// else `f64`.
+fn float_type(lit: &FloatLiteral, expected: Option<Type>) -> Check<Type> {
+    match (lit.suffix, expected) {
+        (Some(kind), _) => Ok(Type::Float(kind)),
+        (None, Some(ty @ Type::Float(_))) => Ok(ty),
+        (None, Some(_)) => Err(Stop::Untyped),
+        (None, None) => Ok(Type::Float(FloatKind::F64)),
+    }
+}
+
+fn no_overload(name: &str, what: &str, overloads: &[Signature], span: Span) -> SemaError {
+    let candidates: Vec<String> = overloads
+        .iter()
+        .map(|sig| format!("`{}`", sig.describe(name)))
+        .collect();
+    SemaError::new(
+        format!(
+            "no overload of function '{name}' {what}; candidates are {}",
+            join_list(&candidates)
+        ),
+        span,
+    )
+}
+
+/// `a`, `a and b`, `a, b and c`.
+fn join_list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [init @ .., last] => format!("{} and {last}", init.join(", ")),
+    }
+}
@@ -2,28 +2,22 @@ use crate::config::{
     CheckConfig, CheckEmitKind, CodeModel, ColorChoice, CompileConfig, CompileEmitKind, CrateType,
     DebugInfo, Edition, ErrorFormat, LtoMode, OptLevel, RelocationModel, StopAfter,
 };
+use crate::source_map::SourceMap;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone)]
-pub struct SourceFile {
-    pub path: PathBuf,
-    pub content: String,
-}
-
-fn load_source_files(paths: Vec<PathBuf>) -> Result<Vec<SourceFile>, String> {
-    paths
-        .into_iter()
-        .map(|path| {
-            let content = std::fs::read_to_string(&path)
-                .map_err(|e| format!("couldn't read {:?}: {}", path, e))?;
-            Ok(SourceFile { path, content })
-        })
-        .collect()
+fn load_source_files(paths: Vec<PathBuf>) -> Result<SourceMap, String> {
+    let mut source_map = SourceMap::new();
+    for path in paths {
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("couldn't read {:?}: {}", path, e))?;
+        source_map.add_file(path, content);
+    }
+    Ok(source_map)
 }
 
 #[derive(Debug, Clone)]
 pub struct Session {
-    pub source: Vec<SourceFile>,
+    pub source_map: SourceMap,
     pub crate_name: Option<String>,
     pub crate_type: CrateType,
     pub edition: Edition,
@@ -63,7 +57,7 @@ pub struct Session {
 impl Session {
     pub fn from_compile_config(config: CompileConfig) -> Result<Self, String> {
         Ok(Self {
-            source: load_source_files(config.source)?,
+            source_map: load_source_files(config.source)?,
             crate_name: config.crate_name,
             crate_type: config.crate_type,
             edition: config.edition,
@@ -103,7 +97,7 @@ impl Session {
 
     pub fn from_check_config(config: CheckConfig) -> Result<Self, String> {
         Ok(Self {
-            source: load_source_files(config.source)?,
+            source_map: load_source_files(config.source)?,
             crate_name: config.crate_name,
             crate_type: config.crate_type,
             edition: config.edition,
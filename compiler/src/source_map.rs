@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+
+use crate::tokens::Span;
+
+/// Index of a file in its `SourceMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(pub usize);
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub id: FileId,
+    pub path: PathBuf,
+    pub content: String,
+    /// Position of the first byte of `content` in the source map.
+    /// Spans of this file's tokens are shifted by it, so one `Span`
+    /// identifies both the file and the bytes within it.
+    pub start_pos: usize,
+    /// Byte offsets (relative to `content`) where each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    /// Position one past the last byte, still owned by this file so that
+    /// end-of-input spans resolve to it.
+    pub fn end_pos(&self) -> usize {
+        self.start_pos + self.content.len()
+    }
+
+    fn contains(&self, pos: usize) -> bool {
+        (self.start_pos..=self.end_pos()).contains(&pos)
+    }
+
+    /// 0-based line index and byte offset of its start, for a relative offset.
+    fn line_of(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line, self.line_starts[line])
+    }
+}
+
+/// A resolved position, displayed as `path:line:column` (both 1-based).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub path: &'a Path,
+    pub line: usize,
+    /// Counted in characters, not bytes.
+    pub column: usize,
+}
+
+impl std::fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+/// All source files of a session, laid out one after the other in a single
+/// position space so that any `Span` can be traced back to its file.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, path: PathBuf, content: String) -> FileId {
+        let id = FileId(self.files.len());
+        // Leave a one-byte gap so a file's end position is never the next file's start.
+        let start_pos = self.files.last().map_or(0, |f| f.end_pos() + 1);
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        self.files.push(SourceFile {
+            id,
+            path,
+            content,
+            start_pos,
+            line_starts,
+        });
+        id
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0]
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// File containing the start of `span`.
+    pub fn lookup_file(&self, span: Span) -> Option<&SourceFile> {
+        let idx = self
+            .files
+            .partition_point(|f| f.start_pos <= span.start)
+            .checked_sub(1)?;
+        let file = &self.files[idx];
+        file.contains(span.start).then_some(file)
+    }
+
+    /// Location of the start of `span`.
+    pub fn location(&self, span: Span) -> Option<Location<'_>> {
+        let file = self.lookup_file(span)?;
+        let offset = span.start - file.start_pos;
+        let (line, line_start) = file.line_of(offset);
+        Some(Location {
+            path: &file.path,
+            line: line + 1,
+            column: file.content[line_start..offset].chars().count() + 1,
+        })
+    }
+
+    /// Full text of the line where `span` starts, without its line break.
+    pub fn source_line(&self, span: Span) -> Option<&str> {
+        let file = self.lookup_file(span)?;
+        let (_, line_start) = file.line_of(span.start - file.start_pos);
+        let rest = &file.content[line_start..];
+        let line = rest.split('\n').next().unwrap_or(rest);
+        Some(line.strip_suffix('\r').unwrap_or(line))
+    }
+
+    /// Renders a diagnostic with its location and the offending source line:
+    ///
+    /// ```text
+    /// error: unknown character
+    ///  --> tests/main.xe:4:12
+    ///   |
+    /// 4 |     return @;
+    ///   |            ^
+    /// ```
+    pub fn render(&self, level: &str, message: &str, span: Option<Span>) -> String {
+        let mut out = format!("{level}: {message}");
+        let Some((span, location)) = span.and_then(|s| Some((s, self.location(s)?))) else {
+            return out;
+        };
+        let line = self.source_line(span).unwrap_or_default();
+
+        // Underline the span on its first line, at least one column wide.
+        let column = location.column - 1;
+        let line_len = line.chars().count();
+        let width = self.lookup_file(span).map_or(1, |file| {
+            let text = &file.content[span.start - file.start_pos..];
+            let len = text[..(span.end - span.start).min(text.len())]
+                .chars()
+                .count();
+            len.min(line_len.saturating_sub(column)).max(1)
+        });
+
+        let gutter = location.line.to_string().len();
+        let pad = " ".repeat(gutter);
+        out.push_str(&format!("\n{pad}--> {location}\n{pad} |\n"));
+        out.push_str(&format!("{} | {line}\n", location.line));
+        // Keep tabs so the caret lines up with the source as displayed.
+        let indent: String = line
+            .chars()
+            .take(column)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        out.push_str(&format!("{pad} | {indent}{}", "^".repeat(width)));
+        out
+    }
+}
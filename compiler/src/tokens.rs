@@ -1,5 +1,8 @@
-use crate::error::{ParseError, ParseResult};
+use crate::error::{LexError, LexErrorKind, LexResult, LexWarning, ParseError, ParseResult};
+use crate::types::FloatKind;
 use logos::Logos;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::MixedScript;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Span {
@@ -7,6 +10,24 @@ pub struct Span {
     pub end: usize,
 }
 
+impl Span {
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Moves the span by `base` bytes, e.g. from file-relative to source-map positions.
+    pub fn shifted(self, base: usize) -> Span {
+        Span {
+            start: self.start + base,
+            end: self.end + base,
+        }
+    }
+}
+
 impl From<std::ops::Range<usize>> for Span {
     fn from(r: std::ops::Range<usize>) -> Self {
         Self {
@@ -25,6 +46,17 @@ pub enum TokenKind {
     Else,
     While,
     For,
+    Do,
+    Loop,
+    Break,
+    Continue,
+    Struct,
+    Enum,
+    Match,
+    Import,
+    As,
+    Pub,
+    Entry,
     // Delimiters
     LParen,
     RParen,
@@ -36,14 +68,51 @@ pub enum TokenKind {
     Semicolon,
     Colon,
     Period,
+    /// `#`, which starts an attribute: `#[packed]`.
+    Hash,
     // Multi-char operators
     Arrow,
+    /// `=>`, between a `match` arm's pattern and its body.
+    FatArrow,
     EqEq,
     NotEq,
     LtEq,
     GtEq,
     AndAnd,
     OrOr,
+    StarStar,
+    Shl,
+    Shr,
+    CaretCaret,
+    PlusEq,
+    MinusEq,
+    StarEq,
+    SlashEq,
+    PercentEq,
+    StarStarEq,
+    AmpEq,
+    PipeEq,
+    CaretEq,
+    ShlEq,
+    ShrEq,
+    PlusPlus,
+    MinusMinus,
+    // Overflow-mode operators: `%` wrapping, `|` saturating, `?` checked
+    PlusWrap,
+    PlusSat,
+    PlusChecked,
+    PlusWrapChecked,
+    PlusSatChecked,
+    MinusWrap,
+    MinusSat,
+    MinusChecked,
+    MinusWrapChecked,
+    MinusSatChecked,
+    StarWrap,
+    StarSat,
+    StarChecked,
+    StarWrapChecked,
+    StarSatChecked,
     // Single-char operators
     Eq,
     Lt,
@@ -53,10 +122,31 @@ pub enum TokenKind {
     Star,
     Slash,
     Bang,
+    Percent,
+    Amp,
+    Pipe,
+    Caret,
+    Tilde,
     // Value-bearing
     Ident,
     Int,
+    Float,
     Str,
+    ByteStr,
+    Char,
+    // Doc comments, kept for documentation tooling; the parser skips them.
+    /// `/// ...`, documents the item that follows.
+    DocComment,
+    /// `//! ...`, documents the enclosing item or file.
+    InnerDocComment,
+    /// Source that failed to lex; the matching `LexError` says why.
+    Error,
+}
+
+impl TokenKind {
+    pub fn is_doc_comment(self) -> bool {
+        matches!(self, TokenKind::DocComment | TokenKind::InnerDocComment)
+    }
 }
 
 impl AsRef<[TokenKind]> for TokenKind {
@@ -65,11 +155,87 @@ impl AsRef<[TokenKind]> for TokenKind {
     }
 }
 
+/// Type suffix of an integer literal, e.g. the `u8` in `42u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntSuffix {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+impl IntSuffix {
+    /// Largest width accepted for `iN`/`uN` suffixes.
+    pub const MAX_BITS: u32 = 128;
+
+    fn parse(s: &str) -> Option<Self> {
+        let signed = match s.as_bytes().first()? {
+            b'i' => true,
+            b'u' => false,
+            _ => return None,
+        };
+        let bits = s[1..].parse::<u32>().ok()?;
+        if bits == 0 || bits > Self::MAX_BITS {
+            return None;
+        }
+        Some(Self { signed, bits })
+    }
+
+    /// Largest literal magnitude accepted for this type. Signed types allow
+    /// one past their maximum so that `-128i8` can be written as `-` `128i8`.
+    pub fn max_literal(&self) -> u128 {
+        let magnitude_bits = if self.signed {
+            self.bits - 1
+        } else {
+            self.bits
+        };
+        if magnitude_bits >= 128 {
+            u128::MAX
+        } else if self.signed {
+            1u128 << magnitude_bits
+        } else {
+            (1u128 << magnitude_bits) - 1
+        }
+    }
+
+    /// Name of the type the suffix refers to, e.g. `"u8"`.
+    pub fn type_name(&self) -> String {
+        format!("{}{}", if self.signed { 'i' } else { 'u' }, self.bits)
+    }
+}
+
+/// Integer literal value. Stored as `u128` so literals valid for `u64`/`u128`
+/// are not rejected by the lexer; range checks against the final type happen later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntLiteral {
+    pub value: u128,
+    pub suffix: Option<IntSuffix>,
+}
+
+/// Floating-point literal. The normalized source text (no `_`, no suffix) is kept
+/// so that formats wider than `f64` can be built without losing precision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatLiteral {
+    pub text: String,
+    pub suffix: Option<FloatKind>,
+}
+
+impl FloatLiteral {
+    pub fn value(&self) -> f64 {
+        self.text
+            .parse()
+            .expect("float literal text is validated by the lexer")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenValue {
     Ident(String),
-    Int(i64),
+    Int(IntLiteral),
+    Float(FloatLiteral),
     Str(String),
+    ByteStr(Vec<u8>),
+    Char(char),
+    /// Doc comment text after the `///` or `//!` marker.
+    Doc(String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -87,22 +253,64 @@ impl Token {
         }
     }
 
-    pub fn int_value(&self) -> ParseResult<i64> {
+    pub fn int_value(&self) -> ParseResult<u128> {
+        self.int_literal().map(|lit| lit.value)
+    }
+
+    pub fn int_literal(&self) -> ParseResult<IntLiteral> {
         match &self.value {
-            Some(TokenValue::Int(v)) => Ok(*v),
+            Some(TokenValue::Int(lit)) => Ok(*lit),
             _ => Err(ParseError::new("expected integer value", self.span)),
         }
     }
 
+    pub fn float_literal(&self) -> ParseResult<&FloatLiteral> {
+        match &self.value {
+            Some(TokenValue::Float(lit)) => Ok(lit),
+            _ => Err(ParseError::new("expected float value", self.span)),
+        }
+    }
+
     pub fn str_value(&self) -> ParseResult<&str> {
         match &self.value {
             Some(TokenValue::Str(s)) => Ok(s.as_str()),
             _ => Err(ParseError::new("expected string value", self.span)),
         }
     }
+
+    pub fn byte_str_value(&self) -> ParseResult<&[u8]> {
+        match &self.value {
+            Some(TokenValue::ByteStr(b)) => Ok(b.as_slice()),
+            _ => Err(ParseError::new("expected byte string value", self.span)),
+        }
+    }
+
+    pub fn doc_value(&self) -> ParseResult<&str> {
+        match &self.value {
+            Some(TokenValue::Doc(s)) => Ok(s.as_str()),
+            _ => Err(ParseError::new("expected doc comment value", self.span)),
+        }
+    }
+
+    pub fn char_value(&self) -> ParseResult<char> {
+        match &self.value {
+            Some(TokenValue::Char(c)) => Ok(*c),
+            _ => Err(ParseError::new("expected char value", self.span)),
+        }
+    }
+}
+
+/// Lexer state shared with the Logos callbacks.
+#[derive(Debug, Default)]
+struct LexExtras {
+    /// Narrower span for the current error when it is not the whole token,
+    /// e.g. the bad escape inside a string literal.
+    error_span: Option<Span>,
+    warnings: Vec<LexWarning>,
 }
 
 #[derive(Logos, Debug, Clone, PartialEq)]
+#[logos(error = LexErrorKind, extras = LexExtras)]
 #[logos(skip r"[ \t\r\n\f]+")]
 enum RawKind {
     // ---------- Keywords ----------
@@ -118,6 +326,28 @@ enum RawKind {
     While,
     #[token("for")]
     For,
+    #[token("do")]
+    Do,
+    #[token("loop")]
+    Loop,
+    #[token("break")]
+    Break,
+    #[token("continue")]
+    Continue,
+    #[token("struct")]
+    Struct,
+    #[token("enum")]
+    Enum,
+    #[token("match")]
+    Match,
+    #[token("import")]
+    Import,
+    #[token("as")]
+    As,
+    #[token("pub")]
+    Pub,
+    #[token("entry")]
+    Entry,
 
     // ---------- Delimiters ----------
     #[token("(")]
@@ -141,10 +371,14 @@ enum RawKind {
     Colon,
     #[token(".")]
     Period,
+    #[token("#")]
+    Hash,
 
     // ---------- Multi-char operators (put before single-char) ----------
     #[token("->")]
     Arrow,
+    #[token("=>")]
+    FatArrow,
 
     #[token("==")]
     EqEq,
@@ -160,6 +394,73 @@ enum RawKind {
     #[token("||")]
     OrOr,
 
+    #[token("**")]
+    StarStar,
+    #[token("<<")]
+    Shl,
+    #[token(">>")]
+    Shr,
+    #[token("^^")]
+    CaretCaret,
+    #[token("+=")]
+    PlusEq,
+    #[token("-=")]
+    MinusEq,
+    #[token("*=")]
+    StarEq,
+    #[token("/=")]
+    SlashEq,
+    #[token("%=")]
+    PercentEq,
+    #[token("**=")]
+    StarStarEq,
+    #[token("&=")]
+    AmpEq,
+    #[token("|=")]
+    PipeEq,
+    #[token("^=")]
+    CaretEq,
+    #[token("<<=")]
+    ShlEq,
+    #[token(">>=")]
+    ShrEq,
+    #[token("++")]
+    PlusPlus,
+    #[token("--")]
+    MinusMinus,
+
+    // ---------- Overflow-mode operators ----------
+    #[token("+%")]
+    PlusWrap,
+    #[token("+|")]
+    PlusSat,
+    #[token("+?")]
+    PlusChecked,
+    #[token("+%?")]
+    PlusWrapChecked,
+    #[token("+|?")]
+    PlusSatChecked,
+    #[token("-%")]
+    MinusWrap,
+    #[token("-|")]
+    MinusSat,
+    #[token("-?")]
+    MinusChecked,
+    #[token("-%?")]
+    MinusWrapChecked,
+    #[token("-|?")]
+    MinusSatChecked,
+    #[token("*%")]
+    StarWrap,
+    #[token("*|")]
+    StarSat,
+    #[token("*?")]
+    StarChecked,
+    #[token("*%?")]
+    StarWrapChecked,
+    #[token("*|?")]
+    StarSatChecked,
+
     // ---------- Single-char operators ----------
     #[token("=")]
     Eq,
@@ -180,37 +481,308 @@ enum RawKind {
     #[token("!")]
     Bang,
 
+    #[token("%")]
+    Percent,
+    #[token("&")]
+    Amp,
+    #[token("|")]
+    Pipe,
+    #[token("^")]
+    Caret,
+    #[token("~")]
+    Tilde,
+
     // ---------- Literals ----------
-    // Integer (decimal only for MVP)
-    #[regex(r"[0-9]+", |lex| lex.slice().parse::<i64>().ok())]
-    Int(i64),
+    // Integer: decimal, 0x/0o/0b prefixes, `_` separators, optional iN/uN suffix.
+    // Prefixed forms accept any digit so `0b102` is one bad literal, not two tokens.
+    #[regex(r"[0-9][0-9_]*([iu][0-9]+)?", parse_int)]
+    #[regex(r"0[xX][0-9A-Fa-f_]*([iu][0-9]+)?", parse_int)]
+    #[regex(r"0[oObB][0-9_]*([iu][0-9]+)?", parse_int)]
+    Int(IntLiteral),
 
-    // String literal with basic escapes allowed (we keep the raw content for MVP).
-    // If you want decoded escapes, do it in the callback.
+    // Float: needs a fraction (`1.5`), an exponent (`1e-3`) or a float suffix (`2f32`).
+    // A digit is required after `.` so that `1.foo` still lexes as a field access.
+    #[regex(
+        r"[0-9][0-9_]*\.[0-9][0-9_]*([eE][+-]?[0-9_]+)?(f16|bf16|f32|f64|f128)?",
+        parse_float
+    )]
+    #[regex(r"[0-9][0-9_]*[eE][+-]?[0-9_]+(f16|bf16|f32|f64|f128)?", parse_float)]
+    #[regex(r"[0-9][0-9_]*(f16|bf16|f32|f64|f128)", parse_float)]
+    Float(FloatLiteral),
+
+    // String literals; escapes are decoded in the callbacks.
+    // The unterminated forms lack the closing quote and only win when the literal never ends.
     #[regex(r#""([^"\\]|\\.)*""#, parse_string)]
     Str(String),
+    #[regex(r#""([^"\\]|\\.)*"#, unterminated_string, allow_greedy = true)]
+    UnterminatedStr,
+    #[regex(r#"b"([^"\\]|\\.)*""#, parse_byte_string)]
+    ByteStr(Vec<u8>),
+    #[regex(r#"b"([^"\\]|\\.)*"#, unterminated_string, allow_greedy = true)]
+    UnterminatedByteStr,
+    #[regex(r"'([^'\\\n]|\\.)*'", parse_char)]
+    Char(char),
+    #[regex(r"'([^'\\\n]|\\.)*", unterminated_char, allow_greedy = true)]
+    UnterminatedChar,
 
     // ---------- Identifiers ----------
-    // Ident: letter/_ then letters/digits/_
+    // Ident: UAX #31 XID_Start/_ then XID_Continue, NFC-normalized in the callback.
     // Put AFTER keywords so keywords match first.
-    #[regex(r"[A-Za-z_][A-Za-z0-9_]*", |lex| lex.slice().to_string())]
+    #[regex(r"[\p{XID_Start}_]\p{XID_Continue}*", parse_ident)]
     Ident(String),
 
     // ---------- Comments ----------
     // Line comment: skip
     #[regex(r"//[^\n]*", logos::skip, allow_greedy = true)]
     LineComment,
+    // Block comment: skip, nesting is handled in the callback
+    #[token("/*", block_comment)]
+    BlockComment,
+    // Doc comments are kept; `////...` stays a plain line comment.
+    #[regex(r"///([^/\n][^\n]*)?", |lex| lex.slice()[3..].to_string(), allow_greedy = true)]
+    DocComment(String),
+    #[regex(r"//![^\n]*", |lex| lex.slice()[3..].to_string(), allow_greedy = true)]
+    InnerDocComment(String),
+}
+
+fn parse_int(lex: &mut logos::Lexer<RawKind>) -> Result<IntLiteral, LexErrorKind> {
+    let s = lex.slice();
+    let (radix, body) = match s.get(..2) {
+        Some("0x" | "0X") => (16, &s[2..]),
+        Some("0o" | "0O") => (8, &s[2..]),
+        Some("0b" | "0B") => (2, &s[2..]),
+        _ => (10, s),
+    };
+
+    // Hex digits never include `i`/`u`, so the first one starts the suffix.
+    let (digits, suffix) = match body.find(['i', 'u']) {
+        Some(idx) => {
+            let suffix = IntSuffix::parse(&body[idx..]).ok_or(LexErrorKind::InvalidIntSuffix)?;
+            (&body[..idx], Some(suffix))
+        }
+        None => (body, None),
+    };
+
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(LexErrorKind::InvalidIntLiteral);
+    }
+    let value = u128::from_str_radix(&digits, radix)
+        .map_err(|_| LexErrorKind::IntLiteralOutOfRange(suffix))?;
+    if suffix.is_some_and(|s| value > s.max_literal()) {
+        return Err(LexErrorKind::IntLiteralOutOfRange(suffix));
+    }
+
+    Ok(IntLiteral { value, suffix })
+}
+
+fn parse_float(lex: &mut logos::Lexer<RawKind>) -> Result<FloatLiteral, LexErrorKind> {
+    let s = lex.slice();
+    let (body, suffix) = match s.find(['f', 'b']) {
+        Some(idx) => (&s[..idx], FloatKind::from_name(&s[idx..])),
+        None => (s, None),
+    };
+
+    let text: String = body.chars().filter(|&c| c != '_').collect();
+    // An exponent made only of separators (`1e_`) leaves no digits behind it.
+    if text.ends_with(['e', 'E', '+', '-']) || text.parse::<f64>().is_err() {
+        return Err(LexErrorKind::InvalidFloatLiteral);
+    }
+
+    Ok(FloatLiteral { text, suffix })
+}
+
+/// Normalizes identifiers to NFC so that differently encoded spellings of the
+/// same name (precomposed `é` or `e` + combining accent) compare equal.
+fn parse_ident(lex: &mut logos::Lexer<RawKind>) -> String {
+    let raw = lex.slice();
+    if raw.is_ascii() {
+        return raw.to_string();
+    }
+
+    let name: String = raw.nfc().collect();
+    if !name.as_str().is_single_script() {
+        lex.extras.warnings.push(LexWarning::MixedScriptIdentifier {
+            name: name.clone(),
+            span: lex.span().into(),
+        });
+    }
+    name
+}
+
+/// Length of the rest of a nested `/* ... */` comment, given the input right
+/// after its opening `/*`. `None` when the comment is never closed.
+fn block_comment_len(rest: &[u8]) -> Option<usize> {
+    let mut depth = 1;
+    let mut i = 0;
+    while i + 1 < rest.len() {
+        match &rest[i..i + 2] {
+            b"/*" => {
+                depth += 1;
+                i += 2;
+            }
+            b"*/" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Skips a possibly nested `/* ... */` comment whose opening was just matched.
+fn block_comment(lex: &mut logos::Lexer<RawKind>) -> Result<logos::Skip, LexErrorKind> {
+    if let Some(len) = block_comment_len(lex.remainder().as_bytes()) {
+        lex.bump(len);
+        return Ok(logos::Skip);
+    }
+
+    // Point at the opening `/*` rather than the rest of the file.
+    let start = lex.span().start;
+    lex.bump(lex.remainder().len());
+    lex.extras.error_span = Some(Span {
+        start,
+        end: start + 2,
+    });
+    Err(LexErrorKind::UnterminatedBlockComment)
+}
+
+fn unterminated_string(_: &mut logos::Lexer<RawKind>) -> Result<(), LexErrorKind> {
+    Err(LexErrorKind::UnterminatedString)
+}
+
+fn unterminated_char(_: &mut logos::Lexer<RawKind>) -> Result<(), LexErrorKind> {
+    Err(LexErrorKind::UnterminatedChar)
 }
 
-fn parse_string(lex: &mut logos::Lexer<RawKind>) -> Option<String> {
+/// Returns the literal body between the quotes and its offset in the source.
+fn literal_body<'s>(lex: &logos::Lexer<'s, RawKind>, prefix_len: usize) -> (&'s str, usize) {
     let s = lex.slice();
-    // s includes quotes. MVP: strip quotes, keep escapes as-is.
-    // Example: "\"a\\n\"" -> "a\\n"
-    if s.len() >= 2 {
-        Some(s[1..s.len() - 1].to_string())
-    } else {
-        None
+    (
+        &s[prefix_len + 1..s.len() - 1],
+        lex.span().start + prefix_len + 1,
+    )
+}
+
+fn parse_string(lex: &mut logos::Lexer<RawKind>) -> Result<String, LexErrorKind> {
+    let (body, offset) = literal_body(lex, 0);
+    let mut out = String::with_capacity(body.len());
+    unescape(body, offset, false, |c| {
+        out.push(char::from_u32(c).expect("unescape yields valid chars"))
+    })
+    .map_err(|err| report_at(lex, err))?;
+    Ok(out)
+}
+
+fn parse_byte_string(lex: &mut logos::Lexer<RawKind>) -> Result<Vec<u8>, LexErrorKind> {
+    let (body, offset) = literal_body(lex, 1);
+    let mut out = Vec::with_capacity(body.len());
+    unescape(body, offset, true, |b| out.push(b as u8)).map_err(|err| report_at(lex, err))?;
+    Ok(out)
+}
+
+fn parse_char(lex: &mut logos::Lexer<RawKind>) -> Result<char, LexErrorKind> {
+    let (body, offset) = literal_body(lex, 0);
+    let mut chars = Vec::new();
+    unescape(body, offset, false, |c| {
+        chars.push(char::from_u32(c).expect("unescape yields valid chars"))
+    })
+    .map_err(|err| report_at(lex, err))?;
+    match chars.as_slice() {
+        [c] => Ok(*c),
+        _ => Err(LexErrorKind::InvalidCharLiteral),
+    }
+}
+
+fn report_at(lex: &mut logos::Lexer<RawKind>, (kind, span): (LexErrorKind, Span)) -> LexErrorKind {
+    lex.extras.error_span = Some(span);
+    kind
+}
+
+/// Decodes the escapes of a literal body, pushing each char (or byte, in
+/// `byte_mode`) to `push`. `offset` is where `body` starts in the source, so
+/// errors carry the exact span of the offending escape or character.
+fn unescape(
+    body: &str,
+    offset: usize,
+    byte_mode: bool,
+    mut push: impl FnMut(u32),
+) -> Result<(), (LexErrorKind, Span)> {
+    let mut chars = body.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let span_to = |end: usize| Span {
+            start: offset + start,
+            end: offset + end,
+        };
+        if c != '\\' {
+            if byte_mode && !c.is_ascii() {
+                let span = span_to(start + c.len_utf8());
+                return Err((LexErrorKind::NonAsciiByteString, span));
+            }
+            push(c as u32);
+            continue;
+        }
+
+        let invalid = |end: usize| (LexErrorKind::InvalidEscape, span_to(end));
+        let Some((_, esc)) = chars.next() else {
+            return Err(invalid(start + 1));
+        };
+        let mut end = start + 1 + esc.len_utf8();
+        let value = match esc {
+            'n' => '\n' as u32,
+            't' => '\t' as u32,
+            'r' => '\r' as u32,
+            '0' => 0,
+            '\\' | '"' | '\'' => esc as u32,
+            'x' => {
+                let mut value = 0;
+                for _ in 0..2 {
+                    match chars.next_if(|(_, d)| d.is_ascii_hexdigit()) {
+                        Some((_, d)) => {
+                            value = value * 16 + d.to_digit(16).unwrap();
+                            end += 1;
+                        }
+                        None => return Err(invalid(end)),
+                    }
+                }
+                // Outside byte strings `\x` is limited to ASCII so the result stays valid UTF-8.
+                if !byte_mode && value > 0x7F {
+                    return Err(invalid(end));
+                }
+                value
+            }
+            'u' if !byte_mode => {
+                if chars.next_if(|(_, d)| *d == '{').is_none() {
+                    return Err(invalid(end));
+                }
+                end += 1;
+                let mut value: u32 = 0;
+                let mut digits = 0;
+                while let Some((_, d)) = chars.next_if(|(_, d)| d.is_ascii_hexdigit()) {
+                    value = value
+                        .saturating_mul(16)
+                        .saturating_add(d.to_digit(16).unwrap());
+                    digits += 1;
+                    end += 1;
+                }
+                if chars.next_if(|(_, d)| *d == '}').is_none() {
+                    return Err(invalid(end));
+                }
+                end += 1;
+                if !(1..=6).contains(&digits) || char::from_u32(value).is_none() {
+                    return Err(invalid(end));
+                }
+                value
+            }
+            _ => return Err(invalid(end)),
+        };
+        push(value);
     }
+    Ok(())
 }
 
 fn into_token_parts(raw: RawKind) -> (TokenKind, Option<TokenValue>) {
@@ -221,6 +793,17 @@ fn into_token_parts(raw: RawKind) -> (TokenKind, Option<TokenValue>) {
         RawKind::Else => (TokenKind::Else, None),
         RawKind::While => (TokenKind::While, None),
         RawKind::For => (TokenKind::For, None),
+        RawKind::Do => (TokenKind::Do, None),
+        RawKind::Loop => (TokenKind::Loop, None),
+        RawKind::Break => (TokenKind::Break, None),
+        RawKind::Continue => (TokenKind::Continue, None),
+        RawKind::Struct => (TokenKind::Struct, None),
+        RawKind::Enum => (TokenKind::Enum, None),
+        RawKind::Match => (TokenKind::Match, None),
+        RawKind::Import => (TokenKind::Import, None),
+        RawKind::As => (TokenKind::As, None),
+        RawKind::Pub => (TokenKind::Pub, None),
+        RawKind::Entry => (TokenKind::Entry, None),
         RawKind::LParen => (TokenKind::LParen, None),
         RawKind::RParen => (TokenKind::RParen, None),
         RawKind::LBrace => (TokenKind::LBrace, None),
@@ -231,13 +814,47 @@ fn into_token_parts(raw: RawKind) -> (TokenKind, Option<TokenValue>) {
         RawKind::Semicolon => (TokenKind::Semicolon, None),
         RawKind::Colon => (TokenKind::Colon, None),
         RawKind::Period => (TokenKind::Period, None),
+        RawKind::Hash => (TokenKind::Hash, None),
         RawKind::Arrow => (TokenKind::Arrow, None),
+        RawKind::FatArrow => (TokenKind::FatArrow, None),
         RawKind::EqEq => (TokenKind::EqEq, None),
         RawKind::NotEq => (TokenKind::NotEq, None),
         RawKind::LtEq => (TokenKind::LtEq, None),
         RawKind::GtEq => (TokenKind::GtEq, None),
         RawKind::AndAnd => (TokenKind::AndAnd, None),
         RawKind::OrOr => (TokenKind::OrOr, None),
+        RawKind::StarStar => (TokenKind::StarStar, None),
+        RawKind::Shl => (TokenKind::Shl, None),
+        RawKind::Shr => (TokenKind::Shr, None),
+        RawKind::CaretCaret => (TokenKind::CaretCaret, None),
+        RawKind::PlusEq => (TokenKind::PlusEq, None),
+        RawKind::MinusEq => (TokenKind::MinusEq, None),
+        RawKind::StarEq => (TokenKind::StarEq, None),
+        RawKind::SlashEq => (TokenKind::SlashEq, None),
+        RawKind::PercentEq => (TokenKind::PercentEq, None),
+        RawKind::StarStarEq => (TokenKind::StarStarEq, None),
+        RawKind::AmpEq => (TokenKind::AmpEq, None),
+        RawKind::PipeEq => (TokenKind::PipeEq, None),
+        RawKind::CaretEq => (TokenKind::CaretEq, None),
+        RawKind::ShlEq => (TokenKind::ShlEq, None),
+        RawKind::ShrEq => (TokenKind::ShrEq, None),
+        RawKind::PlusPlus => (TokenKind::PlusPlus, None),
+        RawKind::MinusMinus => (TokenKind::MinusMinus, None),
+        RawKind::PlusWrap => (TokenKind::PlusWrap, None),
+        RawKind::PlusSat => (TokenKind::PlusSat, None),
+        RawKind::PlusChecked => (TokenKind::PlusChecked, None),
+        RawKind::PlusWrapChecked => (TokenKind::PlusWrapChecked, None),
+        RawKind::PlusSatChecked => (TokenKind::PlusSatChecked, None),
+        RawKind::MinusWrap => (TokenKind::MinusWrap, None),
+        RawKind::MinusSat => (TokenKind::MinusSat, None),
+        RawKind::MinusChecked => (TokenKind::MinusChecked, None),
+        RawKind::MinusWrapChecked => (TokenKind::MinusWrapChecked, None),
+        RawKind::MinusSatChecked => (TokenKind::MinusSatChecked, None),
+        RawKind::StarWrap => (TokenKind::StarWrap, None),
+        RawKind::StarSat => (TokenKind::StarSat, None),
+        RawKind::StarChecked => (TokenKind::StarChecked, None),
+        RawKind::StarWrapChecked => (TokenKind::StarWrapChecked, None),
+        RawKind::StarSatChecked => (TokenKind::StarSatChecked, None),
         RawKind::Eq => (TokenKind::Eq, None),
         RawKind::Lt => (TokenKind::Lt, None),
         RawKind::Gt => (TokenKind::Gt, None),
@@ -246,14 +863,55 @@ fn into_token_parts(raw: RawKind) -> (TokenKind, Option<TokenValue>) {
         RawKind::Star => (TokenKind::Star, None),
         RawKind::Slash => (TokenKind::Slash, None),
         RawKind::Bang => (TokenKind::Bang, None),
+        RawKind::Percent => (TokenKind::Percent, None),
+        RawKind::Amp => (TokenKind::Amp, None),
+        RawKind::Pipe => (TokenKind::Pipe, None),
+        RawKind::Caret => (TokenKind::Caret, None),
+        RawKind::Tilde => (TokenKind::Tilde, None),
         RawKind::Ident(s) => (TokenKind::Ident, Some(TokenValue::Ident(s))),
-        RawKind::Int(v) => (TokenKind::Int, Some(TokenValue::Int(v))),
+        RawKind::Int(lit) => (TokenKind::Int, Some(TokenValue::Int(lit))),
+        RawKind::Float(lit) => (TokenKind::Float, Some(TokenValue::Float(lit))),
         RawKind::Str(s) => (TokenKind::Str, Some(TokenValue::Str(s))),
-        RawKind::LineComment => unreachable!("line comments are skipped by Logos"),
+        RawKind::ByteStr(b) => (TokenKind::ByteStr, Some(TokenValue::ByteStr(b))),
+        RawKind::Char(c) => (TokenKind::Char, Some(TokenValue::Char(c))),
+        RawKind::UnterminatedStr | RawKind::UnterminatedByteStr | RawKind::UnterminatedChar => {
+            unreachable!("unterminated literals always produce an error")
+        }
+        RawKind::DocComment(s) => (TokenKind::DocComment, Some(TokenValue::Doc(s))),
+        RawKind::InnerDocComment(s) => (TokenKind::InnerDocComment, Some(TokenValue::Doc(s))),
+        RawKind::LineComment | RawKind::BlockComment => {
+            unreachable!("comments are skipped by Logos")
+        }
+    }
+}
+
+/// Token stream of a whole input together with every lex error found in it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Lexed {
+    /// All tokens, with a `TokenKind::Error` token where lexing failed.
+    pub tokens: Vec<Token>,
+    pub errors: Vec<LexError>,
+    pub warnings: Vec<LexWarning>,
+}
+
+impl Lexed {
+    /// Moves every span by `base`, for input lexed at that source-map position.
+    pub fn shifted(mut self, base: usize) -> Lexed {
+        for token in &mut self.tokens {
+            token.span = token.span.shifted(base);
+        }
+        for error in &mut self.errors {
+            error.span = error.span.shifted(base);
+        }
+        for warning in &mut self.warnings {
+            warning.shift(base);
+        }
+        self
     }
 }
 
-pub fn tokenize(input: &str) -> Result<Vec<Token>, Span> {
+/// Lexes `input`, stopping at the first error.
+pub fn tokenize(input: &str) -> LexResult<Vec<Token>> {
     let mut tokens = Vec::new();
     let mut lexer = RawKind::lexer(input);
 
@@ -267,9 +925,140 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, Span> {
                     span: lexer.span().into(),
                 });
             }
-            Err(_) => return Err(lexer.span().into()),
+            Err(kind) => {
+                let span = lexer.extras.error_span.take();
+                return Err(LexError {
+                    kind,
+                    span: span.unwrap_or_else(|| lexer.span().into()),
+                });
+            }
         }
     }
 
     Ok(tokens)
 }
+
+/// Lexes all of `input`, replacing each bad span with an error token and
+/// carrying on, so that every lex error of a file is reported at once.
+pub fn tokenize_recovering(input: &str) -> Lexed {
+    let mut lexed = Lexed::default();
+    let mut lexer = RawKind::lexer(input);
+
+    while let Some(raw_res) = lexer.next() {
+        let span: Span = lexer.span().into();
+        match raw_res {
+            Ok(raw) => {
+                let (kind, value) = into_token_parts(raw);
+                lexed.tokens.push(Token { kind, value, span });
+            }
+            Err(kind) => {
+                let error_span = lexer.extras.error_span.take();
+                lexed.errors.push(LexError {
+                    kind,
+                    span: error_span.unwrap_or(span),
+                });
+                lexed.tokens.push(Token {
+                    kind: TokenKind::Error,
+                    value: None,
+                    span,
+                });
+            }
+        }
+    }
+
+    lexed.warnings = std::mem::take(&mut lexer.extras.warnings);
+    lexed
+}
+
+/// Source text the fast lexer skips over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+}
+
+/// Item of a lossless token stream: either a token or the trivia between tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LosslessToken {
+    Token(Token),
+    Trivia { kind: TriviaKind, span: Span },
+}
+
+impl LosslessToken {
+    pub fn span(&self) -> Span {
+        match self {
+            LosslessToken::Token(t) => t.span,
+            LosslessToken::Trivia { span, .. } => *span,
+        }
+    }
+}
+
+/// Lossless token stream of a whole input together with every lex error found in it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LosslessLexed {
+    /// Tokens and trivia, with a `TokenKind::Error` token where lexing failed.
+    pub items: Vec<LosslessToken>,
+    pub errors: Vec<LexError>,
+    pub warnings: Vec<LexWarning>,
+}
+
+/// Like [`tokenize_recovering`], but also returns whitespace and comments as
+/// trivia so that the spans of the result cover every byte of `input`, in
+/// order. Concatenating `&input[span]` for each item yields `input` exactly,
+/// even when the input has lex errors.
+pub fn tokenize_lossless(input: &str) -> LosslessLexed {
+    let Lexed {
+        tokens,
+        errors,
+        warnings,
+    } = tokenize_recovering(input);
+    let mut items = Vec::with_capacity(tokens.len() * 2 + 1);
+    let mut pos = 0;
+
+    for token in tokens {
+        push_trivia(input, pos, token.span.start, &mut items);
+        pos = token.span.end;
+        items.push(LosslessToken::Token(token));
+    }
+    push_trivia(input, pos, input.len(), &mut items);
+
+    LosslessLexed {
+        items,
+        errors,
+        warnings,
+    }
+}
+
+/// Splits a gap between two tokens into trivia. The gap only ever holds
+/// what the fast lexer skips, so it is made of whitespace and comments; an
+/// unterminated block comment comes back as an error token, not a gap.
+fn push_trivia(input: &str, mut pos: usize, end: usize, items: &mut Vec<LosslessToken>) {
+    while pos < end {
+        let rest = &input[pos..end];
+        let (kind, len) = if rest.starts_with("//") {
+            (
+                TriviaKind::LineComment,
+                rest.find('\n').unwrap_or(rest.len()),
+            )
+        } else if rest.starts_with("/*") {
+            let len = block_comment_len(&rest.as_bytes()[2..]).map_or(rest.len(), |len| len + 2);
+            (TriviaKind::BlockComment, len)
+        } else {
+            let len = rest
+                .find(|c: char| !matches!(c, ' ' | '\t' | '\r' | '\n' | '\x0C'))
+                .unwrap_or(rest.len());
+            assert!(len > 0, "unexpected text between tokens: {rest:?}");
+            (TriviaKind::Whitespace, len)
+        };
+
+        items.push(LosslessToken::Trivia {
+            kind,
+            span: Span {
+                start: pos,
+                end: pos + len,
+            },
+        });
+        pos += len;
+    }
+}
@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use crate::tokens::IntSuffix;
+
+/// IEEE-754 (and bfloat16) floating-point formats from docs/language/types.md.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatKind {
+    F16,
+    BF16,
+    F32,
+    F64,
+    F128,
+}
+
+impl FloatKind {
+    pub const ALL: [FloatKind; 5] = [
+        FloatKind::F16,
+        FloatKind::BF16,
+        FloatKind::F32,
+        FloatKind::F64,
+        FloatKind::F128,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FloatKind::F16 => "f16",
+            FloatKind::BF16 => "bf16",
+            FloatKind::F32 => "f32",
+            FloatKind::F64 => "f64",
+            FloatKind::F128 => "f128",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.name() == name)
+    }
+}
+
+/// Value types known to the compiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Int {
+        signed: bool,
+        bits: u32,
+    },
+    Float(FloatKind),
+    Bool,
+    /// Unicode scalar value.
+    Char,
+    /// Pointer to NUL-terminated constant bytes, the type of string literals
+    /// until slices exist.
+    Str,
+    /// A struct declared in the program, by name.
+    Struct(Rc<str>),
+    /// An enum declared in the program, by name.
+    Enum(Rc<str>),
+}
+
+impl Type {
+    /// Resolves a built-in type name as written in source, e.g. `u32`, `bf16`
+    /// or `bool`. Struct and enum names are resolved by codegen.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bool" => return Some(Type::Bool),
+            "char" => return Some(Type::Char),
+            "str" => return Some(Type::Str),
+            _ => {}
+        }
+        if let Some(kind) = FloatKind::from_name(name) {
+            return Some(Type::Float(kind));
+        }
+        let signed = match name.as_bytes().first()? {
+            b'i' => true,
+            b'u' => false,
+            _ => return None,
+        };
+        let digits = &name[1..];
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let bits = digits.parse::<u32>().ok()?;
+        (1..=IntSuffix::MAX_BITS)
+            .contains(&bits)
+            .then_some(Type::Int { signed, bits })
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Float(_))
+    }
+}
+
+impl From<IntSuffix> for Type {
+    fn from(suffix: IntSuffix) -> Self {
+        Type::Int {
+            signed: suffix.signed,
+            bits: suffix.bits,
+        }
+    }
+}
+
+impl From<FloatKind> for Type {
+    fn from(kind: FloatKind) -> Self {
+        Type::Float(kind)
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int { signed: true, bits } => write!(f, "i{bits}"),
+            Type::Int {
+                signed: false,
+                bits,
+            } => write!(f, "u{bits}"),
+            Type::Float(kind) => f.write_str(kind.name()),
+            Type::Bool => f.write_str("bool"),
+            Type::Char => f.write_str("char"),
+            Type::Str => f.write_str("str"),
+            Type::Struct(name) | Type::Enum(name) => f.write_str(name),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use xenonc::attributes::{AttrForm, KNOWN_ATTRIBUTES, check_attributes, lookup};
+
+use common::parse;
+
+fn attribute_errors(src: &str) -> Vec<String> {
+    check_attributes(&parse(src))
+        .into_iter()
+        .map(|e| e.message)
+        .collect()
+}
+
+#[test]
+fn known_attributes_are_registered() {
+    let names: Vec<&str> = KNOWN_ATTRIBUTES.iter().map(|a| a.name).collect();
+    assert_eq!(names, ["entry", "packed"]);
+    assert_eq!(lookup("packed").map(|a| a.form), Some(AttrForm::Word));
+    assert!(lookup("inline").is_none());
+}
+
+#[test]
+fn check_attributes_accepts_known_attributes() {
+    let src = "#[packed] struct S { u8 a; }\n#[entry] fn main() { }";
+    assert!(attribute_errors(src).is_empty());
+}
+
+#[test]
+fn check_attributes_suggests_similar_names() {
+    assert_eq!(
+        attribute_errors("#[pakced] struct S { u8 a; }"),
+        ["unknown attribute 'pakced'; did you mean 'packed'?"]
+    );
+    assert_eq!(
+        attribute_errors("#[entyr] fn main() -> u32 { return 0; }"),
+        ["unknown attribute 'entyr'; did you mean 'entry'?"]
+    );
+    assert_eq!(
+        attribute_errors("#[inline] fn f() -> u32 { return 0; }"),
+        ["unknown attribute 'inline'"]
+    );
+    // The error points at the name.
+    let program = parse("#[pakced] struct S { u8 a; }");
+    let span = check_attributes(&program)[0].span;
+    assert_eq!((span.start, span.end), (2, 8));
+}
+
+#[test]
+fn check_attributes_checks_form_and_target() {
+    for (src, expected) in [
+        (
+            "#[packed(1)] struct S { u8 a; }",
+            "attribute 'packed' must be written as `#[packed]`",
+        ),
+        (
+            "#[entry = 1] fn main() { }",
+            "attribute 'entry' must be written as `#[entry]`",
+        ),
+        (
+            "#[entry] struct S { u8 a; }",
+            "attribute 'entry' cannot be used on a struct; it applies to functions",
+        ),
+        (
+            "struct S { #[packed] u8 a; }",
+            "attribute 'packed' cannot be used on a struct field; it applies to structs",
+        ),
+        (
+            "enum E { #[entry] A }",
+            "attribute 'entry' cannot be used on an enum variant; it applies to functions",
+        ),
+        (
+            "#[packed] #[packed] struct S { u8 a; }",
+            "attribute 'packed' is given more than once",
+        ),
+    ] {
+        assert_eq!(attribute_errors(src), [expected], "{src}");
+    }
+}
+
+#[test]
+fn check_attributes_reaches_nested_statements() {
+    let src = "fn f(u32 x) -> u32 {\n\
+                   u32 y = loop { #[packed] break 1; };\n\
+                   match (x) { 0 => { #[colde] return 0; } _ => {} }\n\
+                   return y;\n\
+               }";
+    assert_eq!(
+        attribute_errors(src),
+        [
+            "attribute 'packed' cannot be used on a statement; it applies to structs",
+            "unknown attribute 'colde'",
+        ]
+    );
+}
@@ -0,0 +1,951 @@
+mod common;
+
+use inkwell::context::Context;
+use inkwell::targets::{TargetData, TargetMachine};
+use xenonc::attributes::check_attributes;
+use xenonc::codegen::{CodeGen, host_target_machine};
+use xenonc::config::CrateType;
+use xenonc::entry::check_entry;
+use xenonc::resolve::resolve_calls;
+use xenonc::sema::check_program;
+use xenonc::tokens::Span;
+
+use common::parse;
+
+fn host() -> TargetMachine {
+    host_target_machine().expect("the host is a supported target")
+}
+
+/// Checks `src` like the pipeline does, without requiring an entry
+/// function, then lowers it to verified IR.
+fn compile_to_ir(src: &str) -> Result<String, String> {
+    let mut program = parse(src);
+    if let Some(e) = check_attributes(&program).into_iter().next() {
+        return Err(e.message);
+    }
+    let entry = check_entry(&program, CrateType::Lib).map_err(|e| e.message)?;
+    resolve_calls(&mut program).map_err(|e| e.message)?;
+    check_program(&mut program).map_err(|e| e.message)?;
+
+    let context = Context::create();
+    let module = CodeGen::new(&context, "test", &host())
+        .compile_program(&program, entry)
+        .map_err(|e| e.message)?;
+    module.verify().map_err(|e| e.to_string())?;
+    Ok(module.print_to_string().to_string())
+}
+
+#[test]
+fn codegen_maps_every_float_type() {
+    for (ty, llvm) in [
+        ("f16", "half"),
+        ("bf16", "bfloat"),
+        ("f32", "float"),
+        ("f64", "double"),
+        ("f128", "fp128"),
+    ] {
+        let ir = compile_to_ir(&format!("fn f()->{ty}{{return 1.5 * 2.0 - 0.5;}}"))
+            .unwrap_or_else(|e| panic!("{ty}: {e}"));
+        assert!(ir.contains(&format!("define {llvm} @f()")), "{ty}: {ir}");
+    }
+}
+
+#[test]
+fn codegen_float_comparison_returns_bool() {
+    let ir = compile_to_ir("fn f()->bool{return 1.5f32 < 2.0f32;}").expect("codegen");
+    assert!(ir.contains("define i1 @f()"), "{ir}");
+}
+
+#[test]
+fn codegen_rejects_mismatched_literal_types() {
+    let err = compile_to_ir("fn f()->f32{return 1.5f64;}").expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected f32, found f64");
+
+    let err = compile_to_ir("fn f()->u32{return 1.5;}").expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected u32, found float literal");
+}
+
+#[test]
+fn codegen_lowers_string_literals_to_private_constants() {
+    let ir = compile_to_ir(r#"fn f()->str{return "hi\n";}"#).expect("codegen");
+    assert!(
+        ir.contains(r#"private unnamed_addr constant [4 x i8] c"hi\0A\00""#),
+        "{ir}"
+    );
+
+    let ir = compile_to_ir(r#"fn f()->str{return b"\xFF";}"#).expect("codegen");
+    assert!(
+        ir.contains(r#"private unnamed_addr constant [2 x i8] c"\FF\00""#),
+        "{ir}"
+    );
+}
+
+#[test]
+fn codegen_char_literal_is_unicode_scalar() {
+    let ir = compile_to_ir(r"fn f()->char{return '\u{e9}';}").expect("codegen");
+    assert!(ir.contains("ret i32 233"), "{ir}");
+}
+
+#[test]
+fn codegen_errors_carry_the_offending_span() {
+    let program = parse("fn f()->u32{return 1.5;}");
+
+    let context = Context::create();
+    let err = CodeGen::new(&context, "test", &host())
+        .compile_program(&program, None)
+        .expect_err("should fail");
+    assert_eq!(err.span, Some(Span { start: 19, end: 22 }));
+
+    let program = parse("fn f()->num{return 1;}");
+    let err = CodeGen::new(&context, "test", &host())
+        .compile_program(&program, None)
+        .expect_err("should fail");
+    assert_eq!(err.message, "Unknown type 'num'");
+    assert_eq!(err.span, Some(Span { start: 8, end: 11 }));
+}
+
+#[test]
+fn codegen_binds_params_as_locals() {
+    let ir = compile_to_ir("fn add(u64 x, u64 y) -> u64 { return x + y * 2; }").expect("codegen");
+    assert!(ir.contains("define i64 @add(i64 %x, i64 %y)"), "{ir}");
+    assert!(ir.contains("%x1 = alloca i64"), "{ir}");
+    assert!(ir.contains("mul i64"), "{ir}");
+
+    // The parameter's type flows into unsuffixed literals on either side.
+    let ir = compile_to_ir("fn lt(i8 x) -> bool { return 1 < x; }").expect("codegen");
+    assert!(ir.contains("icmp slt i8 1"), "{ir}");
+}
+
+#[test]
+fn codegen_checks_param_types() {
+    let err = compile_to_ir("fn f(u32 x) -> u64 { return x; }").expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected u64, found u32");
+
+    let err = compile_to_ir("fn f(u32 x) -> u32 { return y; }").expect_err("should fail");
+    assert_eq!(err, "cannot find value 'y' in this scope");
+
+    let err = compile_to_ir("fn f(u32 x, u8 x) -> u32 { return x; }").expect_err("should fail");
+    assert_eq!(
+        err,
+        "identifier 'x' is bound more than once in this parameter list"
+    );
+}
+
+#[test]
+fn codegen_calls_functions_declared_later() {
+    let ir = compile_to_ir(
+        "fn main() -> i32 { return twice(20) + 2; }\nfn twice(i32 x) -> i32 { return x * 2; }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("call i32 @twice(i32 20)"), "{ir}");
+
+    let err = compile_to_ir("fn f(i32 x) -> i32 { return f(); }").expect_err("should fail");
+    assert_eq!(err, "missing argument for parameter 'x' of function 'f'");
+    let err = compile_to_ir("fn f(i32 x) -> i32 { return f(1, 2); }").expect_err("should fail");
+    assert_eq!(err, "function 'f' takes 1 argument but 2 were supplied");
+
+    let err = compile_to_ir("fn f() -> i32 { return g(1); }").expect_err("should fail");
+    assert_eq!(err, "cannot find function 'g' in this scope");
+}
+
+#[test]
+fn codegen_logical_operators_short_circuit() {
+    let ir = compile_to_ir("fn f(bool a, bool b) -> bool { return a && !b || a ^^ b; }")
+        .expect("codegen");
+    assert!(ir.contains("and.rhs:"), "{ir}");
+    assert!(ir.contains("or.rhs:"), "{ir}");
+    assert!(ir.contains("phi i1 [ false, %entry ]"), "{ir}");
+    assert!(ir.contains("xor i1"), "{ir}");
+
+    let err =
+        compile_to_ir("fn f(u32 a, bool b) -> bool { return a && b; }").expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected bool, found u32");
+}
+
+#[test]
+fn codegen_unary_and_bitwise_operators() {
+    let ir = compile_to_ir("fn f(i32 x) -> i32 { return -x + ~x & x >> 1 | x % 3 ^ x << 2; }")
+        .expect("codegen");
+    for needle in [
+        "sub i32 0, %x",
+        "xor i32 %x",
+        "ashr i32",
+        "srem i32",
+        "shl i32",
+    ] {
+        assert!(ir.contains(needle), "{needle}: {ir}");
+    }
+
+    let ir = compile_to_ir("fn f(u8 x) -> u8 { return x >> 1; }").expect("codegen");
+    assert!(ir.contains("lshr i8"), "{ir}");
+
+    let err = compile_to_ir("fn f(u32 x) -> u32 { return -x; }").expect_err("should fail");
+    assert_eq!(err, "cannot apply `-` to u32");
+    let err = compile_to_ir("fn f(f64 x) -> f64 { return x & x; }").expect_err("should fail");
+    assert_eq!(err, "cannot apply `&` to f64");
+}
+
+#[test]
+fn codegen_exponentiation() {
+    let ir = compile_to_ir("fn f(u64 b, u64 e) -> u64 { return b ** e; }").expect("codegen");
+    assert!(ir.contains("pow.head:"), "{ir}");
+
+    let ir = compile_to_ir("fn f(f32 b) -> f32 { return b ** 0.5; }").expect("codegen");
+    assert!(ir.contains("call float @llvm.pow.f32"), "{ir}");
+}
+
+#[test]
+fn codegen_overflow_suffixes() {
+    let ir =
+        compile_to_ir("fn f(u8 a, u8 b) -> u8 { return a +% b +| a -| b *| a; }").expect("codegen");
+    assert!(ir.contains("add i8 %a"), "{ir}");
+    assert!(ir.contains("@llvm.uadd.sat.i8"), "{ir}");
+    assert!(ir.contains("@llvm.usub.sat.i8"), "{ir}");
+    assert!(ir.contains("@llvm.umul.with.overflow.i8"), "{ir}");
+
+    let ir = compile_to_ir("fn f(i16 a, i16 b) -> i16 { return a *| b; }").expect("codegen");
+    assert!(ir.contains("@llvm.smul.with.overflow.i16"), "{ir}");
+    assert!(ir.contains("i16 -32768, i16 32767"), "{ir}");
+
+    let err = compile_to_ir("fn f(u8 a) -> u8 { return a +? a; }").expect_err("should fail");
+    assert_eq!(
+        err,
+        "checked operator `+?` yields a value and an overflow flag; bind both with a declaration such as `u8 result, bool overflow = a +? b;`"
+    );
+    let err = compile_to_ir("fn f(f32 a) -> f32 { return a +% a; }").expect_err("should fail");
+    assert_eq!(err, "cannot apply `+%` to f32");
+}
+
+#[test]
+fn codegen_lowers_locals_to_entry_allocas() {
+    let ir = compile_to_ir(
+        "fn f(u32 n) -> u32 { u32 total; total += n; u32 twice = total * 2; twice++; --total; return twice - total; }",
+    )
+    .expect("codegen");
+    let entry = ir.split("entry:").nth(1).expect("entry block");
+    for slot in [
+        "%n1 = alloca i32",
+        "%total = alloca i32",
+        "%twice = alloca i32",
+    ] {
+        assert!(entry.contains(slot), "{slot}: {ir}");
+    }
+    // An uninitialized declaration stores zero.
+    assert!(ir.contains("store i32 0, "), "{ir}");
+}
+
+#[test]
+fn codegen_scopes_and_shadowing() {
+    compile_to_ir("fn f() -> u8 { u8 x = 1; { u16 x = 2; x += 1; } return x; }").expect("codegen");
+
+    let err = compile_to_ir("fn f() -> u8 { { u8 y = 1; } return y; }").expect_err("should fail");
+    assert_eq!(err, "cannot find value 'y' in this scope");
+
+    let err = compile_to_ir("fn f(u8 x) -> u8 { u8 x = 1; return x; }").expect_err("should fail");
+    assert_eq!(err, "'x' is already declared in this scope");
+}
+
+#[test]
+fn codegen_rejects_invalid_assignments() {
+    let err = compile_to_ir("fn f() -> u8 { const u8 x = 1; x = 2; return x; }")
+        .expect_err("should fail");
+    assert_eq!(err, "cannot assign to const variable 'x'");
+
+    let err =
+        compile_to_ir("fn f() -> u8 { const u8 x = 1; x++; return x; }").expect_err("should fail");
+    assert_eq!(err, "cannot assign to const variable 'x'");
+
+    let err = compile_to_ir("fn f(u8 x) -> u8 { x + 1 = 2; return x; }").expect_err("should fail");
+    assert_eq!(err, "invalid left-hand side of assignment");
+
+    let err = compile_to_ir("fn f(bool b) -> bool { b++; return b; }").expect_err("should fail");
+    assert_eq!(err, "cannot apply `++` to bool");
+
+    let err = compile_to_ir("fn f(u8 x) -> u8 { x = 1.5; return x; }").expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected u8, found float literal");
+}
+
+#[test]
+fn codegen_requires_a_return_on_every_path() {
+    let err = compile_to_ir("fn f() -> u8 { u8 x = 1; }").expect_err("should fail");
+    assert_eq!(
+        err,
+        "function 'f' may reach its end without returning a value of type u8"
+    );
+
+    // Statements after a `return` are compiled into a dead block.
+    compile_to_ir("fn f() -> u8 { return 1; u8 x = 2; }").expect("codegen");
+}
+
+#[test]
+fn codegen_if_statements() {
+    let ir = compile_to_ir(
+        "fn sign(i32 x) -> i32 { i32 s; if (x < 0) { s = -1; } else if (x == 0) { s = 0; } else { s = 1; } return s; }",
+    )
+    .expect("codegen");
+    assert_eq!(ir.matches("br i1").count(), 2, "{ir}");
+
+    // Returning from every arm leaves the merge block unreachable.
+    compile_to_ir("fn f(bool c) -> u8 { if (c) { return 1; } else { return 2; } }")
+        .expect("codegen");
+
+    let err =
+        compile_to_ir("fn f(bool c) -> u8 { if (c) { return 1; } }").expect_err("should fail");
+    assert_eq!(
+        err,
+        "function 'f' may reach its end without returning a value of type u8"
+    );
+
+    let err = compile_to_ir("fn f(u8 x) -> u8 { if (x) { return 1; } return 2; }")
+        .expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected bool, found u8");
+}
+
+#[test]
+fn codegen_if_expressions_merge_with_phi() {
+    let ir = compile_to_ir("fn f(bool c) -> u8 { return if (c) 5 else 10; }").expect("codegen");
+    assert!(
+        ir.contains("phi i8 [ 5, %if.then ], [ 10, %if.else ]"),
+        "{ir}"
+    );
+
+    let ir = compile_to_ir("fn f(bool c, u16 y) -> u16 { u16 x = 5 if (c) else y; return x; }")
+        .expect("codegen");
+    assert!(ir.contains("phi i16"), "{ir}");
+
+    // Without context the typed arm decides.
+    let ir = compile_to_ir("fn f(bool c) -> bool { return (if (c) 1 else 2u64) == 2; }")
+        .expect("codegen");
+    assert!(ir.contains("phi i64"), "{ir}");
+}
+
+#[test]
+fn codegen_if_expression_arms_must_agree() {
+    let err = compile_to_ir("fn f(bool c, u8 a, u16 b) -> bool { return (a if (c) else b) == a; }")
+        .expect_err("should fail");
+    assert_eq!(err, "`if` and `else` have incompatible types: u8 and u16");
+
+    let err =
+        compile_to_ir("fn f(bool c) -> u8 { return if (c) 1 else 2.0; }").expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected u8, found float literal");
+}
+
+#[test]
+fn codegen_while_and_for_loops() {
+    let ir = compile_to_ir(
+        "fn sum(u32 n) -> u32 { u32 total; u32 i; while (i < n) { i++; total += i; } return total; }",
+    )
+    .expect("codegen");
+    for block in ["while.cond:", "while.body:", "while.end:"] {
+        assert!(ir.contains(block), "{block}: {ir}");
+    }
+
+    let ir = compile_to_ir(
+        "fn f(u32 n) -> u32 { u32 total; for (u32 i = 0; i < n; i++) { if (i == 3) { continue; } if (i == 7) { break; } total += i; } return total; }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("br label %for.update"), "{ir}");
+    assert!(ir.contains("br label %for.end"), "{ir}");
+
+    // The header's declarations are scoped to the loop.
+    let err = compile_to_ir("fn f() -> u32 { for (u32 i = 0; i < 3; i++) { } return i; }")
+        .expect_err("should fail");
+    assert_eq!(err, "cannot find value 'i' in this scope");
+}
+
+#[test]
+fn codegen_do_while_and_infinite_loops() {
+    let ir = compile_to_ir("fn f(u32 n) -> u32 { u32 i; do { i++; } while (i < n); return i; }")
+        .expect("codegen");
+    // The body runs first; the condition is checked at the bottom.
+    let entry = ir.split("entry:").nth(1).expect("entry block");
+    assert!(entry.contains("br label %do.body"), "{ir}");
+    assert!(
+        ir.contains("br i1 %cmp, label %do.body, label %do.end"),
+        "{ir}"
+    );
+
+    let ir =
+        compile_to_ir("fn f() -> u32 { u32 i; loop { i++; if (i > 9) { break; } } return i; }")
+            .expect("codegen");
+    assert!(ir.contains("br label %loop.body"), "{ir}");
+
+    // A loop without a `break` never falls through, so no return is needed after it.
+    compile_to_ir("fn f() -> u32 { loop { } }").expect("codegen");
+    compile_to_ir("fn f() -> u32 { for (;;) { return 1; } }").expect("codegen");
+}
+
+#[test]
+fn codegen_loop_values_merge_with_phi() {
+    let ir = compile_to_ir(
+        "fn f(u8 n) -> u8 { u8 x = while (n > 3) { if (n == 5) { break 5; } n--; } break 0; return x; }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("phi i8"), "{ir}");
+    // The default is evaluated only when the loop ends on its own.
+    assert!(ir.contains("while.exit:"), "{ir}");
+
+    // `break label value` from an inner loop feeds the outer loop's phi.
+    let ir = compile_to_ir(
+        "fn f(u32 n) -> u32 { found: u32 i = loop { u32 j; while (j < n) { if (j * j == n) { break found j; } j++; } break 0; }; return i; }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("phi i32"), "{ir}");
+
+    // The loop's type comes from its `break`s when there is no other context.
+    compile_to_ir("fn f(u16 a) -> bool { return (loop { break a; }) == 3; }").expect("codegen");
+}
+
+#[test]
+fn codegen_labeled_break_and_continue() {
+    let ir = compile_to_ir(
+        "fn f(u32 n) -> u32 { u32 t; outer: for (u32 i = 0; i < n; i++) { for (u32 j = 0; j < n; j++) { if (j == i) { continue outer; } if (j > 7) { break outer; } t++; } } return t; }",
+    )
+    .expect("codegen");
+    // The inner loop's blocks get numbered suffixes; the outer ones keep their names.
+    assert!(ir.contains("br label %for.update\n"), "{ir}");
+    assert!(ir.contains("br label %for.end\n"), "{ir}");
+}
+
+#[test]
+fn codegen_loop_else_runs_when_body_never_ran() {
+    let ir = compile_to_ir(
+        "fn f(u32 n) -> u32 { u32 r = 1; while (n > 0) { n--; } else { r = 0; } return r; }",
+    )
+    .expect("codegen");
+    for block in ["while.exit:", "while.else:", "while.done:"] {
+        assert!(ir.contains(block), "{block}: {ir}");
+    }
+    assert!(ir.contains("store i1 true"), "{ir}");
+    assert!(ir.contains("%while.entered"), "{ir}");
+}
+
+#[test]
+fn codegen_multiple_return_values_use_a_struct() {
+    let ir = compile_to_ir(
+        "fn split(u32 x) -> u32, bool { return x, x > 3; } \
+         fn f(u32 x) -> u32 { u32 v, bool big = split(x); return v if (big) else 0; }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("define { i32, i1 } @split(i32"), "{ir}");
+    assert!(ir.contains("ret { i32, i1 }"), "{ir}");
+    assert!(ir.contains("extractvalue { i32, i1 }"), "{ir}");
+
+    // A call yielding the same values can be returned as is.
+    compile_to_ir(
+        "fn split(u32 x) -> u32, bool { return x, x > 3; } \
+         fn g(u32 x) -> u32, bool { return split(x); }",
+    )
+    .expect("codegen");
+}
+
+#[test]
+fn codegen_named_returns_are_returned_at_the_end() {
+    let ir = compile_to_ir(
+        "fn count(u32 n) -> u32 even, u32 odd { for (u32 i = 0; i < n; i++) { if (i % 2 == 0) { even++; } else { odd++; } } }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("define { i32, i32 } @count(i32"), "{ir}");
+    assert!(ir.contains("ret { i32, i32 }"), "{ir}");
+
+    // An explicit return still works.
+    compile_to_ir("fn f() -> u8 a, u8 b { if (a == 0) { return 1, 2; } }").expect("codegen");
+
+    let err = compile_to_ir("fn f(u8 a) -> u8 a, u8 b { }").expect_err("should fail");
+    assert_eq!(
+        err,
+        "identifier 'a' is bound more than once in this signature"
+    );
+}
+
+#[test]
+fn codegen_checked_operators_yield_an_overflow_flag() {
+    let ir =
+        compile_to_ir("fn f(u8 a, u8 b) -> u8 { u8 r, bool o = a +? b; return 0 if (o) else r; }")
+            .expect("codegen");
+    assert!(ir.contains("@llvm.uadd.with.overflow.i8"), "{ir}");
+
+    let ir = compile_to_ir("fn f(i16 a, i16 b) -> i16, bool { return a *|? b; }").expect("codegen");
+    assert!(ir.contains("@llvm.smul.with.overflow.i16"), "{ir}");
+    assert!(ir.contains("i16 -32768, i16 32767"), "{ir}");
+
+    let ir = compile_to_ir("fn f(u32 a) -> u32, bool { return a -|? 1; }").expect("codegen");
+    assert!(ir.contains("@llvm.usub.sat.i32"), "{ir}");
+    assert!(ir.contains("@llvm.usub.with.overflow.i32"), "{ir}");
+
+    let err =
+        compile_to_ir("fn f(f32 a) -> f32, bool { return a +? a; }").expect_err("should fail");
+    assert_eq!(err, "cannot apply `+?` to f32");
+}
+
+#[test]
+fn codegen_multiple_return_value_errors() {
+    let pair = "fn pair() -> u8, bool { return 1, 1 < 2; } ";
+    let err = compile_to_ir(&format!("{pair}fn f() -> u8 {{ return pair(); }}"))
+        .expect_err("should fail");
+    assert_eq!(
+        err,
+        "function 'pair' returns 2 values; bind them with a declaration such as `u8 a, bool b = pair(..);`"
+    );
+
+    let err = compile_to_ir(&format!(
+        "{pair}fn f() -> u8 {{ u8 a, u8 b = pair(); return a; }}"
+    ))
+    .expect_err("should fail");
+    assert_eq!(err, "mismatched types: expected u8, found bool");
+
+    let err = compile_to_ir(&format!(
+        "{pair}fn f() -> u8 {{ u8 a, bool b, bool c = pair(); return a; }}"
+    ))
+    .expect_err("should fail");
+    assert_eq!(err, "expected 3 values, found 2");
+
+    let err =
+        compile_to_ir("fn f() -> u8 { u8 a, bool b = 3; return a; }").expect_err("should fail");
+    assert_eq!(err, "expected 2 values, found 1");
+
+    let err = compile_to_ir("fn f() -> u8, bool { return 1, 1 < 2, 3; }").expect_err("should fail");
+    assert_eq!(err, "expected 2 return values, found 3");
+
+    let err = compile_to_ir("fn f() -> u8, bool { }").expect_err("should fail");
+    assert_eq!(
+        err,
+        "function 'f' may reach its end without returning values of type u8, bool"
+    );
+}
+
+#[test]
+fn codegen_calls_with_default_and_named_arguments() {
+    let ir = compile_to_ir(
+        "fn foo(u32 x, u32 y = 10) -> u32 { return x + y; }\n\
+         fn f() -> u32 { return foo(5) + foo(y = 12, x = 5); }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("call i32 @foo(i32 5, i32 10)"), "{ir}");
+    assert!(ir.contains("call i32 @foo(i32 5, i32 12)"), "{ir}");
+}
+
+const FOO_OVERLOADS: &str = "fn foo(u32 x) -> u32 { return x; }\n\
+                             fn foo(u64 x) -> u64 { return x; }\n\
+                             fn foo(u32 x, u32 y) -> u32 { return x + y; }\n\
+                             fn foo(u32 x) -> u64 { return 0; }\n";
+
+#[test]
+fn codegen_overloads_get_mangled_symbols() {
+    let ir = compile_to_ir(FOO_OVERLOADS).expect("codegen");
+    for symbol in [
+        "@_X3fooP3u32R3u32(",
+        "@_X3fooP3u64R3u64(",
+        "@_X3fooP3u323u32R3u32(",
+        "@_X3fooP3u32R3u64(",
+    ] {
+        assert!(ir.contains(symbol), "{symbol}: {ir}");
+    }
+
+    // A function that isn't overloaded keeps its name.
+    let ir =
+        compile_to_ir(&format!("{FOO_OVERLOADS}fn bar() -> u32 {{ return 1; }}")).expect("codegen");
+    assert!(ir.contains("@bar()"), "{ir}");
+}
+
+#[test]
+fn codegen_resolves_overloads_by_arguments_and_expected_type() {
+    let ir = compile_to_ir(&format!(
+        "{FOO_OVERLOADS}fn f(u32 a, u64 b) -> u64 {{ u32 x = foo(a); u32 y = foo(a, 2); u64 z = foo(a); return foo(b); }}"
+    ))
+    .expect("codegen");
+    assert!(ir.contains("call i32 @_X3fooP3u32R3u32(i32 %a"), "{ir}");
+    assert!(ir.contains("call i32 @_X3fooP3u323u32R3u32(i32 %a"), "{ir}");
+    assert!(ir.contains("call i64 @_X3fooP3u32R3u64(i32 %a"), "{ir}");
+    assert!(ir.contains("call i64 @_X3fooP3u64R3u64(i64 %b"), "{ir}");
+
+    // Named arguments and defaults take part in picking the overload.
+    let ir = compile_to_ir(
+        "fn g(u32 x, u32 y = 1) -> u32 { return x; }\n\
+         fn g(u64 z) -> u64 { return z; }\n\
+         fn f(u64 b) -> u64 { u32 a = g(y = 2, x = 3); return g(z = b); }",
+    )
+    .expect("codegen");
+    assert!(ir.contains("@_X1gP3u323u32R3u32(i32 3, i32 2)"), "{ir}");
+    assert!(ir.contains("@_X1gP3u64R3u64(i64 %b"), "{ir}");
+}
+
+#[test]
+fn codegen_rejects_overloads_with_the_same_types() {
+    let err =
+        compile_to_ir("fn foo(u32 x) -> u32 { return x; }\nfn foo(u32 y) -> u32 { return y; }")
+            .expect_err("should fail");
+    assert_eq!(
+        err,
+        "function 'foo' is defined multiple times with the same parameter and return types"
+    );
+}
+
+/// x86-64's data layout, with the 16-byte `i128` alignment older LLVMs left out.
+const X86_64_LAYOUT: &str =
+    "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128";
+
+const LAYOUT_STRUCTS: &str = "struct Mixed { u8 a; u32 b; u16 c; u64 d; }\n\
+     #[packed] struct Packed { u8 a; u32 b; u16 c; u64 d; }\n\
+     struct Outer { u8 tag; Mixed inner; bool flag; }\n\
+     struct Wide { u8 a; u128 b; }\n\
+     fn f() -> u32 { return 0; }";
+
+#[test]
+fn codegen_struct_layout_matches_c() {
+    let context = Context::create();
+    let module = CodeGen::new(&context, "test", &host())
+        .compile_program(&parse(LAYOUT_STRUCTS), None)
+        .expect("codegen");
+    let layout = TargetData::create(X86_64_LAYOUT);
+    for (name, llvm, offsets, size, align) in [
+        (
+            "Mixed",
+            "%Mixed = type { i8, i32, i16, i64 }",
+            &[0, 4, 8, 16][..],
+            24,
+            8,
+        ),
+        (
+            "Packed",
+            "%Packed = type <{ i8, i32, i16, i64 }>",
+            &[0, 1, 5, 7],
+            15,
+            1,
+        ),
+        (
+            "Outer",
+            "%Outer = type { i8, %Mixed, i1 }",
+            &[0, 8, 32],
+            40,
+            8,
+        ),
+        ("Wide", "%Wide = type { i8, i128 }", &[0, 16], 32, 16),
+    ] {
+        let ty = module.get_struct_type(name).expect("struct is declared");
+        assert_eq!(ty.print_to_string().to_string(), llvm);
+        let found: Vec<u64> = (0..offsets.len() as u32)
+            .map(|i| layout.offset_of_element(&ty, i).expect("field exists"))
+            .collect();
+        assert_eq!(found, offsets, "{name} offsets");
+        assert_eq!(layout.get_abi_size(&ty), size, "{name} size");
+        assert_eq!(layout.get_abi_alignment(&ty), align, "{name} alignment");
+    }
+}
+
+const POINT: &str = "struct Point { i32 x; i32 y; }\n";
+
+#[test]
+fn codegen_struct_literals_fields_and_assignment() {
+    let ir = compile_to_ir(&format!(
+        "{POINT}struct Line {{ Point from; Point to; }}\n\
+         fn make(i32 x) -> Point {{ return Point {{ y = x * 2, x = x }}; }}\n\
+         fn len(Line l) -> i32 {{ return l.to.x - l.from.x; }}\n\
+         fn f() -> i32 {{\n\
+             Line l = Line {{ from = make(1), to = Point {{ x = 4, y = 5 }} }};\n\
+             l.from.y = 7;\n\
+             l.to.x += 1;\n\
+             l.to.y++;\n\
+             Point zero;\n\
+             return len(l) + make(3).y + zero.x;\n\
+         }}"
+    ))
+    .expect("codegen");
+    assert!(ir.contains("define %Point @make(i32 %x"), "{ir}");
+    assert!(ir.contains("define i32 @len(%Line %l"), "{ir}");
+    assert!(ir.contains("insertvalue %Point"), "{ir}");
+    assert!(ir.contains("getelementptr inbounds %Line"), "{ir}");
+    // A field of a call's result is extracted from the returned value.
+    assert!(ir.contains("extractvalue %Point %call"), "{ir}");
+    assert!(ir.contains("store %Point zeroinitializer"), "{ir}");
+}
+
+#[test]
+fn codegen_struct_errors() {
+    for (src, message) in [
+        (
+            "fn f() -> i32 { Point p = Point { x = 1 }; return 0; }",
+            "missing field 'y' in literal of struct 'Point'",
+        ),
+        (
+            "fn f() -> i32 { Point p = Point { x = 1, y = 2, z = 3 }; return 0; }",
+            "struct 'Point' has no field named 'z'",
+        ),
+        (
+            "fn f() -> i32 { Point p = Point { x = 1, x = 2, y = 3 }; return 0; }",
+            "field 'x' is specified more than once",
+        ),
+        (
+            "fn f() -> i32 { Point p = Point { x = 1u8, y = 2 }; return 0; }",
+            "mismatched types: expected i32, found u8",
+        ),
+        (
+            "fn f(Point p) -> i32 { return p.z; }",
+            "struct 'Point' has no field named 'z'",
+        ),
+        (
+            "fn f(i32 n) -> i32 { return n.x; }",
+            "type i32 has no field named 'x'",
+        ),
+        (
+            "fn f(Point p) -> i32 { const Point q = p; q.x = 1; return 0; }",
+            "cannot assign to a field of const variable 'q'",
+        ),
+        (
+            "fn f() -> Point { return Point { x = 1, y = 2 }; }\n\
+             fn g() -> i32 { f().x = 1; return 0; }",
+            "invalid left-hand side of assignment",
+        ),
+        (
+            "fn f(Point p) -> i32 { return p + p; }",
+            "cannot apply `+` to Point",
+        ),
+        (
+            "fn f() -> i32 { Circle c = Circle { r = 1 }; return 0; }",
+            "Unknown type 'Circle'",
+        ),
+        (
+            "fn f() -> i32 { return Circle { r = 1 }.r; }",
+            "cannot find struct 'Circle' in this scope",
+        ),
+    ] {
+        let err = compile_to_ir(&format!("{POINT}{src}")).expect_err(src);
+        assert_eq!(err, message, "{src}");
+    }
+
+    for (src, message) in [
+        (
+            "struct A { u8 x; u16 x; }",
+            "field 'x' is declared more than once in struct 'A'",
+        ),
+        (
+            "struct A { u8 x; }\nstruct A { u8 y; }",
+            "struct 'A' is defined multiple times",
+        ),
+        (
+            "struct u8 { u8 x; }",
+            "struct 'u8' has the name of a built-in type",
+        ),
+        (
+            "struct A { B b; }\nstruct B { u8 x; A a; }",
+            "struct 'A' contains itself, so it would have infinite size",
+        ),
+        ("#[align] struct A { u8 x; }", "unknown attribute 'align'"),
+        ("struct A { Missing m; }", "Unknown type 'Missing'"),
+    ] {
+        let err = compile_to_ir(&format!("{src}\nfn f() -> u32 {{ return 0; }}")).expect_err(src);
+        assert_eq!(err, message, "{src}");
+    }
+}
+
+const SHAPE: &str = "enum Shape { Circle(f64), Rect(f32, f32, u8), Empty }\n";
+
+#[test]
+fn codegen_enum_layout_is_tag_and_union() {
+    let machine = host();
+    let context = Context::create();
+    let module = CodeGen::new(&context, "test", &machine)
+        .compile_program(
+            &parse(&format!(
+                "{SHAPE}enum Color {{ Red, Green, Blue }}\n\
+             enum Wrap {{ One(Shape), Two(u8) }}\n\
+             fn f() -> u32 {{ return 0; }}"
+            )),
+            None,
+        )
+        .expect("codegen");
+    let layout = machine.get_target_data();
+    for (name, llvm, size) in [
+        // The union is the most aligned payload, padded to the largest.
+        (
+            "Shape",
+            "%Shape = type { i8, { { double }, [4 x i8] } }",
+            24,
+        ),
+        ("Color", "%Color = type { i8 }", 1),
+        ("Wrap", "%Wrap = type { i8, { { %Shape } } }", 32),
+    ] {
+        let ty = module.get_struct_type(name).expect("enum is declared");
+        assert_eq!(ty.print_to_string().to_string(), llvm);
+        assert_eq!(layout.get_abi_size(&ty), size, "{name} size");
+    }
+}
+
+#[test]
+fn codegen_enum_variants_and_match() {
+    let ir = compile_to_ir(&format!(
+        "{SHAPE}enum Maybe {{ Some(Shape), None }}\n\
+         fn area(Shape s) -> f64 {{\n\
+             return match (s) {{\n\
+                 Shape.Circle(r) => r * r * 3.14,\n\
+                 Shape.Rect(w, h, _) => 1.0,\n\
+                 Shape.Empty => 0.0,\n\
+             }};\n\
+         }}\n\
+         fn pick(Maybe m) -> i32 {{\n\
+             match (m) {{\n\
+                 Maybe.Some(Shape.Empty) => {{ return 1; }}\n\
+                 Maybe.Some(inner) => {{ f64 a = area(inner); }}\n\
+                 Maybe.None => {{}}\n\
+             }}\n\
+             return 0;\n\
+         }}\n\
+         fn sign(i32 n, char c) -> i32 {{\n\
+             i32 s = match (n) {{ 0 => 0, -1 => -1, _ => 1 }};\n\
+             return s + match (c) {{ 'a' => 10, other => 20 }};\n\
+         }}\n\
+         fn f() -> i32 {{\n\
+             Shape s = Shape.Rect(1.0, 2.0, 3);\n\
+             Maybe m = Maybe.Some(Shape.Circle(2.0));\n\
+             return pick(m) + pick(Maybe.None) + sign(-1, 'a');\n\
+         }}"
+    ))
+    .expect("codegen");
+    assert!(ir.contains("define double @area(%Shape %s"), "{ir}");
+    // Variants are built in memory: the tag, then the payload.
+    assert!(ir.contains("store i8 1, "), "{ir}");
+    assert!(
+        ir.contains("getelementptr inbounds { float, float, i8 }"),
+        "{ir}"
+    );
+    assert!(ir.contains("load %Shape"), "{ir}");
+    // Arms test in order, and nothing follows the last arm's failed test.
+    assert!(ir.contains("%is.variant = icmp eq i8 %tag"), "{ir}");
+    for constant in ["-1", "97"] {
+        assert!(
+            ir.lines()
+                .any(|l| l.contains("icmp eq i32 %value") && l.ends_with(&format!(", {constant}"))),
+            "{ir}"
+        );
+    }
+    assert!(ir.contains("phi double"), "{ir}");
+    assert!(ir.contains("unreachable"), "{ir}");
+}
+
+#[test]
+fn codegen_enum_errors() {
+    for (src, message) in [
+        (
+            "fn f() -> i32 { Shape s = Shape.Square(1.0); return 0; }",
+            "enum 'Shape' has no variant named 'Square'",
+        ),
+        (
+            "fn f() -> i32 { Shape s = Shape.Rect(1.0); return 0; }",
+            "variant 'Shape.Rect' has 3 fields but 1 value was supplied",
+        ),
+        (
+            "fn f() -> i32 { Shape s = Shape.Circle; return 0; }",
+            "variant 'Shape.Circle' has 1 field but 0 values were supplied",
+        ),
+        (
+            "fn f() -> i32 { Shape s = Shape.Circle(r = 1.0); return 0; }",
+            "the fields of variant 'Shape.Circle' have no names; pass them by position",
+        ),
+        (
+            "fn f() -> i32 { Shape s = Shape.Circle(1); return 0; }",
+            "mismatched types: expected f64, found integer literal",
+        ),
+        (
+            "fn f(i32 n) -> i32 { return match (n) { 0 => 1, _ => 2u8 }; }",
+            "mismatched types: expected i32, found u8",
+        ),
+        (
+            "fn f(Shape s) -> i32 { return s == s; }",
+            "cannot apply `==` to Shape",
+        ),
+    ] {
+        let err = compile_to_ir(&format!("{SHAPE}{src}")).expect_err(src);
+        assert_eq!(err, message, "{src}");
+    }
+
+    for (src, message) in [
+        (
+            "enum E { A, B(u8), A }",
+            "variant 'A' is declared more than once in enum 'E'",
+        ),
+        (
+            "enum E { A }\nenum E { B }",
+            "enum 'E' is defined multiple times",
+        ),
+        (
+            "struct E { u8 x; }\nenum E { B }",
+            "enum 'E' is defined multiple times",
+        ),
+        (
+            "enum i32 { A }",
+            "enum 'i32' has the name of a built-in type",
+        ),
+        (
+            "enum E { A(S) }\nstruct S { u8 x; E e; }",
+            "enum 'E' contains itself, so it would have infinite size",
+        ),
+        (
+            "#[packed] enum E { A }",
+            "attribute 'packed' cannot be used on an enum; it applies to structs",
+        ),
+        ("enum E { A(Missing) }", "Unknown type 'Missing'"),
+    ] {
+        let err = compile_to_ir(&format!("{src}\nfn f() -> u32 {{ return 0; }}")).expect_err(src);
+        assert_eq!(err, message, "{src}");
+    }
+}
+
+#[test]
+fn codegen_entry_is_called_from_a_c_main() {
+    let ir = compile_to_ir("entry fn main() -> u8 { return 3; }").expect("codegen");
+    assert!(ir.contains("define i8 @_X4mainPR2u8()"), "{ir}");
+    assert!(ir.contains("define i32 @main(i32 %argc"), "{ir}");
+    assert!(ir.contains("call i8 @_X4mainPR2u8()"), "{ir}");
+    assert!(ir.contains("zext i8"), "{ir}");
+
+    // The exit status keeps the sign of a signed result.
+    let ir = compile_to_ir("#[entry] fn start() -> i16 { return -1; }").expect("codegen");
+    assert!(ir.contains("define i16 @start()"), "{ir}");
+    assert!(ir.contains("sext i16"), "{ir}");
+    let ir = compile_to_ir("entry fn main() -> u64 { return 1; }").expect("codegen");
+    assert!(ir.contains("trunc i64"), "{ir}");
+    let ir = compile_to_ir("entry fn main() -> i32 { return 1; }").expect("codegen");
+    assert!(ir.contains("ret i32 %"), "{ir}");
+
+    // An entry that returns nothing exits with 0.
+    let ir = compile_to_ir("entry fn main() { u32 x = 1; }").expect("codegen");
+    assert!(ir.contains("define void @_X4mainPR()"), "{ir}");
+    assert!(ir.contains("ret i32 0"), "{ir}");
+
+    // Without an entry, no C `main` is generated.
+    let ir = compile_to_ir("fn main() -> u32 { return 1; }").expect("codegen");
+    assert!(ir.contains("define i32 @main()"), "{ir}");
+    assert!(!ir.contains("%argc"), "{ir}");
+}
+
+#[test]
+fn codegen_entry_errors() {
+    for (src, expected) in [
+        (
+            "entry fn main() -> u32 { return 0; }\nfn f() -> u32 { return main(); }",
+            "the entry function 'main' cannot be called; it runs when the program starts",
+        ),
+        (
+            "entry fn main() { }\n#[entry] fn start() { }",
+            "function 'start' is marked as the entry point, but 'main' already is",
+        ),
+        (
+            "entry fn main(u32 argc) { }",
+            "the entry function 'main' cannot take parameters",
+        ),
+        (
+            "entry fn main() -> f32 { return 0.0; }",
+            "the entry function 'main' must return nothing or an integer, not f32",
+        ),
+        (
+            "entry fn main() -> u8, u8 { return 0, 0; }",
+            "the entry function 'main' must return nothing or an integer, not u8, u8",
+        ),
+        (
+            "#[inline] fn f() -> u32 { return 0; }",
+            "unknown attribute 'inline'",
+        ),
+    ] {
+        assert_eq!(compile_to_ir(src).expect_err(src), expected, "{src}");
+    }
+}
@@ -0,0 +1,10 @@
+use xenonc::ast::Program;
+use xenonc::lexer::lex;
+use xenonc::parser::Parser;
+
+pub fn parse(src: &str) -> Program {
+    let tokens = lex(src).expect("lexing should succeed");
+    let (program, errors) = Parser::new(&tokens).parse_program();
+    assert!(errors.is_empty(), "parsing should succeed: {errors:?}");
+    program
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use xenonc::config::CrateType;
+use xenonc::entry::{check_entry, find_entry};
+use xenonc::tokens::Span;
+
+use common::parse;
+
+#[test]
+fn find_entry_accepts_either_form() {
+    let program = parse("fn f() -> u32 { return 1; }\nentry fn main() { }");
+    let entry = find_entry(&program).expect("valid entry");
+    assert_eq!(entry.map(|f| f.name.as_str()), Some("main"));
+    let program = parse("#[entry] fn start() -> i32 { return 0; }");
+    let entry = find_entry(&program).expect("valid entry");
+    assert_eq!(entry.map(|f| f.name.as_str()), Some("start"));
+    let program = parse("fn main() -> u32 { return 0; }");
+    assert!(find_entry(&program).expect("no entry").is_none());
+
+    // The second entry is the one pointed at.
+    let program = parse("entry fn a() { }\nentry fn b() { }");
+    let err = find_entry(&program).expect_err("two entries");
+    assert_eq!(err.span, Some(Span { start: 26, end: 27 }));
+}
+
+#[test]
+fn check_entry_depends_on_the_crate_type() {
+    let with_entry = parse("entry fn main() { }");
+    let without = parse("fn main() -> u32 { return 0; }");
+
+    check_entry(&with_entry, CrateType::Bin).expect("a bin with an entry");
+    check_entry(&without, CrateType::Lib).expect("a lib without one");
+
+    let err = check_entry(&without, CrateType::Bin).expect_err("a bin needs an entry");
+    assert_eq!(
+        err.message,
+        "crate type `bin` needs an entry function; write `entry fn main() { .. }` or mark a function `#[entry]`"
+    );
+    assert_eq!(err.span, None);
+    check_entry(&with_entry, CrateType::Staticlib).expect("a lib may have one too");
+}
@@ -1,5 +1,7 @@
-use xenonc::lexer::lex;
-use xenonc::tokens::{Span, TokenKind};
+use xenonc::error::{LexErrorKind, LexWarning};
+use xenonc::lexer::{lex, lex_lossless, lex_recovering};
+use xenonc::tokens::{IntLiteral, IntSuffix, LosslessToken, Span, TokenKind, TriviaKind};
+use xenonc::types::FloatKind;
 
 #[test]
 fn lex_emits_kinds_and_spans_point_to_source() {
@@ -37,7 +39,7 @@ fn lex_emits_kinds_and_spans_point_to_source() {
 }
 
 #[test]
-fn lex_string_literal_strip_quotes_no_decode() {
+fn lex_string_literal_strip_quotes_and_decode_escapes() {
     let src = "\"hi\\n\"";
     let tokens = lex(src).expect("lexing should succeed");
 
@@ -51,12 +53,669 @@ fn lex_string_literal_strip_quotes_no_decode() {
     );
 
     assert_eq!(tokens[0].kind, TokenKind::Str);
-    assert_eq!(tokens[0].str_value().unwrap(), "hi\\n");
+    assert_eq!(tokens[0].str_value().unwrap(), "hi\n");
 }
 
 #[test]
 fn lex_invalid_input_returns_error() {
     let err = lex("fn @").expect_err("lexing should fail on invalid token");
 
+    assert_eq!(err.kind, LexErrorKind::UnknownCharacter);
     assert_eq!(err.span, Span { start: 3, end: 4 });
 }
+
+#[test]
+fn lex_int_literals_with_prefixes_separators_and_suffixes() {
+    let src = "1_000 0xFF 0o17 0b1010_1010 42u8 1_000i64 0x7fu16";
+    let tokens = lex(src).expect("lexing should succeed");
+
+    let literals: Vec<IntLiteral> = tokens.iter().map(|t| t.int_literal().unwrap()).collect();
+    let unsuffixed = |value| IntLiteral {
+        value,
+        suffix: None,
+    };
+    let suffixed = |value, signed, bits| IntLiteral {
+        value,
+        suffix: Some(IntSuffix { signed, bits }),
+    };
+
+    assert_eq!(
+        literals,
+        vec![
+            unsuffixed(1000),
+            unsuffixed(255),
+            unsuffixed(15),
+            unsuffixed(0b1010_1010),
+            suffixed(42, false, 8),
+            suffixed(1000, true, 64),
+            suffixed(0x7f, false, 16),
+        ]
+    );
+    assert_eq!(literals[4].suffix.unwrap().type_name(), "u8");
+}
+
+#[test]
+fn lex_int_literal_wider_than_i64() {
+    let src = "18446744073709551615 340282366920938463463374607431768211455u128";
+    let tokens = lex(src).expect("lexing should succeed");
+
+    assert_eq!(tokens[0].int_value().unwrap(), u64::MAX as u128);
+    assert_eq!(tokens[1].int_value().unwrap(), u128::MAX);
+}
+
+#[test]
+fn lex_malformed_int_literals_report_kind() {
+    for (src, kind) in [
+        ("0x", LexErrorKind::InvalidIntLiteral),
+        ("0b102", LexErrorKind::InvalidIntLiteral),
+        ("0o8", LexErrorKind::InvalidIntLiteral),
+        ("1u0", LexErrorKind::InvalidIntSuffix),
+        ("1i129", LexErrorKind::InvalidIntSuffix),
+    ] {
+        let err = lex(src).expect_err("lexing should fail");
+        assert_eq!(err.kind, kind, "{src:?}");
+        assert_eq!(
+            err.span,
+            Span {
+                start: 0,
+                end: src.len()
+            }
+        );
+    }
+}
+
+#[test]
+fn lex_int_literal_overflow_reports_out_of_range() {
+    let src = "return 340282366920938463463374607431768211456;";
+    let err = lex(src).expect_err("lexing should fail on u128 overflow");
+
+    assert_eq!(err.kind, LexErrorKind::IntLiteralOutOfRange(None));
+    assert_eq!(
+        err.span,
+        Span {
+            start: 7,
+            end: src.len() - 1
+        }
+    );
+    assert_eq!(err.to_string(), "integer literal out of range (span 7..46)");
+
+    let u8_suffix = IntSuffix {
+        signed: false,
+        bits: 8,
+    };
+    let err = lex("256u8").expect_err("256 does not fit in u8");
+    assert_eq!(
+        err.kind,
+        LexErrorKind::IntLiteralOutOfRange(Some(u8_suffix))
+    );
+    assert_eq!(
+        err.to_string(),
+        "integer literal out of range for u8 (span 0..5)"
+    );
+
+    lex("255u8 128i8").expect("boundary values should lex");
+    let err = lex("129i8").expect_err("129 does not fit in i8");
+    assert_eq!(err.kind.to_string(), "integer literal out of range for i8");
+}
+
+#[test]
+fn lex_unterminated_string_reports_kind() {
+    let err = lex("fn \"abc").expect_err("lexing should fail");
+
+    assert_eq!(err.kind, LexErrorKind::UnterminatedString);
+    assert_eq!(err.span, Span { start: 3, end: 7 });
+}
+
+#[test]
+fn lex_float_literals_with_exponents_and_suffixes() {
+    let src = "1.5 1e-3 2.0f32 1_000.25E+2 3bf16 0.1f128 x.y";
+    let tokens = lex(src).expect("lexing should succeed");
+
+    let floats: Vec<(&str, Option<FloatKind>)> = tokens[..6]
+        .iter()
+        .map(|t| {
+            let lit = t.float_literal().unwrap();
+            (lit.text.as_str(), lit.suffix)
+        })
+        .collect();
+
+    assert_eq!(
+        floats,
+        vec![
+            ("1.5", None),
+            ("1e-3", None),
+            ("2.0", Some(FloatKind::F32)),
+            ("1000.25E+2", None),
+            ("3", Some(FloatKind::BF16)),
+            ("0.1", Some(FloatKind::F128)),
+        ]
+    );
+    assert_eq!(tokens[1].float_literal().unwrap().value(), 0.001);
+
+    // `.` followed by a non-digit stays a field access.
+    let kinds: Vec<TokenKind> = tokens[6..].iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![TokenKind::Ident, TokenKind::Period, TokenKind::Ident]
+    );
+}
+
+#[test]
+fn lex_float_exponent_without_digits_is_error() {
+    let err = lex("1e_").expect_err("lexing should fail");
+    assert_eq!(err.kind, LexErrorKind::InvalidFloatLiteral);
+}
+
+#[test]
+fn lex_string_escapes_are_decoded() {
+    let src = r#""\t\\\"\0\x41\u{e9}\u{1F600}\r""#;
+    let tokens = lex(src).expect("lexing should succeed");
+
+    assert_eq!(tokens[0].str_value().unwrap(), "\t\\\"\0A\u{e9}\u{1F600}\r");
+}
+
+#[test]
+fn lex_invalid_escape_reports_exact_span() {
+    for (src, escape) in [
+        (r#"x = "ab\qcd";"#, r"\q"),
+        (r#""\x4""#, r"\x4"),
+        (r#""\x80""#, r"\x80"),
+        (r#""\u{110000}""#, r"\u{110000}"),
+        (r#""\u{}""#, r"\u{}"),
+        (r#""\u41""#, r"\u"),
+    ] {
+        let err = lex(src).expect_err("lexing should fail");
+        assert_eq!(err.kind, LexErrorKind::InvalidEscape, "{src}");
+        assert_eq!(&src[err.span.start..err.span.end], escape, "{src}");
+    }
+}
+
+#[test]
+fn lex_char_literals() {
+    let tokens = lex(r"'a' '\n' '\u{e9}' '\''").expect("lexing should succeed");
+
+    let chars: Vec<char> = tokens.iter().map(|t| t.char_value().unwrap()).collect();
+    assert_eq!(chars, vec!['a', '\n', 'é', '\'']);
+    assert!(tokens.iter().all(|t| t.kind == TokenKind::Char));
+
+    assert_eq!(
+        lex("'ab'").expect_err("two chars").kind,
+        LexErrorKind::InvalidCharLiteral
+    );
+    assert_eq!(
+        lex("''").expect_err("empty char").kind,
+        LexErrorKind::InvalidCharLiteral
+    );
+    assert_eq!(
+        lex("'a").expect_err("unterminated").kind,
+        LexErrorKind::UnterminatedChar
+    );
+}
+
+#[test]
+fn lex_byte_string_literals() {
+    let tokens = lex(r#"b"a\xFF\n" b"#).expect("lexing should succeed");
+
+    assert_eq!(tokens[0].kind, TokenKind::ByteStr);
+    assert_eq!(tokens[0].byte_str_value().unwrap(), b"a\xFF\n");
+    // A lone `b` is still an identifier.
+    assert_eq!(tokens[1].ident_value().unwrap(), "b");
+
+    let src = r#"b"\u{41}""#;
+    let err = lex(src).expect_err("unicode escapes are not allowed in byte strings");
+    assert_eq!(err.kind, LexErrorKind::InvalidEscape);
+
+    let src = "b\"caf\u{e9}\"";
+    let err = lex(src).expect_err("non-ASCII is not allowed in byte strings");
+    assert_eq!(err.kind, LexErrorKind::NonAsciiByteString);
+    assert_eq!(&src[err.span.start..err.span.end], "é");
+}
+
+#[test]
+fn lex_skips_nested_block_comments() {
+    let src = "fn /* outer /* inner */ still outer */ x /**/";
+    let tokens = lex(src).expect("lexing should succeed");
+
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![TokenKind::Fn, TokenKind::Ident]);
+    assert_eq!(&src[tokens[1].span.start..tokens[1].span.end], "x");
+}
+
+#[test]
+fn lex_unterminated_block_comment_points_at_opening() {
+    let src = "fn x /* a /* b */\n return 1;";
+    let err = lex(src).expect_err("lexing should fail");
+
+    assert_eq!(err.kind, LexErrorKind::UnterminatedBlockComment);
+    assert_eq!(err.span, Span { start: 5, end: 7 });
+}
+
+#[test]
+fn lex_keeps_doc_comments() {
+    let src = "//! file docs\n/// item docs\n//// not a doc\n// plain\nfn";
+    let tokens = lex(src).expect("lexing should succeed");
+
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::InnerDocComment,
+            TokenKind::DocComment,
+            TokenKind::Fn
+        ]
+    );
+    assert_eq!(tokens[0].doc_value().unwrap(), " file docs");
+    assert_eq!(tokens[1].doc_value().unwrap(), " item docs");
+}
+
+#[test]
+fn lex_every_operator() {
+    let cases = [
+        ("+", TokenKind::Plus),
+        ("-", TokenKind::Minus),
+        ("*", TokenKind::Star),
+        ("/", TokenKind::Slash),
+        ("%", TokenKind::Percent),
+        ("**", TokenKind::StarStar),
+        ("&", TokenKind::Amp),
+        ("|", TokenKind::Pipe),
+        ("^", TokenKind::Caret),
+        ("~", TokenKind::Tilde),
+        ("<<", TokenKind::Shl),
+        (">>", TokenKind::Shr),
+        ("&&", TokenKind::AndAnd),
+        ("||", TokenKind::OrOr),
+        ("^^", TokenKind::CaretCaret),
+        ("!", TokenKind::Bang),
+        ("==", TokenKind::EqEq),
+        ("!=", TokenKind::NotEq),
+        ("<", TokenKind::Lt),
+        (">", TokenKind::Gt),
+        ("<=", TokenKind::LtEq),
+        (">=", TokenKind::GtEq),
+        ("=", TokenKind::Eq),
+        ("+=", TokenKind::PlusEq),
+        ("-=", TokenKind::MinusEq),
+        ("*=", TokenKind::StarEq),
+        ("/=", TokenKind::SlashEq),
+        ("%=", TokenKind::PercentEq),
+        ("**=", TokenKind::StarStarEq),
+        ("&=", TokenKind::AmpEq),
+        ("|=", TokenKind::PipeEq),
+        ("^=", TokenKind::CaretEq),
+        ("<<=", TokenKind::ShlEq),
+        (">>=", TokenKind::ShrEq),
+        ("++", TokenKind::PlusPlus),
+        ("--", TokenKind::MinusMinus),
+        ("+%", TokenKind::PlusWrap),
+        ("+|", TokenKind::PlusSat),
+        ("+?", TokenKind::PlusChecked),
+        ("+%?", TokenKind::PlusWrapChecked),
+        ("+|?", TokenKind::PlusSatChecked),
+        ("-%", TokenKind::MinusWrap),
+        ("-|", TokenKind::MinusSat),
+        ("-?", TokenKind::MinusChecked),
+        ("-%?", TokenKind::MinusWrapChecked),
+        ("-|?", TokenKind::MinusSatChecked),
+        ("*%", TokenKind::StarWrap),
+        ("*|", TokenKind::StarSat),
+        ("*?", TokenKind::StarChecked),
+        ("*%?", TokenKind::StarWrapChecked),
+        ("*|?", TokenKind::StarSatChecked),
+    ];
+
+    for (src, kind) in cases {
+        let tokens = lex(src).expect("lexing should succeed");
+        assert_eq!(tokens.len(), 1, "{src:?} should be a single token");
+        assert_eq!(tokens[0].kind, kind, "{src:?}");
+    }
+}
+
+#[test]
+fn lex_operators_use_longest_match() {
+    let kinds = |src: &str| -> Vec<TokenKind> {
+        lex(src)
+            .expect("lexing should succeed")
+            .iter()
+            .map(|t| t.kind)
+            .collect()
+    };
+
+    assert_eq!(
+        kinds("a+%?b"),
+        vec![
+            TokenKind::Ident,
+            TokenKind::PlusWrapChecked,
+            TokenKind::Ident
+        ]
+    );
+    assert_eq!(
+        kinds("a>>=b<<c"),
+        vec![
+            TokenKind::Ident,
+            TokenKind::ShrEq,
+            TokenKind::Ident,
+            TokenKind::Shl,
+            TokenKind::Ident
+        ]
+    );
+    assert_eq!(
+        kinds("a+++b"),
+        vec![
+            TokenKind::Ident,
+            TokenKind::PlusPlus,
+            TokenKind::Plus,
+            TokenKind::Ident
+        ]
+    );
+    assert_eq!(
+        kinds("a***b"),
+        vec![
+            TokenKind::Ident,
+            TokenKind::StarStar,
+            TokenKind::Star,
+            TokenKind::Ident
+        ]
+    );
+    assert_eq!(
+        kinds("a||b|c"),
+        vec![
+            TokenKind::Ident,
+            TokenKind::OrOr,
+            TokenKind::Ident,
+            TokenKind::Pipe,
+            TokenKind::Ident
+        ]
+    );
+}
+
+#[test]
+fn lex_lossless_round_trips_source() {
+    let sources = [
+        "",
+        "   ",
+        "fn x()->u32{return 42;}",
+        "// Testing\nfn main()->u32\n{\n\treturn 1; // one\n}\n",
+        "/* a /* nested */ b */fn/**/x //! inner\n/// doc\r\n\"s // not a comment\"",
+        "  x  // trailing comment without newline",
+    ];
+
+    for src in sources {
+        let lexed = lex_lossless(src);
+        assert!(lexed.errors.is_empty(), "{src:?}: {:?}", lexed.errors);
+        let items = lexed.items;
+        let rebuilt: String = items
+            .iter()
+            .map(|item| &src[item.span().start..item.span().end])
+            .collect();
+        assert_eq!(rebuilt, src);
+
+        // Items are contiguous and in order.
+        let mut pos = 0;
+        for item in &items {
+            assert_eq!(item.span().start, pos, "{src:?}");
+            pos = item.span().end;
+        }
+    }
+}
+
+#[test]
+fn lex_lossless_classifies_trivia() {
+    let src = "fn /* c */ x // end\n";
+    let items = lex_lossless(src).items;
+
+    let trivia: Vec<(TriviaKind, &str)> = items
+        .iter()
+        .filter_map(|item| match item {
+            LosslessToken::Trivia { kind, span } => Some((*kind, &src[span.start..span.end])),
+            LosslessToken::Token(_) => None,
+        })
+        .collect();
+    assert_eq!(
+        trivia,
+        vec![
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::BlockComment, "/* c */"),
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::Whitespace, " "),
+            (TriviaKind::LineComment, "// end"),
+            (TriviaKind::Whitespace, "\n"),
+        ]
+    );
+
+    // The token stream matches the fast path.
+    let tokens: Vec<_> = items
+        .into_iter()
+        .filter_map(|item| match item {
+            LosslessToken::Token(t) => Some(t),
+            LosslessToken::Trivia { .. } => None,
+        })
+        .collect();
+    assert_eq!(tokens, lex(src).unwrap());
+}
+
+#[test]
+fn lex_lossless_round_trips_source_with_errors() {
+    let sources = [
+        "fn @ x $ () -> 300u8",
+        "fn x() { /* open /* nested */ never closed",
+        "let s = \"unterminated\n  x",
+        "` // comment after an error\n'ab' u32",
+    ];
+
+    for src in sources {
+        let lexed = lex_lossless(src);
+        assert!(!lexed.errors.is_empty(), "{src:?}");
+        assert_eq!(lexed.errors, lex_recovering(src).errors, "{src:?}");
+
+        let mut rebuilt = String::new();
+        let mut pos = 0;
+        for item in &lexed.items {
+            assert_eq!(item.span().start, pos, "{src:?}");
+            pos = item.span().end;
+            rebuilt.push_str(&src[item.span().start..item.span().end]);
+        }
+        assert_eq!(rebuilt, src);
+
+        // Every error is covered by an error token.
+        for error in &lexed.errors {
+            assert!(
+                lexed.items.iter().any(|item| matches!(
+                    item,
+                    LosslessToken::Token(t)
+                        if t.kind == TokenKind::Error && t.span.start <= error.span.start
+                            && error.span.end <= t.span.end
+                )),
+                "{src:?}: {error:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn lex_recovering_reports_every_error_and_keeps_going() {
+    let src = "fn @ x $ () -> 300u8 \"a\\q\" ` u32";
+    let lexed = lex_recovering(src);
+
+    let errors: Vec<(LexErrorKind, &str)> = lexed
+        .errors
+        .iter()
+        .map(|e| (e.kind, &src[e.span.start..e.span.end]))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (LexErrorKind::UnknownCharacter, "@"),
+            (LexErrorKind::UnknownCharacter, "$"),
+            (
+                LexErrorKind::IntLiteralOutOfRange(Some(IntSuffix {
+                    signed: false,
+                    bits: 8
+                })),
+                "300u8"
+            ),
+            (LexErrorKind::InvalidEscape, "\\q"),
+            (LexErrorKind::UnknownCharacter, "`"),
+        ]
+    );
+
+    let kinds: Vec<TokenKind> = lexed.tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Fn,
+            TokenKind::Error,
+            TokenKind::Ident,
+            TokenKind::Error,
+            TokenKind::LParen,
+            TokenKind::RParen,
+            TokenKind::Arrow,
+            TokenKind::Error,
+            TokenKind::Error,
+            TokenKind::Error,
+            TokenKind::Ident,
+        ]
+    );
+    // The error token covers the whole bad literal, the error only the bad escape.
+    let string_token = &lexed.tokens[8];
+    assert_eq!(
+        &src[string_token.span.start..string_token.span.end],
+        "\"a\\q\""
+    );
+}
+
+#[test]
+fn lex_stops_at_first_error() {
+    let err = lex("@ $").expect_err("lexing should fail");
+    assert_eq!(err.span, Span { start: 0, end: 1 });
+}
+
+#[test]
+fn lex_unicode_identifiers() {
+    let src = "fn café 変数 _ñ x1 δx";
+    let tokens = lex(src).expect("lexing should succeed");
+
+    assert_eq!(tokens[0].kind, TokenKind::Fn);
+    let names: Vec<&str> = tokens[1..]
+        .iter()
+        .map(|t| t.ident_value().unwrap())
+        .collect();
+    assert_eq!(names, vec!["café", "変数", "_ñ", "x1", "δx"]);
+}
+
+#[test]
+fn lex_identifiers_are_nfc_normalized() {
+    // Precomposed U+00E9 and `e` followed by combining U+0301.
+    let src = "caf\u{e9} cafe\u{301}";
+    let tokens = lex(src).expect("lexing should succeed");
+
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].ident_value().unwrap(), "caf\u{e9}");
+    assert_eq!(
+        tokens[0].ident_value().unwrap(),
+        tokens[1].ident_value().unwrap()
+    );
+    // Spans still cover the original bytes.
+    assert_eq!(tokens[1].span.end, src.len());
+}
+
+#[test]
+fn lex_warns_on_mixed_script_identifiers() {
+    // `p` + Cyrillic `а` (U+0430) + `ypal`.
+    let src = "paypal p\u{430}ypal 変数";
+    let lexed = lex_recovering(src);
+
+    assert!(lexed.errors.is_empty());
+    assert_eq!(
+        lexed.warnings,
+        vec![LexWarning::MixedScriptIdentifier {
+            name: "p\u{430}ypal".to_string(),
+            span: Span { start: 7, end: 14 },
+        }]
+    );
+}
+
+#[test]
+fn lex_loop_keywords() {
+    let tokens = lex("while for do loop break continue looping").expect("lexing should succeed");
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::While,
+            TokenKind::For,
+            TokenKind::Do,
+            TokenKind::Loop,
+            TokenKind::Break,
+            TokenKind::Continue,
+            TokenKind::Ident,
+        ]
+    );
+}
+
+#[test]
+fn lex_struct_keyword_and_attribute_tokens() {
+    let tokens = lex("#[packed] struct structure").expect("lexing should succeed");
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Hash,
+            TokenKind::LBracket,
+            TokenKind::Ident,
+            TokenKind::RBracket,
+            TokenKind::Struct,
+            TokenKind::Ident,
+        ]
+    );
+}
+
+#[test]
+fn lex_enum_and_match_tokens() {
+    let tokens = lex("enum match matches x => y >= =").expect("lexing should succeed");
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Enum,
+            TokenKind::Match,
+            TokenKind::Ident,
+            TokenKind::Ident,
+            TokenKind::FatArrow,
+            TokenKind::Ident,
+            TokenKind::GtEq,
+            TokenKind::Eq,
+        ]
+    );
+}
+
+#[test]
+fn lex_module_keywords() {
+    let tokens = lex("pub import a.b as c; imports public").expect("lexing should succeed");
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Pub,
+            TokenKind::Import,
+            TokenKind::Ident,
+            TokenKind::Period,
+            TokenKind::Ident,
+            TokenKind::As,
+            TokenKind::Ident,
+            TokenKind::Semicolon,
+            TokenKind::Ident,
+            TokenKind::Ident,
+        ]
+    );
+}
+
+#[test]
+fn lex_entry_keyword() {
+    let tokens = lex("entry fn entry_point").expect("lexing should succeed");
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![TokenKind::Entry, TokenKind::Fn, TokenKind::Ident]
+    );
+}
@@ -0,0 +1,259 @@
+use std::path::PathBuf;
+
+use inkwell::context::Context;
+use xenonc::ast::{ExprKind, Program, StmtKind};
+use xenonc::codegen::{CodeGen, host_target_machine};
+use xenonc::lexer::lex;
+use xenonc::modules::{Module, load_modules, resolve_modules};
+use xenonc::parser::Parser;
+use xenonc::resolve::resolve_calls;
+use xenonc::sema::check_program;
+use xenonc::source_map::{FileId, SourceMap};
+
+fn module(name: &str, src: &str) -> Module {
+    let tokens = lex(src).expect("lexing should succeed");
+    let (program, errors) = Parser::new(&tokens).parse_program();
+    assert!(errors.is_empty(), "parsing should succeed: {errors:?}");
+    Module {
+        name: name.to_string(),
+        file: FileId(0),
+        program,
+    }
+}
+
+/// Merges `(name, source)` modules, the first being the root module.
+fn resolve(modules: &[(&str, &str)]) -> Result<Program, String> {
+    let modules = modules
+        .iter()
+        .map(|(name, src)| module(name, src))
+        .collect();
+    resolve_modules(modules).map_err(|e| e.message)
+}
+
+/// The callee of each call returned by function `name`.
+fn returned_callees(program: &Program, name: &str) -> Vec<String> {
+    let function = program.functions.iter().find(|f| f.name == name);
+    let body = &function.expect("a function").body;
+    let Some(StmtKind::Return(values)) = body.stmts.last().map(|s| &s.kind) else {
+        panic!("Expected a return statement");
+    };
+    values
+        .iter()
+        .map(|value| match &value.kind {
+            ExprKind::Call { callee, .. } => match &callee.kind {
+                ExprKind::Ident(name) => name.clone(),
+                other => format!("{other:?}"),
+            },
+            other => panic!("Expected a call, got {other:?}"),
+        })
+        .collect()
+}
+
+const GEO: &str = "pub struct Point { u32 x; u32 y; }\n\
+                   pub enum Shape { Square(u32), Dot }\n\
+                   struct Secret { u8 a; }\n\
+                   fn helper(Point p) -> u32 { return p.x * p.y; }\n\
+                   pub fn area(Point p) -> u32 { return helper(p); }\n";
+
+#[test]
+fn resolve_modules_names_items_after_their_module() {
+    let root = "import geo;\n\
+                fn helper(u32 x) -> u32 { return x; }\n\
+                fn f(geo.Point p, geo.Shape s) -> u32, u32 { return helper(1), geo.area(p); }";
+    let program = resolve(&[("main", root), ("geo", GEO)]).expect("resolves");
+
+    let functions: Vec<&str> = program.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(functions, ["helper", "f", "geo.helper", "geo.area"]);
+    let structs: Vec<&str> = program.structs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(structs, ["geo.Point", "geo.Secret"]);
+    assert_eq!(program.enums[0].name, "geo.Shape");
+
+    let f = &program.functions[1];
+    let params: Vec<&str> = f.params.iter().map(|p| p.ty.as_str()).collect();
+    assert_eq!(params, ["geo.Point", "geo.Shape"]);
+    assert_eq!(returned_callees(&program, "f"), ["helper", "geo.area"]);
+    // Within `geo`, its own items are named the same way.
+    assert_eq!(program.functions[3].params[0].ty, "geo.Point");
+    assert_eq!(returned_callees(&program, "geo.area"), ["geo.helper"]);
+    // Unless it is the root module.
+    let program = resolve(&[("geo", GEO)]).expect("resolves");
+    assert_eq!(returned_callees(&program, "area"), ["helper"]);
+}
+
+#[test]
+fn resolve_modules_rewrites_enums_struct_literals_and_patterns() {
+    let root = "import geo as g;\n\
+                fn f(g.Shape s) -> u32 {\n\
+                    g.Point p = g.Point { x = 1, y = 2 };\n\
+                    g.Shape t = g.Shape.Square(p.x);\n\
+                    match (s) { g.Shape.Square(n) => { return n; } g.Shape.Dot => {} }\n\
+                    return 0;\n\
+                }";
+    let program = resolve(&[("main", root), ("geo", GEO)]).expect("resolves");
+    let stmts: Vec<&StmtKind> = program.functions[0]
+        .body
+        .stmts
+        .iter()
+        .map(|s| &s.kind)
+        .collect();
+    let StmtKind::VarDecl(point) = stmts[0] else {
+        panic!("Expected declaration, got {:?}", stmts[0]);
+    };
+    assert_eq!(point.ty, "geo.Point");
+    assert!(matches!(
+        &point.init.as_ref().unwrap().kind,
+        ExprKind::StructLit { name, .. } if name == "geo.Point"
+    ));
+    let StmtKind::VarDecl(shape) = stmts[1] else {
+        panic!("Expected declaration, got {:?}", stmts[1]);
+    };
+    let ExprKind::Call { callee, .. } = &shape.init.as_ref().unwrap().kind else {
+        panic!("Expected a variant, got {:?}", shape.init);
+    };
+    let ExprKind::Field { base, name, .. } = &callee.kind else {
+        panic!("Expected a variant path, got {callee:?}");
+    };
+    assert!(matches!(&base.kind, ExprKind::Ident(e) if e == "geo.Shape"));
+    assert_eq!(name, "Square");
+    let StmtKind::Match(m) = stmts[2] else {
+        panic!("Expected match, got {:?}", stmts[2]);
+    };
+    for arm in &m.arms {
+        assert!(matches!(
+            &arm.pattern.kind,
+            xenonc::ast::PatternKind::Variant { enum_name, .. } if enum_name == "geo.Shape"
+        ));
+    }
+}
+
+#[test]
+fn resolve_modules_enforces_visibility() {
+    for (root, expected) in [
+        (
+            "import geo; fn f(geo.Point p) -> u32 { return geo.helper(p); }",
+            "function 'helper' is private to module 'geo'",
+        ),
+        (
+            "import geo; fn f(geo.Secret s) -> u32 { return 0; }",
+            "struct 'Secret' is private to module 'geo'",
+        ),
+        (
+            "import geo; fn f() -> u32 { return geo.volume(); }",
+            "cannot find function 'volume' in module 'geo'",
+        ),
+        (
+            "import geo; fn f(geo.Line l) -> u32 { return 0; }",
+            "cannot find type 'Line' in module 'geo'",
+        ),
+        (
+            "import geo; fn f(geom.Point p) -> u32 { return 0; }",
+            "cannot find module 'geom' in this scope",
+        ),
+        (
+            "import geo; import geo; fn f() -> u32 { return 0; }",
+            "the name 'geo' is imported more than once",
+        ),
+        (
+            "import space; fn f() -> u32 { return 0; }",
+            "cannot find module 'space'",
+        ),
+        (
+            "pub fn f(u8 x) -> u32 { return 0; } fn f(u16 x) -> u32 { return 0; }",
+            "the overloads of function 'f' must either all be `pub` or none be",
+        ),
+    ] {
+        let err = resolve(&[("main", root), ("geo", GEO)]).expect_err(root);
+        assert_eq!(err, expected, "{root}");
+    }
+}
+
+#[test]
+fn resolve_modules_keeps_modules_apart() {
+    // A module cannot see the root module's items without importing it.
+    let util = "pub fn g() -> u32 { return helper(); }";
+    let root = "import util; fn helper() -> u32 { return 1; }";
+    let err = resolve(&[("main", root), ("util", util)]).expect_err("helper is not in util");
+    assert_eq!(err, "cannot find function 'helper' in this scope");
+    let util = "pub fn g(Point p) -> u32 { return 0; }";
+    let root = "struct Point { u32 x; }";
+    let err = resolve(&[("main", root), ("util", util)]).expect_err("Point is not in util");
+    assert_eq!(err, "Unknown type 'Point'");
+
+    // A local hides an imported module of the same name before a `.`.
+    let root = "import geo; fn f(geo.Point geo) -> u32 { return geo.x; }";
+    resolve(&[("main", root), ("geo", GEO)]).expect("`geo.x` is a field");
+    let root = "fn f() -> u32 { return Shape.Dot; }";
+    let err = resolve(&[("main", root)]).expect_err("no enum Shape");
+    assert_eq!(err, "cannot find value 'Shape' in this scope");
+}
+
+/// A fresh directory for one test's source files.
+fn scratch_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xenonc-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn write(path: &PathBuf, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[test]
+fn load_modules_follows_imports() {
+    let dir = scratch_dir("load-modules");
+    let root = dir.join("src/main.xe");
+    write(&root, "import geo.shapes as s; import util;");
+    write(&dir.join("src/geo/shapes.xe"), "import util;");
+    write(&dir.join("lib/util.xe"), "import geo.shapes;");
+
+    let mut source_map = SourceMap::new();
+    source_map.add_file(root.clone(), std::fs::read_to_string(&root).unwrap());
+    let loaded = load_modules(&mut source_map, &[dir.join("lib")]);
+    assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+    let names: Vec<&str> = loaded.modules.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["main", "geo.shapes", "util"]);
+    assert_eq!(source_map.files().len(), 3);
+    assert_eq!(
+        source_map.file(loaded.modules[2].file).path,
+        dir.join("lib/util.xe")
+    );
+
+    // Without the include path, `util` is looked for only next to the root.
+    let mut source_map = SourceMap::new();
+    source_map.add_file(root.clone(), std::fs::read_to_string(&root).unwrap());
+    let loaded = load_modules(&mut source_map, &[]);
+    let errors: Vec<&str> = loaded.errors.iter().map(|e| e.message.as_str()).collect();
+    let expected = format!(
+        "cannot find module 'util'; looked for {}",
+        dir.join("src/util.xe").display()
+    );
+    assert_eq!(errors, [expected.as_str(), expected.as_str()]);
+    // The error points at the path in the import that names the module.
+    let span = loaded.errors[0].span;
+    assert_eq!(&source_map.files()[0].content[span.start..span.end], "util");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn modules_get_qualified_symbols() {
+    let root = "import util;\n\
+                fn helper(u32 x) -> u32 { return x; }\n\
+                fn main() -> u32 { return helper(1) + util.helper(2); }";
+    let util = "pub fn helper(u32 x) -> u32 { return x * 2; }";
+    let mut program = resolve(&[("main", root), ("util", util)]).expect("resolves");
+    resolve_calls(&mut program).expect("calls resolve");
+    check_program(&mut program).expect("checks");
+
+    let context = Context::create();
+    let tm = host_target_machine().expect("the host is a supported target");
+    let module = CodeGen::new(&context, "test", &tm)
+        .compile_program(&program, None)
+        .expect("codegen");
+    module.verify().expect("valid module");
+    let symbols: Vec<String> = module
+        .get_functions()
+        .map(|f| f.get_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(symbols, ["helper", "main", "_X4util6helperP3u32R3u32"]);
+}
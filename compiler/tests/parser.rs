@@ -1,55 +1,62 @@
-use xenonc::ast::Expr;
-use xenonc::lexer::lex;
+mod common;
+
+use xenonc::ast::{
+    ArmBody, AttrArgs, AttrLitKind, BinaryOp, Expr, ExprKind, LoopKind, Match, MetaItem, Modifier,
+    Pattern, PatternKind, Program, StmtKind,
+};
+use xenonc::error::ParseError;
+use xenonc::lexer::{lex, lex_recovering};
 use xenonc::parser::Parser;
-use xenonc::tokens::Span;
+use xenonc::tokens::{IntLiteral, Span};
+
+use common::parse;
+
+/// The first syntax error of a program that has some.
+fn parse_err(src: &str) -> ParseError {
+    let tokens = lex(src).expect("lexing should succeed");
+    let (_, errors) = Parser::new(&tokens).parse_program();
+    errors.into_iter().next().expect("parsing should fail")
+}
 
 #[test]
 fn parse_program_parses_minimal_function() {
     let src = "fn x()->u32{return 42;}";
-    let tokens = lex(src).expect("lexing should succeed");
-
-    let mut parser = Parser::new(&tokens);
-    let program = parser.parse_program().expect("parsing should succeed");
+    let program = parse(src);
 
     assert_eq!(program.functions.len(), 1);
     let function = &program.functions[0];
     assert_eq!(function.name, "x");
-    assert_eq!(function.return_type, "u32");
-    assert_eq!(function.body.len(), 1);
+    assert_eq!(function.returns.len(), 1);
+    assert_eq!(function.returns[0].ty, "u32");
+    assert!(function.returns[0].name.is_none());
+    assert_eq!(function.body.stmts.len(), 1);
 
     assert!(matches!(
-        &function.body[0],
-        Expr::Return(expr) if matches!(expr.as_ref(), Expr::Int(42))
+        &function.body.stmts[0].kind,
+        StmtKind::Return(values) if matches!(values[..], [Expr { kind: ExprKind::Int(IntLiteral { value: 42, suffix: None }), .. }])
     ));
 }
 
 #[test]
 fn parse_program_reports_token_span_for_invalid_return_expr() {
     let src = "fn x()->u32{return;}";
-    let tokens = lex(src).expect("lexing should succeed");
-
-    let mut parser = Parser::new(&tokens);
-    let err = parser
-        .parse_program()
-        .expect_err("parsing should fail on missing return expression");
+    let err = parse_err(src);
 
-    assert_eq!(err.message, "Expected one of [Int, Ident], found Semicolon");
+    assert_eq!(
+        err.message,
+        "Expected one of [Int, Float, Str, ByteStr, Char, Ident, LParen], found Semicolon"
+    );
     assert_eq!(err.span, Span { start: 18, end: 19 });
 }
 
 #[test]
 fn parse_program_reports_eof_span_when_expression_is_missing() {
     let src = "fn x()->u32{return";
-    let tokens = lex(src).expect("lexing should succeed");
-
-    let mut parser = Parser::new(&tokens);
-    let err = parser
-        .parse_program()
-        .expect_err("parsing should fail at end of input");
+    let err = parse_err(src);
 
     assert_eq!(
         err.message,
-        "Expected one of [Int, Ident], found end of input"
+        "Expected one of [Int, Float, Str, ByteStr, Char, Ident, LParen], found end of input"
     );
     assert_eq!(err.span, Span { start: 18, end: 18 });
 }
@@ -57,12 +64,1219 @@ fn parse_program_reports_eof_span_when_expression_is_missing() {
 #[test]
 fn parse_program_parses_return_ident() {
     let src = "fn x()->u32{return y;}";
+    let program = parse(src);
+
+    match &program.functions[0].body.stmts[0].kind {
+        StmtKind::Return(values) => {
+            assert!(matches!(&values[0].kind, ExprKind::Ident(s) if s == "y"))
+        }
+        other => panic!("Expected return statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn parse_program_binary_expr_respects_precedence() {
+    let src = "fn x()->f64{return 1.5 + 2.0 * 3.0 < 4.0;}";
+    let program = parse(src);
+
+    let StmtKind::Return(values) = &program.functions[0].body.stmts[0].kind else {
+        panic!("Expected return statement");
+    };
+    let expr = &values[0];
+    let ExprKind::Binary { op, lhs, .. } = &expr.kind else {
+        panic!("Expected comparison, got {:?}", expr);
+    };
+    assert_eq!(*op, BinaryOp::Lt);
+    let ExprKind::Binary { op, rhs, .. } = &lhs.kind else {
+        panic!("Expected addition, got {:?}", lhs);
+    };
+    assert_eq!(*op, BinaryOp::Add);
+    assert!(matches!(
+        rhs.kind,
+        ExprKind::Binary {
+            op: BinaryOp::Mul,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn parse_program_skips_doc_comments() {
+    let src = "//! module\n/// Returns one.\nfn x()->u32{ /// inner\n return 1;}";
+    let program = parse(src);
+
+    assert_eq!(program.functions.len(), 1);
+    assert_eq!(program.functions[0].name, "x");
+}
+
+#[test]
+fn parse_program_skips_lex_error_tokens() {
+    let src = "fn x()->u32{ @ return 1; }";
+    let lexed = lex_recovering(src);
+    assert_eq!(lexed.errors.len(), 1);
+
+    let (program, errors) = Parser::new(&lexed.tokens).parse_program();
+    assert!(errors.is_empty(), "parsing should succeed: {errors:?}");
+    assert_eq!(program.functions[0].name, "x");
+}
+
+#[test]
+fn parse_program_records_expression_spans() {
+    let src = "fn x()->u32{return 1 + 23;}";
+    let program = parse(src);
+
+    let function = &program.functions[0];
+    assert_eq!(function.span, Span { start: 3, end: 4 });
+    assert_eq!(function.returns[0].ty_span, Span { start: 8, end: 11 });
+    let ret = &function.body.stmts[0];
+    assert_eq!(ret.span, Span { start: 12, end: 26 });
+    let StmtKind::Return(values) = &ret.kind else {
+        panic!("Expected return statement");
+    };
+    let expr = &values[0];
+    assert_eq!(expr.span, Span { start: 19, end: 25 });
+}
+
+#[test]
+fn parse_program_parses_typed_params() {
+    let src = "fn foo(u32 x, u64 y) -> u32 { return x; }";
+    let program = parse(src);
+
+    let params = &program.functions[0].params;
+    assert_eq!(params.len(), 2);
+    assert_eq!(
+        (params[0].ty.as_str(), params[0].name.as_str()),
+        ("u32", "x")
+    );
+    assert_eq!(params[0].ty_span, Span { start: 7, end: 10 });
+    assert_eq!(params[0].span, Span { start: 7, end: 12 });
+    assert_eq!(
+        (params[1].ty.as_str(), params[1].name.as_str()),
+        ("u64", "y")
+    );
+}
+
+#[test]
+fn parse_program_rejects_param_without_name() {
+    let src = "fn foo(u32) -> u32 { return 1; }";
+    let err = parse_err(src);
+
+    assert_eq!(err.message, "Expected Ident, found RParen");
+    assert_eq!(err.span, Span { start: 10, end: 11 });
+}
+
+fn parse_expr(src: &str) -> Expr {
     let tokens = lex(src).expect("lexing should succeed");
     let mut parser = Parser::new(&tokens);
-    let program = parser.parse_program().expect("parsing should succeed");
+    parser.parse_expression().expect("parsing should succeed")
+}
 
-    match &program.functions[0].body[0] {
-        Expr::Return(expr) => assert!(matches!(expr.as_ref(), Expr::Ident(s) if s == "y")),
-        other => panic!("Expected return statement, got {:?}", other),
+/// Renders an expression as an S-expression so tests can spell out its shape.
+fn sexpr(e: &Expr) -> String {
+    match &e.kind {
+        ExprKind::Int(lit) => lit.value.to_string(),
+        ExprKind::Float(lit) => lit.text.clone(),
+        ExprKind::Ident(name) => name.clone(),
+        ExprKind::Binary { op, lhs, rhs } => {
+            format!("({} {} {})", op.symbol(), sexpr(lhs), sexpr(rhs))
+        }
+        ExprKind::Unary { op, operand } => format!("({} {})", op.symbol(), sexpr(operand)),
+        ExprKind::Call { callee, args, .. } => {
+            let mut out = format!("(call {}", sexpr(callee));
+            for arg in args {
+                out.push(' ');
+                if let Some(name) = &arg.name {
+                    out.push_str(name);
+                    out.push('=');
+                }
+                out.push_str(&sexpr(&arg.value));
+            }
+            out + ")"
+        }
+        ExprKind::Field { base, name, .. } => format!("(. {} {name})", sexpr(base)),
+        ExprKind::StructLit { name, fields } => {
+            let mut out = format!("({name}");
+            for field in fields {
+                out.push_str(&format!(" {}={}", field.name, sexpr(&field.value)));
+            }
+            out + ")"
+        }
+        ExprKind::Match(m) => sexpr_match(m),
+        other => format!("{other:?}"),
+    }
+}
+
+/// `(match x [pattern => body] ..)`, with block bodies as `{..}`.
+fn sexpr_match(m: &Match) -> String {
+    let mut out = format!("(match {}", sexpr(&m.scrutinee));
+    for arm in &m.arms {
+        let body = match &arm.body {
+            ArmBody::Expr(e) => sexpr(e),
+            ArmBody::Block(_) => "{..}".to_string(),
+        };
+        out.push_str(&format!(" [{} => {body}]", pattern_str(&arm.pattern)));
+    }
+    out + ")"
+}
+
+fn pattern_str(pattern: &Pattern) -> String {
+    match &pattern.kind {
+        PatternKind::Wildcard => "_".to_string(),
+        PatternKind::Binding(name) => name.clone(),
+        PatternKind::Int { negative, lit } => {
+            format!("{}{}", if *negative { "-" } else { "" }, lit.value)
+        }
+        PatternKind::Char(c) => format!("{c:?}"),
+        PatternKind::Variant {
+            enum_name,
+            variant,
+            fields,
+        } if fields.is_empty() => format!("{enum_name}.{variant}"),
+        PatternKind::Variant {
+            enum_name,
+            variant,
+            fields,
+        } => {
+            let fields: Vec<String> = fields.iter().map(pattern_str).collect();
+            format!("{enum_name}.{variant}({})", fields.join(", "))
+        }
+    }
+}
+
+#[test]
+fn parse_expression_follows_precedence_table() {
+    for (src, expected) in [
+        ("a || b ^^ c && d", "(|| a (^^ b (&& c d)))"),
+        ("a && b == c", "(&& a (== b c))"),
+        ("a == b | c", "(== a (| b c))"),
+        ("a | b ^ c & d", "(| a (^ b (& c d)))"),
+        ("a & b << 1", "(& a (<< b 1))"),
+        ("a >> 1 + 2", "(>> a (+ 1 2))"),
+        ("a + b * c % d", "(+ a (% (* b c) d))"),
+        ("a +% b *| c -? d", "(-? (+% a (*| b c)) d)"),
+        ("-a * b", "(* (- a) b)"),
+        ("-a ** 2", "(- (** a 2))"),
+        ("2 ** -a", "(** 2 (- a))"),
+        ("!a && ~b == c", "(&& (! a) (== (~ b) c))"),
+    ] {
+        assert_eq!(sexpr(&parse_expr(src)), expected, "{src}");
+    }
+}
+
+#[test]
+fn parse_expression_associativity() {
+    assert_eq!(sexpr(&parse_expr("a - b - c")), "(- (- a b) c)");
+    assert_eq!(sexpr(&parse_expr("a / b / c")), "(/ (/ a b) c)");
+    assert_eq!(sexpr(&parse_expr("a ** b ** c")), "(** a (** b c))");
+    // `--` is the decrement token, so nested negation needs a space.
+    assert_eq!(sexpr(&parse_expr("- -a")), "(- (- a))");
+}
+
+#[test]
+fn parse_expression_parentheses_override_precedence() {
+    let expr = parse_expr("(a + b) * c");
+    assert_eq!(sexpr(&expr), "(* (+ a b) c)");
+    let ExprKind::Binary { lhs, .. } = &expr.kind else {
+        panic!("Expected multiplication, got {:?}", expr);
+    };
+    // The parenthesized operand's span includes the parentheses.
+    assert_eq!(lhs.span, Span { start: 0, end: 7 });
+    assert_eq!(expr.span, Span { start: 0, end: 11 });
+}
+
+#[test]
+fn parse_expression_calls() {
+    assert_eq!(sexpr(&parse_expr("f()")), "(call f)");
+    assert_eq!(
+        sexpr(&parse_expr("f(1, g(x) + 2,)(y)")),
+        "(call (call f 1 (+ (call g x) 2)) y)"
+    );
+    assert_eq!(sexpr(&parse_expr("-f(x) ** 2")), "(- (** (call f x) 2))");
+
+    let expr = parse_expr("foo(1, 2)");
+    assert_eq!(expr.span, Span { start: 0, end: 9 });
+}
+
+#[test]
+fn parse_expression_unary_span_covers_operator() {
+    let expr = parse_expr("x + -y");
+    let ExprKind::Binary { rhs, .. } = &expr.kind else {
+        panic!("Expected addition, got {:?}", expr);
+    };
+    assert_eq!(rhs.span, Span { start: 4, end: 6 });
+}
+
+#[test]
+fn parse_expression_reports_unclosed_delimiters() {
+    for (src, message, span) in [
+        (
+            "(a + b",
+            "Expected RParen, found end of input",
+            Span { start: 6, end: 6 },
+        ),
+        (
+            "f(a b)",
+            "Expected RParen, found Ident",
+            Span { start: 4, end: 5 },
+        ),
+    ] {
+        let tokens = lex(src).expect("lexing should succeed");
+        let err = Parser::new(&tokens)
+            .parse_expression()
+            .expect_err("parsing should fail");
+        assert_eq!(err.message, message, "{src}");
+        assert_eq!(err.span, span, "{src}");
+    }
+}
+
+fn parse_body(src: &str) -> Vec<StmtKind> {
+    let mut program = parse(&format!("fn f() -> u32 {{ {src} }}"));
+    let function = program.functions.remove(0);
+    function.body.stmts.into_iter().map(|s| s.kind).collect()
+}
+
+fn parse_body_err(src: &str) -> String {
+    parse_err(&format!("fn f() -> u32 {{ {src} }}")).message
+}
+
+#[test]
+fn parse_statements() {
+    let stmts =
+        parse_body("const u64 x = 42; u8 y;; y = 1; y += 2; y++; --y; f(y); { u8 z; } return 0;");
+    assert_eq!(stmts.len(), 9);
+
+    let StmtKind::VarDecl(decl) = &stmts[0] else {
+        panic!("Expected declaration, got {:?}", stmts[0]);
+    };
+    assert_eq!(decl.modifiers, [Modifier::Const]);
+    assert_eq!((decl.ty.as_str(), decl.name.as_str()), ("u64", "x"));
+    assert!(decl.init.is_some());
+    assert!(
+        matches!(&stmts[1], StmtKind::VarDecl(decl) if decl.init.is_none() && decl.modifiers.is_empty())
+    );
+    assert!(matches!(&stmts[2], StmtKind::Assign { op: None, .. }));
+    assert!(matches!(
+        &stmts[3],
+        StmtKind::Assign {
+            op: Some(BinaryOp::Add),
+            ..
+        }
+    ));
+    assert!(matches!(&stmts[4], StmtKind::Increment(_)));
+    assert!(matches!(&stmts[5], StmtKind::Decrement(_)));
+    assert!(matches!(&stmts[6], StmtKind::Expr(e) if matches!(e.kind, ExprKind::Call { .. })));
+    assert!(matches!(&stmts[7], StmtKind::Block(b) if b.stmts.len() == 1));
+    assert!(matches!(&stmts[8], StmtKind::Return(_)));
+}
+
+#[test]
+fn parse_compound_assignment_operators() {
+    let stmts = parse_body(
+        "x -= 1; x *= 1; x /= 1; x %= 1; x **= 1; x &= 1; x |= 1; x ^= 1; x <<= 1; x >>= 1;",
+    );
+    let ops: Vec<_> = stmts
+        .iter()
+        .map(|s| match s {
+            StmtKind::Assign { op: Some(op), .. } => *op,
+            other => panic!("Expected compound assignment, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        ops,
+        [
+            BinaryOp::Sub,
+            BinaryOp::Mul,
+            BinaryOp::Div,
+            BinaryOp::Rem,
+            BinaryOp::Pow,
+            BinaryOp::BitAnd,
+            BinaryOp::BitOr,
+            BinaryOp::BitXor,
+            BinaryOp::Shl,
+            BinaryOp::Shr,
+        ]
+    );
+}
+
+#[test]
+fn parse_statement_spans_include_semicolon() {
+    let src = "fn f() -> u32 { u8 y = 1; }";
+    let program = parse(src);
+    let body = &program.functions[0].body;
+    assert_eq!(body.span, Span { start: 14, end: 27 });
+    assert_eq!(body.stmts[0].span, Span { start: 16, end: 25 });
+}
+
+#[test]
+fn parse_statement_errors() {
+    assert_eq!(parse_body_err("static u8 x;"), "Unknown modifier 'static'");
+    assert_eq!(
+        parse_body_err("const const u8 x;"),
+        "Duplicate modifier 'const'"
+    );
+    assert_eq!(
+        parse_body_err("x = y++;"),
+        "`++` and `--` are statements and cannot be used inside an expression"
+    );
+    assert_eq!(
+        parse_body_err("u8 x = 1"),
+        "Expected Semicolon, found RBrace"
+    );
+}
+
+#[test]
+fn parse_if_else_chain() {
+    let stmts = parse_body("if (a) { x = 1; } else if (b) { x = 2; } else { x = 3; } if (c) {}");
+    assert_eq!(stmts.len(), 2);
+
+    let StmtKind::If {
+        cond, else_branch, ..
+    } = &stmts[0]
+    else {
+        panic!("Expected if statement, got {:?}", stmts[0]);
+    };
+    assert_eq!(sexpr(cond), "a");
+    let else_if = &else_branch.as_ref().expect("else branch").kind;
+    let StmtKind::If { else_branch, .. } = else_if else {
+        panic!("Expected else-if, got {else_if:?}");
+    };
+    assert!(matches!(
+        else_branch.as_ref().map(|s| &s.kind),
+        Some(StmtKind::Block(_))
+    ));
+    assert!(matches!(
+        &stmts[1],
+        StmtKind::If {
+            else_branch: None,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn parse_if_expressions() {
+    let render = |e: &Expr| match &e.kind {
+        ExprKind::If {
+            cond,
+            then_expr,
+            else_expr,
+        } => format!(
+            "(if {} {} {})",
+            sexpr(cond),
+            sexpr(then_expr),
+            sexpr(else_expr)
+        ),
+        _ => sexpr(e),
+    };
+    assert_eq!(render(&parse_expr("if (c) 5 else 10")), "(if c 5 10)");
+    assert_eq!(render(&parse_expr("5 if (c) else 10")), "(if c 5 10)");
+    // The else arm extends to the right; the postfix form binds loosest.
+    assert_eq!(
+        render(&parse_expr("if (c) 1 else 2 + 3")),
+        "(if c 1 (+ 2 3))"
+    );
+    assert_eq!(
+        render(&parse_expr("x + 1 if (a < b) else y")),
+        "(if (< a b) (+ x 1) y)"
+    );
+
+    let expr = parse_expr("5 if (c) else 10");
+    assert_eq!(expr.span, Span { start: 0, end: 16 });
+}
+
+#[test]
+fn parse_if_errors() {
+    assert_eq!(parse_body_err("if c { }"), "Expected LParen, found Ident");
+    assert_eq!(
+        parse_body_err("u8 x = if (c) 1;"),
+        "Expected Else, found Semicolon"
+    );
+    assert_eq!(
+        parse_body_err("u8 x = 1 if (c);"),
+        "Expected Else, found Semicolon"
+    );
+}
+
+#[test]
+fn parse_loop_forms() {
+    let stmts = parse_body(
+        "while (a) { break; } for (u32 i = 0; i < n; i++) { continue; } do { } while (a); loop { } for (;;) { }",
+    );
+    assert_eq!(stmts.len(), 5);
+
+    let kinds: Vec<&LoopKind> = stmts
+        .iter()
+        .map(|s| match s {
+            StmtKind::Loop(lp) => &lp.kind,
+            other => panic!("Expected loop, got {other:?}"),
+        })
+        .collect();
+    assert!(matches!(kinds[0], LoopKind::While(cond) if sexpr(cond) == "a"));
+    let LoopKind::For { init, cond, update } = kinds[1] else {
+        panic!("Expected for loop, got {:?}", kinds[1]);
+    };
+    assert!(matches!(
+        init.as_deref().map(|s| &s.kind),
+        Some(StmtKind::VarDecl(_))
+    ));
+    assert_eq!(sexpr(cond.as_ref().expect("condition")), "(< i n)");
+    assert!(matches!(
+        update.as_deref().map(|s| &s.kind),
+        Some(StmtKind::Increment(_))
+    ));
+    assert!(matches!(kinds[2], LoopKind::DoWhile(_)));
+    assert!(matches!(kinds[3], LoopKind::Infinite));
+    assert!(matches!(
+        kinds[4],
+        LoopKind::For {
+            init: None,
+            cond: None,
+            update: None
+        }
+    ));
+
+    let StmtKind::Loop(lp) = &stmts[0] else {
+        unreachable!()
+    };
+    assert!(matches!(lp.body.stmts[0].kind, StmtKind::Break { .. }));
+}
+
+#[test]
+fn parse_loop_spans_and_errors() {
+    let src = "fn f() -> u32 { do { } while (a); }";
+    let program = parse(src);
+    // The trailing `;` belongs to the do-while statement.
+    assert_eq!(
+        program.functions[0].body.stmts[0].span,
+        Span { start: 16, end: 33 }
+    );
+
+    assert_eq!(
+        parse_body_err("do { } while (a)"),
+        "Expected Semicolon, found RBrace"
+    );
+    assert_eq!(
+        parse_body_err("for (i = 0; i < 3) { }"),
+        "Expected Semicolon, found RParen"
+    );
+    assert_eq!(parse_body_err("loop x"), "Expected LBrace, found Ident");
+}
+
+#[test]
+fn parse_labeled_loops_and_break_values() {
+    let stmts = parse_body(
+        "outer: while (a) { for (;;) { continue outer; break outer; } } \
+         u8 x = while (c) { break 5; } break 0; \
+         search: u8 y = loop { while (d) { break search 7; } };",
+    );
+
+    let StmtKind::Loop(outer) = &stmts[0] else {
+        panic!("Expected loop, got {:?}", stmts[0]);
+    };
+    assert_eq!(outer.label.as_ref().map(|l| l.name.as_str()), Some("outer"));
+    let StmtKind::Loop(inner) = &outer.body.stmts[0].kind else {
+        panic!("Expected inner loop");
+    };
+    assert!(inner.label.is_none());
+    assert!(matches!(
+        &inner.body.stmts[0].kind,
+        StmtKind::Continue { label: Some(l) } if l.name == "outer"
+    ));
+    assert!(matches!(
+        &inner.body.stmts[1].kind,
+        StmtKind::Break { label: Some(l), value: None } if l.name == "outer"
+    ));
+
+    let StmtKind::VarDecl(decl) = &stmts[1] else {
+        panic!("Expected declaration, got {:?}", stmts[1]);
+    };
+    let Some(ExprKind::Loop(lp)) = decl.init.as_ref().map(|e| &e.kind) else {
+        panic!("Expected loop initializer, got {:?}", decl.init);
+    };
+    assert!(matches!(
+        &lp.body.stmts[0].kind,
+        StmtKind::Break { label: None, value: Some(v) } if sexpr(v) == "5"
+    ));
+    assert_eq!(sexpr(lp.default.as_deref().expect("default")), "0");
+
+    let StmtKind::VarDecl(decl) = &stmts[2] else {
+        panic!("Expected declaration, got {:?}", stmts[2]);
+    };
+    let Some(ExprKind::Loop(lp)) = decl.init.as_ref().map(|e| &e.kind) else {
+        panic!("Expected loop initializer, got {:?}", decl.init);
+    };
+    assert_eq!(lp.label.as_ref().map(|l| l.name.as_str()), Some("search"));
+    let StmtKind::Loop(inner) = &lp.body.stmts[0].kind else {
+        panic!("Expected inner loop");
+    };
+    // `search` is a label, so `7` is the value rather than `search`.
+    assert!(matches!(
+        &inner.body.stmts[0].kind,
+        StmtKind::Break { label: Some(l), value: Some(v) } if l.name == "search" && sexpr(v) == "7"
+    ));
+}
+
+#[test]
+fn parse_loop_else_blocks() {
+    let stmts = parse_body("while (a) { } else { x = 1; } for (;;) { } else { }");
+    for stmt in &stmts {
+        let StmtKind::Loop(lp) = stmt else {
+            panic!("Expected loop, got {stmt:?}");
+        };
+        assert!(lp.else_block.is_some());
+    }
+
+    assert_eq!(
+        parse_body_err("do { } while (a) else { };"),
+        "A `do` loop always runs its body, so it cannot have an `else` block"
+    );
+    assert_eq!(
+        parse_body_err("loop { } else { }"),
+        "A `loop` loop always runs its body, so it cannot have an `else` block"
+    );
+}
+
+#[test]
+fn parse_label_and_loop_value_errors() {
+    assert_eq!(
+        parse_body_err("while (a) { continue outer; }"),
+        "Unknown loop label 'outer'"
+    );
+    assert_eq!(
+        parse_body_err("outer: x = 1;"),
+        "Label 'outer' must be followed by a loop, or by a declaration initialized with one"
+    );
+    assert_eq!(
+        parse_body_err("outer: u8 x = f(loop { break 1; });"),
+        "Label 'outer' must be followed by a loop, or by a declaration initialized with one"
+    );
+    // A label is only visible inside its loop's body.
+    assert_eq!(
+        parse_body_err("outer: while (a) { } while (b) { continue outer; }"),
+        "Unknown loop label 'outer'"
+    );
+    assert_eq!(
+        parse_body_err("u8 x = while (c) { break 5; };"),
+        "A `while` loop used as a value needs a trailing `break <value>` for when it ends without a `break`"
+    );
+    assert_eq!(
+        parse_body_err("u8 x = loop { break 5; } break 0;"),
+        "A `loop` only ends through `break`, so it cannot have a default value"
+    );
+}
+
+#[test]
+fn parse_multiple_return_values() {
+    let src = "fn f(u32 x) -> u32 white, u32 black { return x, 2; } fn g() -> u8, bool { return a +? b; }";
+    let program = parse(src);
+
+    let returns: Vec<(&str, Option<&str>)> = program.functions[0]
+        .returns
+        .iter()
+        .map(|r| (r.ty.as_str(), r.name.as_deref()))
+        .collect();
+    assert_eq!(returns, [("u32", Some("white")), ("u32", Some("black"))]);
+    assert_eq!(
+        program.functions[0].returns[1].span,
+        Span { start: 26, end: 35 }
+    );
+    let StmtKind::Return(values) = &program.functions[0].body.stmts[0].kind else {
+        panic!("Expected return statement");
+    };
+    let values: Vec<String> = values.iter().map(sexpr).collect();
+    assert_eq!(values, ["x", "2"]);
+
+    assert_eq!(program.functions[1].returns.len(), 2);
+    assert!(
+        program.functions[1]
+            .returns
+            .iter()
+            .all(|r| r.name.is_none())
+    );
+
+    let err = parse_err("fn f() -> u8 a, bool { }");
+    assert_eq!(
+        err.message,
+        "Either all return values are named or none are"
+    );
+    assert_eq!(err.span, Span { start: 16, end: 20 });
+}
+
+#[test]
+fn parse_destructuring_declarations() {
+    let stmts = parse_body("u8 result, bool overflow = a +? b; const u32 w, u32 k = f();");
+    let StmtKind::Destructure(decl) = &stmts[0] else {
+        panic!("Expected destructuring declaration, got {:?}", stmts[0]);
+    };
+    let bindings: Vec<(&str, &str)> = decl
+        .bindings
+        .iter()
+        .map(|b| (b.ty.as_str(), b.name.as_str()))
+        .collect();
+    assert_eq!(bindings, [("u8", "result"), ("bool", "overflow")]);
+    assert_eq!(sexpr(&decl.init), "(+? a b)");
+
+    let StmtKind::Destructure(decl) = &stmts[1] else {
+        panic!("Expected destructuring declaration, got {:?}", stmts[1]);
+    };
+    assert_eq!(decl.modifiers, [Modifier::Const]);
+    assert_eq!(decl.bindings.len(), 2);
+
+    assert_eq!(
+        parse_body_err("u8 a, bool b;"),
+        "Expected Eq, found Semicolon"
+    );
+}
+
+#[test]
+fn parse_default_parameters_and_named_arguments() {
+    let src = "fn foo(u32 x, u32 y = 10 + 1) -> u32 { return foo(y = 12, x = a == b); }";
+    let program = parse(src);
+
+    let params = &program.functions[0].params;
+    assert!(params[0].default.is_none());
+    assert_eq!(
+        sexpr(params[1].default.as_ref().expect("default")),
+        "(+ 10 1)"
+    );
+    assert_eq!(params[1].span, Span { start: 14, end: 19 });
+
+    let StmtKind::Return(values) = &program.functions[0].body.stmts[0].kind else {
+        panic!("Expected return statement");
+    };
+    assert_eq!(sexpr(&values[0]), "(call foo y=12 x=(== a b))");
+    let ExprKind::Call { args, .. } = &values[0].kind else {
+        unreachable!()
+    };
+    assert_eq!(args[0].span, Span { start: 50, end: 56 });
+}
+
+fn parse_all(src: &str) -> (Program, Vec<ParseError>) {
+    let tokens = lex(src).expect("lexing should succeed");
+    Parser::new(&tokens).parse_program()
+}
+
+#[test]
+fn parse_recovers_at_statement_boundaries() {
+    let (program, errors) = parse_all(
+        "fn f() -> u32 { u32 a = ; a = 1 a = 2; if (a) { b = ) ; } return a; }\n\
+         fn g() -> u32 { return 1; }",
+    );
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Expected one of [Int, Float, Str, ByteStr, Char, Ident, LParen], found Semicolon",
+            "Expected Semicolon, found Ident",
+            "Expected one of [Int, Float, Str, ByteStr, Char, Ident, LParen], found RParen",
+        ]
+    );
+
+    // Both functions are kept, with an error node for each broken statement.
+    assert_eq!(program.functions.len(), 2);
+    let stmts = &program.functions[0].body.stmts;
+    assert!(matches!(stmts[0].kind, StmtKind::Error));
+    assert_eq!(stmts[0].span, Span { start: 16, end: 25 });
+    assert!(matches!(stmts[1].kind, StmtKind::Error));
+    let StmtKind::If { then_block, .. } = &stmts[2].kind else {
+        panic!("Expected if statement, got {:?}", stmts[2]);
+    };
+    assert!(matches!(then_block.stmts[0].kind, StmtKind::Error));
+    assert!(matches!(stmts[3].kind, StmtKind::Return(_)));
+}
+
+#[test]
+fn parse_recovers_at_closing_brace_and_next_function() {
+    // The error node stops before the `}` closing the block.
+    let (program, errors) = parse_all("fn f() -> u32 { while (a) { x = } return 1; }");
+    assert_eq!(errors.len(), 1);
+    let StmtKind::Loop(lp) = &program.functions[0].body.stmts[0].kind else {
+        panic!("Expected loop");
+    };
+    assert!(matches!(lp.body.stmts[0].kind, StmtKind::Error));
+    assert!(matches!(
+        program.functions[0].body.stmts[1].kind,
+        StmtKind::Return(_)
+    ));
+
+    // An unclosed body ends at the next `fn`.
+    let (program, errors) = parse_all("fn f() -> u32 { return 1;\nfn g() -> u32 { return 2; }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expected RBrace, found Fn");
+    assert_eq!(program.functions.len(), 2);
+
+    // Stray tokens between functions are skipped.
+    let (program, errors) =
+        parse_all("fn f() -> u32 { return 1; } 42 ; fn g() -> u32 { return 2; }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expected Fn, found Int");
+    assert_eq!(program.functions.len(), 2);
+
+    // A function that breaks off before its name is dropped.
+    let (program, errors) =
+        parse_all("fn (u32 x) -> u32 { return 1; } fn g() -> u32 { return 2; }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expected Ident, found LParen");
+    assert_eq!(program.functions.len(), 1);
+    assert_eq!(program.functions[0].name, "g");
+}
+
+#[test]
+fn parse_recovers_inside_items() {
+    // A broken field is skipped through its `;`, keeping the struct.
+    let (program, errors) =
+        parse_all("struct S { u32 a; u8 = 1; u8 b c; u16 d; } fn f() -> u32 { return 1; }");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Expected Ident, found Eq",
+            "Expected Semicolon, found Ident"
+        ]
+    );
+    let fields: Vec<&str> = program.structs[0]
+        .fields
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+    assert_eq!(fields, ["a", "d"]);
+    assert_eq!(program.functions.len(), 1);
+
+    // A broken variant is skipped through its `,`, brackets included.
+    let (program, errors) = parse_all("enum E { A(u8 x, u16), B C, D(u8) } struct T { u8 a; }");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Expected RParen, found Ident",
+            "Expected RBrace, found Ident"
+        ]
+    );
+    let variants: Vec<&str> = program.enums[0]
+        .variants
+        .iter()
+        .map(|v| v.name.as_str())
+        .collect();
+    assert_eq!(variants, ["D"]);
+    assert_eq!(program.structs.len(), 1);
+
+    // A body missing its `}` is reported once, and ends at the next item.
+    let (program, errors) = parse_all(
+        "struct S { u8 a;
+enum E { A, 5
+fn f() -> u32 { return 1; }",
+    );
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["Expected RBrace, found Enum", "Expected Ident, found Int"]
+    );
+    assert_eq!(program.structs[0].fields.len(), 1);
+    assert_eq!(program.enums[0].variants.len(), 1);
+    assert_eq!(program.functions.len(), 1);
+}
+
+#[test]
+fn parse_recovers_in_function_headers() {
+    // A broken parameter is skipped through its `,`, keeping the function.
+    let (program, errors) =
+        parse_all("fn f(u32, u8 b = 1 +, u16 c) -> u32 { return c; } fn g() -> u32 { return 2; }");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Expected Ident, found Comma",
+            "Expected one of [Int, Float, Str, ByteStr, Char, Ident, LParen], found Comma",
+        ]
+    );
+    assert_eq!(program.functions.len(), 2);
+    let params: Vec<&str> = program.functions[0]
+        .params
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(params, ["c"]);
+    assert!(matches!(
+        program.functions[0].body.stmts[0].kind,
+        StmtKind::Return(_)
+    ));
+
+    // An unclosed parameter list stops at the `->`, and the body still parses.
+    let (program, errors) = parse_all("fn f(u32 a, u8 b -> u32 { return a; }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expected RParen, found Arrow");
+    assert_eq!(program.functions[0].params.len(), 2);
+    assert_eq!(program.functions[0].returns[0].ty, "u32");
+    assert!(matches!(
+        program.functions[0].body.stmts[0].kind,
+        StmtKind::Return(_)
+    ));
+
+    // Broken return types skip to the body.
+    let (program, errors) = parse_all("fn f() -> 5 u32 { return 1; }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expected Ident, found Int");
+    assert!(matches!(
+        program.functions[0].body.stmts[0].kind,
+        StmtKind::Return(_)
+    ));
+
+    // A function without a body gets an error node for one, through its `;`.
+    let (program, errors) = parse_all(
+        "fn f(u32 a) -> u32;
+fn g() -> u32 { return 2; }",
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expected LBrace, found Semicolon");
+    assert_eq!(program.functions.len(), 2);
+    assert_eq!(program.functions[0].params[0].name, "a");
+    let body = &program.functions[0].body;
+    assert!(matches!(body.stmts[0].kind, StmtKind::Error));
+    assert_eq!(body.span, Span { start: 18, end: 19 });
+}
+
+#[test]
+fn parse_struct_declarations() {
+    let src =
+        "#[packed] struct Point { u32 x; u8 y; }\nstruct Empty {}\nfn f() -> u32 { return 1; }";
+    let program = parse(src);
+
+    assert_eq!(program.structs.len(), 2);
+    assert_eq!(program.functions.len(), 1);
+    let point = &program.structs[0];
+    assert_eq!(point.name, "Point");
+    assert_eq!(point.span, Span { start: 17, end: 22 });
+    assert_eq!(point.attrs.len(), 1);
+    assert_eq!(point.attrs[0].name, "packed");
+    assert_eq!(point.attrs[0].span, Span { start: 0, end: 9 });
+    let fields: Vec<(&str, &str)> = point
+        .fields
+        .iter()
+        .map(|f| (f.ty.as_str(), f.name.as_str()))
+        .collect();
+    assert_eq!(fields, [("u32", "x"), ("u8", "y")]);
+    assert_eq!(point.fields[1].span, Span { start: 32, end: 36 });
+    assert!(program.structs[1].attrs.is_empty());
+    assert!(program.structs[1].fields.is_empty());
+
+    let (_, errors) = parse_all("struct P { u32 x, u8 y; }");
+    assert_eq!(errors[0].message, "Expected Semicolon, found Comma");
+    let (_, errors) = parse_all("#[packed fn f() -> u32 { return 1; }");
+    assert_eq!(errors[0].message, "Expected RBracket, found Fn");
+}
+
+#[test]
+fn parse_struct_literals_and_field_access() {
+    for (src, expected) in [
+        ("Point { x = 1, y = a + 2 }", "(Point x=1 y=(+ a 2))"),
+        ("Point { y = 2, }", "(Point y=2)"),
+        ("Empty {}", "(Empty)"),
+        ("a.b.c", "(. (. a b) c)"),
+        ("f(x).y + p.x * 2", "(+ (. (call f x) y) (* (. p x) 2))"),
+        ("-p.x", "(- (. p x))"),
+        ("Point { x = 1 }.x", "(. (Point x=1) x)"),
+    ] {
+        assert_eq!(sexpr(&parse_expr(src)), expected, "{src}");
+    }
+
+    let stmts = parse_body("p.x = 1; p.inner.y += 2; p.x++;");
+    let StmtKind::Assign { target, op, .. } = &stmts[0] else {
+        panic!("Expected assignment, got {:?}", stmts[0]);
+    };
+    assert_eq!(sexpr(target), "(. p x)");
+    assert!(op.is_none());
+    let StmtKind::Assign { target, op, .. } = &stmts[1] else {
+        panic!("Expected assignment, got {:?}", stmts[1]);
+    };
+    assert_eq!(sexpr(target), "(. (. p inner) y)");
+    assert_eq!(*op, Some(BinaryOp::Add));
+    assert!(matches!(&stmts[2], StmtKind::Increment(target) if sexpr(target) == "(. p x)"));
+
+    let expr = parse_expr("p.x");
+    let ExprKind::Field { name_span, .. } = &expr.kind else {
+        panic!("Expected field access, got {expr:?}");
+    };
+    assert_eq!(*name_span, Span { start: 2, end: 3 });
+    assert_eq!(expr.span, Span { start: 0, end: 3 });
+}
+
+#[test]
+fn parse_enum_declarations() {
+    let src = "enum Shape { Circle(f64), Rect(f32, f32,), Empty, }\nenum Never {}\n\
+               fn f() -> u32 { return 1; }";
+    let program = parse(src);
+
+    assert_eq!(program.enums.len(), 2);
+    assert_eq!(program.functions.len(), 1);
+    let shape = &program.enums[0];
+    assert_eq!(shape.name, "Shape");
+    assert_eq!(shape.span, Span { start: 5, end: 10 });
+    let variants: Vec<(&str, Vec<&str>)> = shape
+        .variants
+        .iter()
+        .map(|v| {
+            (
+                v.name.as_str(),
+                v.fields.iter().map(|(ty, _)| ty.as_str()).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        variants,
+        [
+            ("Circle", vec!["f64"]),
+            ("Rect", vec!["f32", "f32"]),
+            ("Empty", vec![]),
+        ]
+    );
+    assert_eq!(shape.variants[1].span, Span { start: 26, end: 30 });
+    assert_eq!(shape.variants[1].fields[1].1, Span { start: 36, end: 39 });
+    assert!(program.enums[1].variants.is_empty());
+
+    let (_, errors) = parse_all("enum E { A B }");
+    assert_eq!(errors[0].message, "Expected RBrace, found Ident");
+    let (_, errors) = parse_all("enum E { A(u8 x) }");
+    assert_eq!(errors[0].message, "Expected RParen, found Ident");
+}
+
+#[test]
+fn parse_match_arms_and_patterns() {
+    for (src, expected) in [
+        (
+            "match (s) { Shape.Circle(r) => r, Shape.Rect(w, _) => w * 2.0, _ => 0.0 }",
+            "(match s [Shape.Circle(r) => r] [Shape.Rect(w, _) => (* w 2.0)] [_ => 0.0])",
+        ),
+        (
+            "match (n + 1) { 0 => a, -1 => b, 'x' => c, other => other, }",
+            "(match (+ n 1) [0 => a] [-1 => b] ['x' => c] [other => other])",
+        ),
+        (
+            "match (e) { E.A(E.B, E.C(x, -2)) => 1, E.D() => 2 }",
+            "(match e [E.A(E.B, E.C(x, -2)) => 1] [E.D => 2])",
+        ),
+        ("match (x) {}", "(match x)"),
+        ("1 + match (x) { _ => 2 }", "(+ 1 (match x [_ => 2]))"),
+    ] {
+        assert_eq!(sexpr(&parse_expr(src)), expected, "{src}");
     }
+
+    // As a statement, arms can be blocks, which need no `,` after them.
+    let stmts =
+        parse_body("match (s) { Shape.Empty => { return 1; } _ => f(), Shape.Circle(r) => {} }");
+    let StmtKind::Match(m) = &stmts[0] else {
+        panic!("Expected match statement, got {:?}", stmts[0]);
+    };
+    assert_eq!(
+        sexpr_match(m),
+        "(match s [Shape.Empty => {..}] [_ => (call f)] [Shape.Circle(r) => {..}])"
+    );
+    let ArmBody::Block(block) = &m.arms[0].body else {
+        panic!("Expected block arm");
+    };
+    assert!(matches!(block.stmts[0].kind, StmtKind::Return(_)));
+
+    let expr = parse_expr("match (s) { Shape.Rect(w, h) => w }");
+    let ExprKind::Match(m) = &expr.kind else {
+        panic!("Expected match, got {expr:?}");
+    };
+    assert_eq!(expr.span, Span { start: 0, end: 35 });
+    assert_eq!(m.arms[0].pattern.span, Span { start: 12, end: 28 });
+
+    assert_eq!(
+        parse_body_err("u32 x = match (s) { _ => { 1 } };"),
+        "A `match` used as a value needs an expression as each arm's body, not a block"
+    );
+    assert_eq!(
+        parse_body_err("match (s) { _ => 1 _ => 2 }"),
+        "Expected RBrace, found Ident"
+    );
+    assert_eq!(
+        parse_body_err("match (s) { + => 1 }"),
+        "Expected one of [Ident, Int, Minus, Char], found Plus"
+    );
+    assert_eq!(
+        parse_body_err("match (s) { x -> 1 }"),
+        "Expected FatArrow, found Arrow"
+    );
+}
+
+#[test]
+fn parse_imports_and_visibility() {
+    let src = "import geo.shapes as s;\nimport util;\n\
+               pub fn f() -> u32 { return 1; }\nfn g() -> u32 { return 2; }\n\
+               #[packed] pub struct P { u8 a; }\npub enum E { A }";
+    let program = parse(src);
+
+    let imports: Vec<(Vec<&str>, Option<&str>, &str)> = program
+        .imports
+        .iter()
+        .map(|i| {
+            (
+                i.path.iter().map(String::as_str).collect(),
+                i.alias.as_deref(),
+                i.binding(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        imports,
+        [
+            (vec!["geo", "shapes"], Some("s"), "s"),
+            (vec!["util"], None, "util"),
+        ]
+    );
+    assert_eq!(program.imports[0].path_span, Span { start: 7, end: 17 });
+    let public: Vec<bool> = program.functions.iter().map(|f| f.public).collect();
+    assert_eq!(public, [true, false]);
+    assert!(program.structs[0].public);
+    assert_eq!(program.structs[0].attrs[0].name, "packed");
+    assert!(program.enums[0].public);
+
+    let (_, errors) = parse_all("import a.;");
+    assert_eq!(errors[0].message, "Expected Ident, found Semicolon");
+    let (_, errors) = parse_all("import a as b.c;");
+    assert_eq!(errors[0].message, "Expected Semicolon, found Period");
+}
+
+#[test]
+fn parse_module_qualified_names() {
+    let src = "fn f(geo.Point p) -> geo.Point m, u32 n {\n\
+               geo.Point q = geo.Point { x = 1 };\n\
+               const geo.Shape s, u32 k = g();\n\
+               match (s) { geo.Shape.Dot => {} Shape.Circle(r) => {} }\n}";
+    let function = &parse(src).functions[0];
+    assert_eq!(function.params[0].ty, "geo.Point");
+    assert_eq!(function.params[0].ty_span, Span { start: 5, end: 14 });
+    assert_eq!(function.returns[0].ty, "geo.Point");
+
+    let stmts: Vec<&StmtKind> = function.body.stmts.iter().map(|s| &s.kind).collect();
+    let StmtKind::VarDecl(decl) = stmts[0] else {
+        panic!("Expected declaration, got {:?}", stmts[0]);
+    };
+    assert_eq!((decl.ty.as_str(), decl.name.as_str()), ("geo.Point", "q"));
+    assert_eq!(sexpr(decl.init.as_ref().unwrap()), "(geo.Point x=1)");
+    let StmtKind::Destructure(decl) = stmts[1] else {
+        panic!("Expected destructuring declaration, got {:?}", stmts[1]);
+    };
+    assert_eq!(decl.modifiers, [Modifier::Const]);
+    assert_eq!(decl.bindings[0].ty, "geo.Shape");
+    let StmtKind::Match(m) = stmts[2] else {
+        panic!("Expected match, got {:?}", stmts[2]);
+    };
+    assert_eq!(
+        sexpr_match(m),
+        "(match s [geo.Shape.Dot => {..}] [Shape.Circle(r) => {..}])"
+    );
+
+    assert_eq!(
+        sexpr(&parse_expr("geo.Shape.Circle(1) + geo.area(p)")),
+        "(+ (call (. (. geo Shape) Circle) 1) (call (. geo area) p))"
+    );
+    assert_eq!(
+        parse_body_err("u32 a.b = 1;"),
+        "Expected a variable name, found 'a.b'"
+    );
+}
+
+#[test]
+fn parse_entry_functions() {
+    let src = "entry fn main() { }\n\
+               #[entry] pub fn start() -> u8 { return 0; }\n\
+               fn f() -> u32 { return 1; }";
+    let program = parse(src);
+    let main = &program.functions[0];
+    assert!(main.entry && main.is_entry());
+    assert!(main.returns.is_empty());
+    let start = &program.functions[1];
+    assert!(!start.entry && start.is_entry() && start.public);
+    assert_eq!(start.attrs[0].name, "entry");
+    assert_eq!(start.attrs[0].span, Span { start: 20, end: 28 });
+    assert!(!program.functions[2].is_entry());
+
+    // Only an entry function may leave out its return types.
+    let (_, errors) = parse_all("fn main() { }");
+    assert_eq!(errors[0].message, "Expected Arrow, found LBrace");
+    let (_, errors) = parse_all("entry struct S { }");
+    assert_eq!(errors[0].message, "Expected Fn, found Struct");
+}
+
+/// `name`, `name = lit` or `name(..)`, with nested items written the same way.
+fn sexpr_attr_args(name: &str, args: &AttrArgs) -> String {
+    let lit = |kind: &AttrLitKind| match kind {
+        AttrLitKind::Int(lit) => lit.value.to_string(),
+        AttrLitKind::Str(s) => format!("{s:?}"),
+    };
+    match args {
+        AttrArgs::None => name.to_string(),
+        AttrArgs::Value(value) => format!("{name} = {}", lit(&value.kind)),
+        AttrArgs::List(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| match item {
+                    MetaItem::Meta { name, args, .. } => sexpr_attr_args(name, args),
+                    MetaItem::Lit(value) => lit(&value.kind),
+                })
+                .collect();
+            format!("{name}({})", items.join(", "))
+        }
+    }
+}
+
+#[test]
+fn parse_attribute_grammar() {
+    let src = "#[doc = \"hi\"] #[cfg(any(unix, target.os = \"windows\"), 8,)]\n\
+               struct S { #[align(8)] u32 x; }\n\
+               enum E { #[default] A, B }\n\
+               fn f() -> u32 { #[likely] if (x) { #[a.b] return 1; } return 0; }";
+    let program = parse(src);
+
+    let attrs: Vec<String> = program.structs[0]
+        .attrs
+        .iter()
+        .map(|attr| sexpr_attr_args(&attr.name, &attr.args))
+        .collect();
+    assert_eq!(
+        attrs,
+        ["doc = \"hi\"", "cfg(any(unix, target.os = \"windows\"), 8)"]
+    );
+    assert_eq!(
+        program.structs[0].attrs[1].name_span,
+        Span { start: 16, end: 19 }
+    );
+    let AttrArgs::List(items) = &program.structs[0].attrs[1].args else {
+        panic!("Expected a list");
+    };
+    let MetaItem::Meta { span, .. } = &items[0] else {
+        panic!("Expected a nested path");
+    };
+    assert_eq!(
+        &src[span.start..span.end],
+        "any(unix, target.os = \"windows\")"
+    );
+
+    assert_eq!(program.structs[0].fields[0].attrs[0].name, "align");
+    assert_eq!(program.enums[0].variants[0].attrs[0].name, "default");
+    assert!(program.enums[0].variants[1].attrs.is_empty());
+    let body = &program.functions[0].body;
+    assert_eq!(body.stmts[0].attrs[0].name, "likely");
+    let StmtKind::If { then_block, .. } = &body.stmts[0].kind else {
+        panic!("Expected if, got {:?}", body.stmts[0].kind);
+    };
+    assert_eq!(then_block.stmts[0].attrs[0].name, "a.b");
+    assert!(body.stmts[1].attrs.is_empty());
+
+    let (_, errors) = parse_all("#[cfg(a b)] fn f() -> u32 { return 1; }");
+    assert_eq!(errors[0].message, "Expected RParen, found Ident");
+    let (_, errors) = parse_all("#[doc = x] fn f() -> u32 { return 1; }");
+    assert_eq!(errors[0].message, "Expected one of [Int, Str], found Ident");
+    let (_, errors) = parse_all("fn f() -> u32 { #[a]; return 1; }");
+    assert_eq!(
+        errors[0].message,
+        "Expected a statement after the attribute, found ';'"
+    );
+    // An attribute of the next item still ends an unclosed body.
+    let (program, errors) = parse_all("fn f() -> u32 { return 1;\n#[packed] struct S { u8 a; }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Expected RBrace, found Hash");
+    assert_eq!(program.structs[0].attrs[0].name, "packed");
 }
@@ -0,0 +1,158 @@
+mod common;
+
+use xenonc::ast::{Expr, ExprKind, StmtKind};
+use xenonc::resolve::resolve_calls;
+use xenonc::tokens::Span;
+
+use common::parse;
+
+/// Resolves `src` and renders the arguments of the call returned by the
+/// last function, e.g. `[5, 10]`.
+fn resolved_args(src: &str) -> Result<Vec<String>, String> {
+    let mut program = parse(src);
+    resolve_calls(&mut program).map_err(|e| e.message)?;
+    let body = &program.functions.last().expect("a function").body;
+    let Some(StmtKind::Return(values)) = body.stmts.last().map(|s| &s.kind) else {
+        panic!("Expected a return statement");
+    };
+    let ExprKind::Call { args, .. } = &values[0].kind else {
+        panic!("Expected a call, got {:?}", values[0]);
+    };
+    Ok(args
+        .iter()
+        .map(|arg| {
+            assert!(arg.name.is_none(), "resolved arguments are positional");
+            render(&arg.value)
+        })
+        .collect())
+}
+
+fn render(e: &Expr) -> String {
+    match &e.kind {
+        ExprKind::Int(lit) => lit.value.to_string(),
+        ExprKind::Ident(name) => name.clone(),
+        ExprKind::Call { callee, args, .. } => {
+            let args: Vec<String> = args.iter().map(|a| render(&a.value)).collect();
+            format!("{}({})", render(callee), args.join(", "))
+        }
+        other => format!("{other:?}"),
+    }
+}
+
+const FOO: &str = "fn foo(u32 x, u32 y = 10) -> u32 { return x + y; }\n";
+
+#[test]
+fn resolve_fills_in_defaults_and_reorders_named_arguments() {
+    for (call, expected) in [
+        ("foo(5)", ["5", "10"]),
+        ("foo(5, 20)", ["5", "20"]),
+        ("foo(y = 12, x = 5)", ["5", "12"]),
+        ("foo(5, y = 12)", ["5", "12"]),
+        ("foo(x = 5)", ["5", "10"]),
+    ] {
+        let src = format!("{FOO}fn f() -> u32 {{ return {call}; }}");
+        assert_eq!(resolved_args(&src).expect(call), expected, "{call}");
+    }
+}
+
+#[test]
+fn resolve_defaults_may_call_functions_with_defaults() {
+    let src = format!(
+        "{FOO}fn bar(u32 a = foo(1)) -> u32 {{ return a; }}\nfn f() -> u32 {{ return bar(); }}"
+    );
+    assert_eq!(resolved_args(&src).expect("resolves"), ["foo(1, 10)"]);
+
+    let src = "fn f(u32 a = f()) -> u32 { return a; }\nfn g() -> u32 { return f(); }";
+    assert_eq!(
+        resolved_args(src).expect_err("should fail"),
+        "the default value of parameter 'a' of function 'f' depends on itself"
+    );
+}
+
+#[test]
+fn resolve_rejects_defaults_that_use_variables() {
+    // `x` would otherwise bind to whatever `x` is at the call.
+    let src = "fn f(u32 x, u32 y = x) -> u32 { return y; }\n\
+               fn g() -> u32 { u32 x = 7; return f(1); }";
+    let mut program = parse(src);
+    let err = resolve_calls(&mut program).expect_err("should fail");
+    assert_eq!(
+        err.message,
+        "the default value of parameter 'y' of function 'f' cannot use 'x': defaults are evaluated at each call, where the function's parameters and locals are not in scope"
+    );
+    assert_eq!(err.span, Span { start: 20, end: 21 });
+
+    for default in [
+        "p.x",
+        "1 + loop { u32 i = 0; break n; }",
+        "match (1) { n => k }",
+    ] {
+        let mut program = parse(&format!("fn f(u32 a = {default}) -> u32 {{ return a; }}"));
+        resolve_calls(&mut program).expect_err(default);
+    }
+
+    // Variables the default declares itself, callees and enums are fine.
+    let src = "enum Color { Red, Green }\n\
+               fn pick(Color c) -> u32 { return 1; }\n\
+               fn f(u32 a = loop { u32 i = 1; break i + pick(Color.Red); }, \
+                    u32 b = match (2) { n => n }) -> u32 { return a; }\n\
+               fn g() -> u32 { return f(); }";
+    assert_eq!(resolved_args(src).expect("resolves").len(), 2);
+}
+
+#[test]
+fn resolve_reports_bad_calls() {
+    for (call, message, span) in [
+        (
+            "foo(z = 1, x = 2)",
+            "function 'foo' has no parameter named 'z'",
+            (78, 83),
+        ),
+        (
+            "foo(1, x = 2)",
+            "argument 'x' is supplied more than once",
+            (81, 86),
+        ),
+        (
+            "foo(y = 2)",
+            "missing argument for parameter 'x' of function 'foo'",
+            (74, 84),
+        ),
+        (
+            "foo(y = 2, 1)",
+            "positional arguments must come before named arguments",
+            (85, 86),
+        ),
+    ] {
+        let mut program = parse(&format!("{FOO}fn f() -> u32 {{ return {call}; }}"));
+        let err = resolve_calls(&mut program).expect_err(call);
+        assert_eq!(err.message, message, "{call}");
+        assert_eq!(
+            err.span,
+            Span {
+                start: span.0,
+                end: span.1
+            },
+            "{call}"
+        );
+    }
+}
+
+#[test]
+fn resolve_picks_the_only_overload_that_takes_the_arguments() {
+    let overloads = "fn g(u32 x, u32 y = 1) -> u32 { return x; }\n\
+                     fn g(u64 z) -> u64 { return z; }\n";
+    let src = format!("{overloads}fn f() -> u32 {{ return g(y = 2, x = 3); }}");
+    assert_eq!(resolved_args(&src).expect("resolves"), ["3", "2"]);
+
+    // Both overloads take one positional argument, so semantic analysis picks by type.
+    let mut program = parse(&format!("{overloads}fn f() -> u32 {{ return g(3); }}"));
+    resolve_calls(&mut program).expect("resolves");
+
+    let mut program = parse(&format!("{overloads}fn f() -> u32 {{ return g(w = 3); }}"));
+    let err = resolve_calls(&mut program).expect_err("should fail");
+    assert_eq!(
+        err.message,
+        "no overload of function 'g' takes these arguments"
+    );
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use xenonc::ast::{ExprKind, Program, StmtKind};
+use xenonc::error::{SemaResult, SemaWarning};
+use xenonc::resolve::resolve_calls;
+use xenonc::sema::check_program;
+use xenonc::tokens::Span;
+
+use common::parse;
+
+/// Resolves and checks `src` like `xenonc check` does, giving the checked
+/// program and the warnings raised.
+fn check(src: &str) -> SemaResult<(Program, Vec<SemaWarning>)> {
+    let mut program = parse(src);
+    resolve_calls(&mut program).unwrap_or_else(|e| panic!("{src}: {}", e.message));
+    let warnings = check_program(&mut program)?;
+    Ok((program, warnings))
+}
+
+const FOO_OVERLOADS: &str = "fn foo(u32 x) -> u32 { return x; }\n\
+                             fn foo(u64 x) -> u64 { return x; }\n\
+                             fn foo(u32 x, u32 y = 1) -> u32 { return x + y; }\n\
+                             fn foo(u32 x) -> u64 { return 0; }\n";
+
+#[test]
+fn sema_records_the_overload_each_call_picks() {
+    let (program, _) = check(&format!(
+        "{FOO_OVERLOADS}fn f(u32 a) -> u64 {{ u32 x = foo(a, y = 2); return foo(a); }}"
+    ))
+    .expect("checks");
+    let body = &program.functions.last().expect("a function").body;
+    let StmtKind::VarDecl(decl) = &body.stmts[0].kind else {
+        panic!("Expected a declaration");
+    };
+    let Some(ExprKind::Call { args, overload, .. }) = decl.init.as_ref().map(|e| &e.kind) else {
+        panic!("Expected a call");
+    };
+    assert_eq!(*overload, Some(2));
+    assert!(args.iter().all(|arg| arg.name.is_none()));
+    let StmtKind::Return(values) = &body.stmts[1].kind else {
+        panic!("Expected a return");
+    };
+    let ExprKind::Call { overload, .. } = &values[0].kind else {
+        panic!("Expected a call");
+    };
+    assert_eq!(*overload, Some(3), "picked by the expected return type");
+}
+
+#[test]
+fn sema_reports_calls_no_single_overload_fits() {
+    let src = format!("{FOO_OVERLOADS}fn f() -> u32 {{ foo(5); return 0; }}");
+    let err = check(&src).expect_err("should fail");
+    assert_eq!(
+        err.message,
+        "call to function 'foo' is ambiguous between `fn foo(u32 x) -> u32`, `fn foo(u64 x) -> u64`, \
+         `fn foo(u32 x, u32 y = ..) -> u32` and `fn foo(u32 x) -> u64`; give the arguments explicit types"
+    );
+    let start = src.find("foo(5)").expect("the call");
+    assert_eq!(
+        err.span,
+        Span {
+            start,
+            end: start + 6
+        }
+    );
+
+    let err = check(&format!(
+        "{FOO_OVERLOADS}fn f(u8 a) -> u32 {{ return foo(a); }}"
+    ))
+    .expect_err("should fail");
+    assert_eq!(
+        err.message,
+        "no overload of function 'foo' matches the argument types; candidates are \
+         `fn foo(u32 x) -> u32`, `fn foo(u64 x) -> u64`, `fn foo(u32 x, u32 y = ..) -> u32` \
+         and `fn foo(u32 x) -> u64`"
+    );
+
+    let err = check("fn f() -> u32 { return g(); }").expect_err("should fail");
+    assert_eq!(err.message, "cannot find function 'g' in this scope");
+}
+
+#[test]
+fn sema_leaves_other_mistakes_to_codegen() {
+    // The mismatched literal stops checking `f` before the ambiguous call.
+    check(&format!(
+        "{FOO_OVERLOADS}fn f() -> u32 {{ u32 x = 1.5; foo(5); return 0; }}"
+    ))
+    .expect("nothing for sema to report");
+    check("fn f() -> num { return g(); }").expect("unknown types are not checked");
+}
+
+const SHAPE: &str = "enum Shape { Circle(f64), Rect(f32, f32, u8), Empty }\n";
+
+#[test]
+fn sema_match_exhaustiveness() {
+    for (src, message) in [
+        (
+            "fn f(Shape s) -> i32 { return match (s) { Shape.Circle(r) => 1, Shape.Empty => 2 }; }",
+            "non-exhaustive match on Shape: `Shape.Rect(_, _, _)` not covered",
+        ),
+        (
+            "fn f(Shape s) -> i32 { match (s) {} return 0; }",
+            "non-exhaustive match on Shape: `Shape.Circle(_)`, `Shape.Rect(_, _, _)` and `Shape.Empty` not covered",
+        ),
+        (
+            "enum Maybe { Some(Shape), None }\n\
+             fn f(Maybe m) -> i32 { return match (m) { Maybe.Some(Shape.Circle(_)) => 1, Maybe.None => 2 }; }",
+            "non-exhaustive match on Maybe: `Maybe.Some(Shape.Rect(_, _, _))` not covered",
+        ),
+        (
+            "enum Pair { Two(Shape, Shape) }\n\
+             fn f(Pair p) -> i32 { return match (p) { Pair.Two(Shape.Empty, _) => 1, Pair.Two(_, Shape.Empty) => 2 }; }",
+            "non-exhaustive match on Pair: `Pair.Two(Shape.Circle(_), Shape.Circle(_))` not covered",
+        ),
+        (
+            "fn f(i32 n) -> i32 { return match (n) { 0 => 1, 1 => 2 }; }",
+            "non-exhaustive match on i32: `_` not covered",
+        ),
+    ] {
+        let err = check(&format!("{SHAPE}{src}")).expect_err(src);
+        assert_eq!(err.message, message, "{src}");
+    }
+
+    // An empty enum has no values, so no arms cover them all.
+    check("enum Never {}\nfn f(Never n) -> i32 { match (n) {} return 0; }").expect("checks");
+
+    let src = format!(
+        "{SHAPE}fn f(Shape s, i32 n) -> i32 {{\n\
+             i32 a = match (s) {{ _ => 1, Shape.Empty => 2 }};\n\
+             i32 b = match (n) {{ 1 => 1, 1 => 2, x => 3, -1 => 4 }};\n\
+             i32 c = match (s) {{ Shape.Rect(_, _, 0) => 1, Shape.Rect(_, _, _) => 2, other => 3 }};\n\
+             return a + b + c;\n\
+         }}"
+    );
+    let (_, warnings) = check(&src).expect("checks");
+    let unreachable: Vec<&str> = warnings
+        .iter()
+        .map(|w| {
+            assert_eq!(w.message, "unreachable pattern");
+            &src[w.span.start..w.span.end]
+        })
+        .collect();
+    assert_eq!(unreachable, ["Shape.Empty", "1", "-1"]);
+}
+
+#[test]
+fn sema_checks_patterns_against_the_matched_type() {
+    for (src, message) in [
+        (
+            "fn f(Shape s) -> i32 { return match (s) { Shape.Rect(w, h) => 1, _ => 2 }; }",
+            "variant 'Shape.Rect' has 3 fields but the pattern has 2",
+        ),
+        (
+            "fn f(Shape s) -> i32 { return match (s) { Shape.Oval => 1, _ => 2 }; }",
+            "enum 'Shape' has no variant named 'Oval'",
+        ),
+        (
+            "enum Color { Red }\nfn f(Shape s) -> i32 { return match (s) { Color.Red => 1, _ => 2 }; }",
+            "mismatched types: expected Shape, found Color",
+        ),
+        (
+            "fn f(Shape s) -> i32 { return match (s) { Form.Red => 1, _ => 2 }; }",
+            "cannot find enum 'Form' in this scope",
+        ),
+        (
+            "fn f(Shape s) -> i32 { return match (s) { 1 => 1, _ => 2 }; }",
+            "mismatched types: expected Shape, found integer literal",
+        ),
+        (
+            "fn f(u8 n) -> i32 { return match (n) { -1 => 1, _ => 2 }; }",
+            "cannot apply `-` to u8",
+        ),
+        (
+            "fn f(u8 n) -> i32 { return match (n) { 'a' => 1, _ => 2 }; }",
+            "mismatched types: expected u8, found char",
+        ),
+        (
+            "fn f(Shape s) -> i32 { return match (s) { Shape.Rect(x, x, _) => 1, _ => 2 }; }",
+            "identifier 'x' is bound more than once in the same pattern",
+        ),
+        (
+            "fn f(u8 n) -> i32 { return match (n) { 300 => 1, _ => 2 }; }",
+            "integer literal 300 out of range for u8",
+        ),
+    ] {
+        let err = check(&format!("{SHAPE}{src}")).expect_err(src);
+        assert_eq!(err.message, message, "{src}");
+    }
+}
+
+#[test]
+fn sema_rejects_break_outside_loop() {
+    let err = check("fn f() -> u32 { break; }").expect_err("should fail");
+    assert_eq!(err.message, "`break` outside of a loop");
+    let err =
+        check("fn f() -> u32 { if (1 < 2) { continue; } return 1; }").expect_err("should fail");
+    assert_eq!(err.message, "`continue` outside of a loop");
+}
+
+#[test]
+fn sema_loop_value_errors() {
+    let err =
+        check("fn f(u16 a) -> u8 { u8 x = loop { if (a > 1) { break a; } break 1; }; return x; }")
+            .expect_err("should fail");
+    assert_eq!(err.message, "mismatched types: expected u8, found u16");
+
+    let err = check(
+        "fn f(u16 a, u8 b) -> bool { return (loop { if (b > 1) { break a; } break b; }) == 3; }",
+    )
+    .expect_err("should fail");
+    assert_eq!(err.message, "mismatched types: expected u16, found u8");
+
+    // The default must match the `break` values too.
+    let src = "fn f(u8 n, u16 m) -> u8 { u8 x = while (n > 3) { n--; } break m; return x; }";
+    let err = check(src).expect_err("should fail");
+    assert_eq!(err.message, "mismatched types: expected u8, found u16");
+    let start = src.rfind('m').expect("the default");
+    assert_eq!(
+        err.span,
+        Span {
+            start,
+            end: start + 1
+        }
+    );
+
+    let err =
+        check("fn f() -> u32 { while (1 < 2) { break 3; } return 1; }").expect_err("should fail");
+    assert_eq!(
+        err.message,
+        "`break` with a value is only allowed in a loop used as a value"
+    );
+
+    let err =
+        check("fn f() -> u32 { u32 x = loop { break; }; return x; }").expect_err("should fail");
+    assert_eq!(
+        err.message,
+        "`break` needs a value inside a loop used as a value"
+    );
+}
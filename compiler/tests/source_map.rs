@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use xenonc::lexer::lex_recovering;
+use xenonc::source_map::SourceMap;
+use xenonc::tokens::Span;
+
+fn two_files() -> SourceMap {
+    let mut map = SourceMap::new();
+    map.add_file(
+        PathBuf::from("a.xe"),
+        "fn a()->u32{\n  return 1;\n}\n".into(),
+    );
+    map.add_file(
+        PathBuf::from("tests/main.xe"),
+        "fn main()->u32{\n\treturn @;\n}".into(),
+    );
+    map
+}
+
+#[test]
+fn source_map_lays_files_out_with_a_gap() {
+    let map = two_files();
+    let files = map.files();
+    assert_eq!(files[0].start_pos, 0);
+    assert_eq!(files[1].start_pos, files[0].end_pos() + 1);
+}
+
+#[test]
+fn source_map_resolves_spans_to_their_file() {
+    let map = two_files();
+    let second = map.files()[1].start_pos;
+
+    let loc = map.location(Span { start: 15, end: 21 }).unwrap();
+    assert_eq!(loc.path, Path::new("a.xe"));
+    assert_eq!((loc.line, loc.column), (2, 3));
+
+    let loc = map
+        .location(Span {
+            start: second + 24,
+            end: second + 25,
+        })
+        .unwrap();
+    assert_eq!(loc.to_string(), "tests/main.xe:2:9");
+}
+
+#[test]
+fn source_map_resolves_end_of_file_spans() {
+    let map = two_files();
+    let end = map.files()[0].end_pos();
+    let loc = map.location(Span { start: end, end }).unwrap();
+    assert_eq!((loc.line, loc.column), (4, 1));
+
+    // The next position already belongs to the following file.
+    let next = map.lookup_file(Span {
+        start: end + 1,
+        end: end + 1,
+    });
+    assert_eq!(next.unwrap().path, Path::new("tests/main.xe"));
+}
+
+#[test]
+fn source_map_counts_columns_in_characters() {
+    let mut map = SourceMap::new();
+    map.add_file(PathBuf::from("u.xe"), "é = ü;".into());
+    // `ü` starts at byte 5 but is the fifth character.
+    let loc = map.location(Span { start: 5, end: 7 }).unwrap();
+    assert_eq!(loc.column, 5);
+}
+
+#[test]
+fn source_map_returns_the_source_line() {
+    let mut map = SourceMap::new();
+    map.add_file(PathBuf::from("crlf.xe"), "one\r\ntwo\r\n".into());
+    assert_eq!(map.source_line(Span { start: 6, end: 7 }), Some("two"));
+}
+
+#[test]
+fn source_map_renders_shifted_lex_errors() {
+    let map = two_files();
+    let file = &map.files()[1];
+    let lexed = lex_recovering(&file.content).shifted(file.start_pos);
+    assert_eq!(lexed.errors.len(), 1);
+
+    let err = &lexed.errors[0];
+    let rendered = map.render("error", &err.kind.to_string(), Some(err.span));
+    assert_eq!(
+        rendered,
+        "error: unknown character\n --> tests/main.xe:2:9\n  |\n2 | \treturn @;\n  | \t       ^"
+    );
+}
+
+#[test]
+fn source_map_renders_without_span() {
+    let map = two_files();
+    assert_eq!(map.render("error", "boom", None), "error: boom");
+}